@@ -39,7 +39,7 @@ async fn main() -> anyhow::Result<()> {
             ..Default::default()
         };
 
-        match fetcher.fetch_issues_with_limit(&repo, &filters, 2).await {
+        match fetcher.fetch_issues_with_limit(repo, &filters, 2).await {
             Ok(result) => {
                 println!("   ✓ Found {} issues", result.issues.len());
                 for issue in &result.issues {
@@ -23,6 +23,14 @@ async fn main() -> anyhow::Result<()> {
         result.issues.len(),
         result.filters_applied
     );
+    println!("  applied by GitHub: {:?}", result.server_side_filters);
+    println!(
+        "  scanned {} items on {} pages with {} API calls",
+        result.stats.items_seen, result.stats.pages_scanned, result.stats.api_calls
+    );
+    for (rule, count) in &result.stats.rejections {
+        println!("  rejected {} by {}", count, rule);
+    }
     for issue in &result.issues {
         println!(
             "  #{}: {} (comments: {})",
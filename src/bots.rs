@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::GitHubUser;
+
+/// Decides whether an account is a bot
+///
+/// An account is a bot when GitHub reports its type as `Bot`, its login ends
+/// in `[bot]`, or it is on the deny list. The allow list overrides all three.
+///
+/// ```
+/// use github_fetch::{BotClassifier, GitHubUser};
+///
+/// let user = |login: &str, user_type: &str| GitHubUser {
+///     id: 1,
+///     login: login.to_string(),
+///     avatar_url: String::new(),
+///     user_type: user_type.to_string(),
+/// };
+/// let classifier = BotClassifier::default().deny("my-ci").allow("bors");
+///
+/// assert!(classifier.is_bot(&user("dependabot[bot]", "Bot")));
+/// assert!(classifier.is_bot(&user("my-ci", "User")));
+/// assert!(!classifier.is_bot(&user("bors", "User")));
+/// assert!(!classifier.is_bot(&user("alice", "User")));
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BotClassifier {
+    /// Trust the account type GitHub reports
+    pub use_user_type: bool,
+    /// Treat logins ending in `[bot]` as bots
+    pub use_login_suffix: bool,
+    /// Logins always treated as bots, such as bots running on regular user accounts
+    pub deny: Vec<String>,
+    /// Logins never treated as bots
+    pub allow: Vec<String>,
+}
+
+impl Default for BotClassifier {
+    fn default() -> Self {
+        Self {
+            use_user_type: true,
+            use_login_suffix: true,
+            deny: [
+                "dependabot",
+                "renovate",
+                "github-actions",
+                "codecov",
+                "coveralls",
+                "netlify",
+                "vercel",
+                "mergify",
+                "stale",
+                "bors",
+                "rustbot",
+                "rust-highfive",
+                "rust-timer",
+                "rust-log-analyzer",
+                "rfcbot",
+            ]
+            .iter()
+            .map(|login| login.to_string())
+            .collect(),
+            allow: vec![],
+        }
+    }
+}
+
+impl BotClassifier {
+    /// Classifier that only trusts the deny list
+    pub fn deny_list_only() -> Self {
+        Self {
+            use_user_type: false,
+            use_login_suffix: false,
+            ..Default::default()
+        }
+    }
+
+    pub fn deny(mut self, login: impl Into<String>) -> Self {
+        self.deny.push(login.into());
+        self
+    }
+
+    pub fn allow(mut self, login: impl Into<String>) -> Self {
+        self.allow.push(login.into());
+        self
+    }
+
+    pub fn is_bot(&self, user: &GitHubUser) -> bool {
+        if listed(&self.allow, &user.login) {
+            return false;
+        }
+        (self.use_user_type && user.user_type.eq_ignore_ascii_case("Bot"))
+            || (self.use_login_suffix && user.login.to_lowercase().ends_with("[bot]"))
+            || listed(&self.deny, &user.login)
+    }
+
+    /// Drop the items whose `user` is a bot, returning how many were dropped
+    pub fn retain_humans<T>(&self, items: &mut Vec<T>, user: impl Fn(&T) -> &GitHubUser) -> usize {
+        let before = items.len();
+        items.retain(|item| !self.is_bot(user(item)));
+        before - items.len()
+    }
+}
+
+/// `login` is on `list`, ignoring case and a `[bot]` suffix on either side
+fn listed(list: &[String], login: &str) -> bool {
+    let login = strip_bot_suffix(login);
+    list.iter()
+        .any(|entry| strip_bot_suffix(entry).eq_ignore_ascii_case(login))
+}
+
+fn strip_bot_suffix(login: &str) -> &str {
+    login
+        .get(login.len().saturating_sub(5)..)
+        .filter(|suffix| suffix.eq_ignore_ascii_case("[bot]"))
+        .map_or(login, |_| &login[..login.len() - 5])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn user(login: &str, user_type: &str) -> GitHubUser {
+        GitHubUser {
+            user_type: user_type.to_string(),
+            ..fixtures::user(login)
+        }
+    }
+
+    #[test]
+    fn test_bot_classification() {
+        let deny_only = BotClassifier::deny_list_only();
+        assert!(deny_only.is_bot(&user("renovate[bot]", "")));
+        assert!(deny_only.is_bot(&user("GitHub-Actions", "")));
+        assert!(!deny_only.is_bot(&user("someone[bot]", "Bot")));
+
+        let classifier = BotClassifier::default().allow("rustbot");
+        assert!(classifier.is_bot(&user("someone[bot]", "")));
+        assert!(!classifier.is_bot(&user("rustbot", "Bot")));
+
+        let mut users = vec![user("alice", "User"), user("dependabot[bot]", "Bot")];
+        assert_eq!(classifier.retain_humans(&mut users, |u| u), 1);
+        assert_eq!(users[0].login, "alice");
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+use crate::error::Result;
+use crate::types::{
+    GitHubComment, GitHubIssue, PrFile, PrRefs, PrReview, PrReviewComment, Repository,
+};
+
+/// Fetches of the whole bundle before settling for an inconsistent one
+const MAX_ATTEMPTS: usize = 3;
+
+/// Everything a PR review needs, fetched in one go
+///
+/// Each part is `None` when fetching it failed; the reason is recorded in `errors`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrBundle {
+    pub repository: Repository,
+    pub number: u64,
+    /// Head commit all parts were fetched against
+    pub head_sha: Option<String>,
+    pub base_sha: Option<String>,
+    /// Whether the head stayed at `head_sha` for the whole fetch
+    pub consistent: bool,
+    pub pr: Option<GitHubIssue>,
+    pub files: Option<Vec<PrFile>>,
+    pub reviews: Option<Vec<PrReview>>,
+    pub review_comments: Option<Vec<PrReviewComment>>,
+    pub comments: Option<Vec<GitHubComment>>,
+    pub errors: Vec<BundleError>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BundlePart {
+    Refs,
+    Pr,
+    Files,
+    Reviews,
+    ReviewComments,
+    Comments,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleError {
+    pub part: BundlePart,
+    pub message: String,
+}
+
+/// One round of fetches between two head checks
+pub(crate) struct BundleParts {
+    pub pr: Result<GitHubIssue>,
+    pub files: Result<Vec<PrFile>>,
+    pub reviews: Result<Vec<PrReview>>,
+    pub review_comments: Result<Vec<PrReviewComment>>,
+    pub comments: Result<Vec<GitHubComment>>,
+}
+
+impl PrBundle {
+    pub(crate) fn new(repository: &Repository, number: u64) -> Self {
+        Self {
+            repository: repository.clone(),
+            number,
+            head_sha: None,
+            base_sha: None,
+            consistent: false,
+            pr: None,
+            files: None,
+            reviews: None,
+            review_comments: None,
+            comments: None,
+            errors: Vec::new(),
+            fetched_at: Utc::now(),
+        }
+    }
+
+    /// Fetch the parts between two head checks, fetching again while the head
+    /// moves, up to `MAX_ATTEMPTS` times
+    pub(crate) async fn fetch_consistent<R, RF, P, PF>(
+        repository: &Repository,
+        number: u64,
+        mut fetch_refs: R,
+        mut fetch_parts: P,
+    ) -> Self
+    where
+        R: FnMut() -> RF,
+        RF: Future<Output = Result<PrRefs>>,
+        P: FnMut() -> PF,
+        PF: Future<Output = BundleParts>,
+    {
+        let mut before = fetch_refs().await;
+        let mut attempt = 1;
+
+        loop {
+            let parts = fetch_parts().await;
+            let after = fetch_refs().await;
+
+            let consistent = match (&before, &after) {
+                (Ok(before), Ok(after)) => before.head_sha == after.head_sha,
+                _ => false,
+            };
+            if !consistent && attempt < MAX_ATTEMPTS && after.is_ok() {
+                log::warn!(
+                    "Head of PR #{} moved while fetching bundle, retrying ({}/{})",
+                    number,
+                    attempt,
+                    MAX_ATTEMPTS
+                );
+                before = after;
+                attempt += 1;
+                continue;
+            }
+
+            let mut bundle = Self::new(repository, number);
+            bundle.consistent = consistent;
+            if let Some(refs) = bundle.record(BundlePart::Refs, after) {
+                bundle.head_sha = Some(refs.head_sha);
+                bundle.base_sha = Some(refs.base_sha);
+            }
+            bundle.pr = bundle.record(BundlePart::Pr, parts.pr);
+            bundle.files = bundle.record(BundlePart::Files, parts.files);
+            bundle.reviews = bundle.record(BundlePart::Reviews, parts.reviews);
+            bundle.review_comments =
+                bundle.record(BundlePart::ReviewComments, parts.review_comments);
+            bundle.comments = bundle.record(BundlePart::Comments, parts.comments);
+            return bundle;
+        }
+    }
+
+    /// Store a fetched part, or record why it is missing
+    pub(crate) fn record<T>(&mut self, part: BundlePart, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.errors.push(BundleError {
+                    part,
+                    message: e.to_string(),
+                });
+                None
+            }
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn error_for(&self, part: BundlePart) -> Option<&BundleError> {
+        self.errors.iter().find(|error| error.part == part)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::GitHubFetchError;
+    use std::cell::{Cell, RefCell};
+
+    fn refs(head_sha: &str) -> PrRefs {
+        PrRefs {
+            base_ref: "master".to_string(),
+            base_sha: "base".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: head_sha.to_string(),
+        }
+    }
+
+    fn parts() -> BundleParts {
+        BundleParts {
+            pr: Err(GitHubFetchError::NotFound("PR #7".to_string())),
+            files: Ok(Vec::new()),
+            reviews: Ok(Vec::new()),
+            review_comments: Ok(Vec::new()),
+            comments: Ok(Vec::new()),
+        }
+    }
+
+    /// Bundle fetched against refs returning `heads` in turn, with the number
+    /// of rounds of part fetches it took
+    async fn fetch(heads: &[&str]) -> (PrBundle, usize) {
+        let repo = Repository::new("rust-lang", "rust");
+        let heads = RefCell::new(heads.iter().map(|head| refs(head)));
+        let rounds = Cell::new(0);
+        let bundle = PrBundle::fetch_consistent(
+            &repo,
+            7,
+            || {
+                let next = heads.borrow_mut().next();
+                async move { next.ok_or_else(|| GitHubFetchError::ApiError("offline".to_string())) }
+            },
+            || {
+                rounds.set(rounds.get() + 1);
+                async { parts() }
+            },
+        )
+        .await;
+        (bundle, rounds.get())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_consistent_retries_on_head_move() {
+        let (bundle, rounds) = fetch(&["a", "a"]).await;
+        assert!(bundle.consistent);
+        assert_eq!(rounds, 1);
+        assert_eq!(bundle.head_sha.as_deref(), Some("a"));
+        assert_eq!(bundle.files.as_ref().map(Vec::len), Some(0));
+        assert!(bundle.pr.is_none());
+        assert!(bundle.error_for(BundlePart::Pr).is_some());
+        assert!(!bundle.is_complete());
+
+        // The head moves during the first round and then holds still
+        let (bundle, rounds) = fetch(&["a", "b", "b"]).await;
+        assert!(bundle.consistent);
+        assert_eq!(rounds, 2);
+        assert_eq!(bundle.head_sha.as_deref(), Some("b"));
+
+        // A head that keeps moving is given up on after the last attempt
+        let (bundle, rounds) = fetch(&["a", "b", "c", "d"]).await;
+        assert!(!bundle.consistent);
+        assert_eq!(rounds, MAX_ATTEMPTS);
+        assert_eq!(bundle.head_sha.as_deref(), Some("d"));
+
+        // Without a second head check there is nothing to retry against
+        let (bundle, rounds) = fetch(&["a"]).await;
+        assert!(!bundle.consistent);
+        assert_eq!(rounds, 1);
+        assert!(bundle.head_sha.is_none());
+        assert!(bundle.error_for(BundlePart::Refs).is_some());
+    }
+}
@@ -0,0 +1,368 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use crate::filters::DateRange;
+use crate::search::{SearchQuery, SearchState};
+use crate::types::{GitHubIssue, Repository};
+
+/// How merged PRs are sorted into changelog sections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChangelogGroup {
+    /// One section for PRs carrying any of the labels; a trailing `*` matches a prefix
+    Labels { title: String, labels: Vec<String> },
+    /// One section per distinct label starting with `prefix`, titled by the label
+    Prefix { prefix: String },
+}
+
+impl ChangelogGroup {
+    pub fn labels(title: impl Into<String>, labels: &[&str]) -> Self {
+        Self::Labels {
+            title: title.into(),
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+        }
+    }
+
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        Self::Prefix {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Section title for a PR, if this group takes it
+    fn section_for(&self, pr: &GitHubIssue) -> Option<String> {
+        match self {
+            Self::Labels { title, labels } => pr
+                .labels
+                .iter()
+                .any(|label| {
+                    labels
+                        .iter()
+                        .any(|pattern| label_matches(pattern, &label.name))
+                })
+                .then(|| title.clone()),
+            Self::Prefix { prefix } => pr
+                .labels
+                .iter()
+                .map(|label| &label.name)
+                .filter(|name| name.to_lowercase().starts_with(&prefix.to_lowercase()))
+                .min()
+                .cloned(),
+        }
+    }
+}
+
+fn label_matches(pattern: &str, label: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => label.to_lowercase().starts_with(&prefix.to_lowercase()),
+        None => label.eq_ignore_ascii_case(pattern),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangelogOptions {
+    /// Groups in priority order; each PR lands in the first group that takes it
+    pub groups: Vec<ChangelogGroup>,
+    /// Section for PRs no group takes
+    pub other_title: String,
+    /// PRs with any of these labels are left out entirely
+    pub exclude_labels: Vec<String>,
+    /// Branch the tags were cut from; the repository's default branch when unset
+    #[serde(default)]
+    pub base_branch: Option<String>,
+}
+
+impl Default for ChangelogOptions {
+    fn default() -> Self {
+        Self {
+            groups: vec![
+                ChangelogGroup::labels("Breaking Changes", &["breaking-change", "C-breaking"]),
+                ChangelogGroup::labels("Features", &["C-feature", "C-enhancement", "enhancement"]),
+                ChangelogGroup::labels("Bug Fixes", &["C-bug", "bug"]),
+                ChangelogGroup::labels("Documentation", &["A-docs", "C-docs", "documentation"]),
+            ],
+            other_title: "Other Changes".to_string(),
+            exclude_labels: vec!["skip-changelog".to_string(), "no-changelog".to_string()],
+            base_branch: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangelogEntry {
+    pub number: u64,
+    pub title: String,
+    pub author: String,
+    pub html_url: String,
+    pub merged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangelogSection {
+    pub title: String,
+    pub entries: Vec<ChangelogEntry>,
+}
+
+/// PRs merged between two tags, grouped into sections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Changelog {
+    pub from_tag: Option<String>,
+    pub to_tag: String,
+    pub date: Option<DateTime<Utc>>,
+    pub sections: Vec<ChangelogSection>,
+    /// Authors of the included PRs, sorted
+    pub contributors: Vec<String>,
+    /// Contributors whose first merged PR is part of this changelog, with that PR's number
+    pub first_time_contributors: Vec<(String, u64)>,
+}
+
+impl Changelog {
+    /// Group merged PRs into sections
+    ///
+    /// `first_time_contributors` lists the logins with no merged PR before this
+    /// range. Sections follow the order of `options.groups`; prefix groups are
+    /// sorted by label, and the catch-all section comes last.
+    pub fn build(
+        from_tag: Option<&str>,
+        to_tag: &str,
+        date: Option<DateTime<Utc>>,
+        prs: &[GitHubIssue],
+        first_time_contributors: &[String],
+        options: &ChangelogOptions,
+    ) -> Self {
+        let mut prs: Vec<&GitHubIssue> = prs
+            .iter()
+            .filter(|pr| {
+                !pr.labels.iter().any(|label| {
+                    options
+                        .exclude_labels
+                        .iter()
+                        .any(|pattern| label_matches(pattern, &label.name))
+                })
+            })
+            .collect();
+        prs.sort_by_key(|pr| (pr.merged_at, pr.number));
+
+        // (group index, title) keeps groups in configured order
+        let mut sections: Vec<((usize, String), Vec<ChangelogEntry>)> = Vec::new();
+        for pr in &prs {
+            let key = options
+                .groups
+                .iter()
+                .enumerate()
+                .find_map(|(index, group)| group.section_for(pr).map(|title| (index, title)))
+                .unwrap_or_else(|| (options.groups.len(), options.other_title.clone()));
+
+            let entry = ChangelogEntry {
+                number: pr.number,
+                title: pr.title.trim().to_string(),
+                author: pr.user.login.clone(),
+                html_url: pr.html_url.clone(),
+                merged_at: pr.merged_at,
+            };
+            match sections.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, entries)) => entries.push(entry),
+                None => sections.push((key, vec![entry])),
+            }
+        }
+        sections.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut contributors: Vec<String> = prs.iter().map(|pr| pr.user.login.clone()).collect();
+        contributors.sort_by_key(|login| login.to_lowercase());
+        contributors.dedup();
+
+        let mut first_time: Vec<(String, u64)> = Vec::new();
+        for pr in &prs {
+            let login = &pr.user.login;
+            if first_time_contributors
+                .iter()
+                .any(|first| first.eq_ignore_ascii_case(login))
+                && !first_time.iter().any(|(seen, _)| seen == login)
+            {
+                first_time.push((login.clone(), pr.number));
+            }
+        }
+
+        Self {
+            from_tag: from_tag.map(|tag| tag.to_string()),
+            to_tag: to_tag.to_string(),
+            date,
+            sections: sections
+                .into_iter()
+                .map(|((_, title), entries)| ChangelogSection { title, entries })
+                .collect(),
+            contributors,
+            first_time_contributors: first_time,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+
+        let _ = write!(out, "## {}", self.to_tag);
+        if let Some(date) = self.date {
+            let _ = write!(out, " ({})", date.format("%Y-%m-%d"));
+        }
+        out.push_str("\n\n");
+
+        if self.sections.is_empty() {
+            out.push_str("_No merged pull requests._\n");
+            return out;
+        }
+
+        for section in &self.sections {
+            let _ = writeln!(out, "### {}\n", section.title);
+            for entry in &section.entries {
+                let _ = writeln!(
+                    out,
+                    "- {} ([#{}]({})) by @{}",
+                    entry.title, entry.number, entry.html_url, entry.author
+                );
+            }
+            out.push('\n');
+        }
+
+        if !self.first_time_contributors.is_empty() {
+            out.push_str("### New Contributors\n\n");
+            for (login, number) in &self.first_time_contributors {
+                let _ = writeln!(
+                    out,
+                    "- @{} made their first contribution in #{}",
+                    login, number
+                );
+            }
+            out.push('\n');
+        }
+
+        let _ = writeln!(
+            out,
+            "**Contributors:** {}",
+            self.contributors
+                .iter()
+                .map(|login| format!("@{}", login))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        if let Some(from) = &self.from_tag {
+            let _ = writeln!(out, "\n**Full diff:** `{}...{}`", from, self.to_tag);
+        }
+
+        out
+    }
+}
+
+/// Search for the PRs merged into `branch` between two tag dates, whatever
+/// their labels, body or title
+pub(crate) fn merged_pr_query(
+    repo: &Repository,
+    branch: &str,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> SearchQuery {
+    SearchQuery::new()
+        .repo(repo)
+        .pull_requests()
+        .state(SearchState::Merged)
+        .base(branch)
+        .merged(DateRange { start, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{label, pull_request, user};
+    use chrono::TimeZone;
+
+    fn pr(number: u64, author: &str, labels: &[&str], day: u32) -> GitHubIssue {
+        let date = Utc.with_ymd_and_hms(2024, 6, day, 12, 0, 0).unwrap();
+        GitHubIssue {
+            title: format!("Change {}", number),
+            state: "Closed".to_string(),
+            labels: labels.iter().map(|name| label(name)).collect(),
+            user: user(author),
+            created_at: date,
+            updated_at: date,
+            closed_at: Some(date),
+            merged_at: Some(date),
+            ..pull_request(number)
+        }
+    }
+
+    #[test]
+    fn test_changelog_grouping() {
+        let prs = vec![
+            pr(4, "carol", &["A-parser"], 4),
+            pr(1, "alice", &["C-bug", "A-parser"], 1),
+            pr(2, "bob", &["C-feature"], 2),
+            pr(3, "bob", &["A-docs", "skip-changelog"], 3),
+            pr(5, "alice", &[], 5),
+            pr(6, "carol", &["A-lexer"], 6),
+        ];
+        let options = ChangelogOptions {
+            groups: vec![
+                ChangelogGroup::labels("Bug Fixes", &["C-bug"]),
+                ChangelogGroup::labels("Features", &["C-feature"]),
+                ChangelogGroup::prefix("A-"),
+            ],
+            ..Default::default()
+        };
+        let changelog = Changelog::build(
+            Some("v1.0.0"),
+            "v1.1.0",
+            None,
+            &prs,
+            &["carol".to_string()],
+            &options,
+        );
+
+        let layout: Vec<(&str, Vec<u64>)> = changelog
+            .sections
+            .iter()
+            .map(|s| {
+                (
+                    s.title.as_str(),
+                    s.entries.iter().map(|e| e.number).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                ("Bug Fixes", vec![1]),
+                ("Features", vec![2]),
+                ("A-lexer", vec![6]),
+                ("A-parser", vec![4]),
+                ("Other Changes", vec![5]),
+            ]
+        );
+        assert_eq!(changelog.contributors, vec!["alice", "bob", "carol"]);
+        assert_eq!(
+            changelog.first_time_contributors,
+            vec![("carol".to_string(), 4)]
+        );
+
+        let markdown = changelog.to_markdown();
+        assert!(markdown.starts_with("## v1.1.0\n\n### Bug Fixes\n\n- Change 1 ([#1](https://github.com/o/r/pull/1)) by @alice\n"));
+        assert!(markdown
+            .contains("### New Contributors\n\n- @carol made their first contribution in #4\n"));
+    }
+
+    #[test]
+    fn test_merged_pr_query() {
+        let query = merged_pr_query(
+            &Repository::new("o", "r"),
+            "main",
+            Some(Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap()),
+        );
+        assert_eq!(
+            query.to_string(),
+            "repo:o/r is:pr is:merged base:main merged:2024-06-01T00:00:00Z..2024-07-01T00:00:00Z"
+        );
+    }
+}
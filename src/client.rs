@@ -1,20 +1,59 @@
-use chrono::Utc;
+use base64::Engine;
+use chrono::{DateTime, Utc};
 use log::{debug, info, warn};
 use octocrab::models::issues::Issue;
+use octocrab::models::repos::DiffEntry;
 use octocrab::{Octocrab, Page};
-use tokio::time::{sleep, Duration};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Duration, Instant};
 
-use crate::config::{FetchConfig, GitHubConfig};
+use crate::config::{BotConfig, FetchConfig, GitHubConfig};
 use crate::error::{GitHubFetchError, Result};
-use crate::filters::{IssueFilters, IssueState};
+use crate::filters::{IssueFilters, IssueSort, IssueState, SortDirection, SortField};
+use crate::plan::CollectionPlan;
+use crate::rules::{all_of, IssueFilter, KeywordFilter};
+use crate::search::{SearchQuery, SearchResult, SearchWindows, SEARCH_RESULT_CAP};
+use crate::tree::{RepoTree, TreeEntry, TreeEntryType, TreeWalk};
 use crate::types::{
-    CollectionResult, GitHubComment, GitHubIssue, GitHubLabel, GitHubUser, PrFile, PrReview,
-    PrReviewComment, Repository,
+    CiCheck, CiStatus, CollectionResult, CollectionStats, CommitSummary, Comparison, FileContent,
+    GitHubComment, GitHubIssue, GitHubLabel, GitHubRelease, GitHubTag, GitHubUser, PrFile,
+    PrMergeStatus, PrRefs, PrReview, PrReviewComment, ReleaseAsset, Repository,
 };
 
+/// An issue from the list and search APIs, with the reaction count and merge
+/// time octocrab's `Issue` model drops
+#[derive(Debug, serde::Deserialize)]
+struct IssueItem {
+    #[serde(flatten)]
+    issue: Issue,
+    reactions: Option<ReactionCounts>,
+    /// Present on pull requests; claimed here, so `issue.pull_request` stays `None`
+    pull_request: Option<PullRequestMerge>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PullRequestMerge {
+    merged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ReactionCounts {
+    total_count: u32,
+}
+
 pub struct GitHubClient {
     octocrab: Octocrab,
     rate_limit_delay: Duration,
+    /// Earliest time the next request may start, shared by concurrent callers
+    next_request_at: Mutex<Instant>,
+    search_delay: Duration,
+    /// Earliest time the next search request may start
+    next_search_at: Mutex<Instant>,
+    /// Requests made so far; every request passes through `throttle`
+    api_calls: AtomicU64,
+    bots: BotConfig,
     #[allow(dead_code)]
     config: GitHubConfig,
 }
@@ -46,19 +85,55 @@ impl GitHubClient {
 
         let octocrab = builder.build()?;
         let rate_limit_delay = config.rate_limiting.delay_duration();
+        let search_delay = config.rate_limiting.search_delay_duration();
 
         Ok(Self {
             octocrab,
             rate_limit_delay,
+            next_request_at: Mutex::new(Instant::now()),
+            search_delay,
+            next_search_at: Mutex::new(Instant::now()),
+            api_calls: AtomicU64::new(0),
+            bots: config.bots,
             config: config.github,
         })
     }
 
-    fn convert_state(state: &IssueState) -> Option<octocrab::params::State> {
+    /// Wait for the next request slot so that requests, including concurrent
+    /// ones, are spaced at least `rate_limit_delay` apart
+    async fn throttle(&self) {
+        self.api_calls.fetch_add(1, Ordering::Relaxed);
+        let slot = {
+            let mut next_request_at = self.next_request_at.lock().await;
+            let slot = (*next_request_at).max(Instant::now());
+            *next_request_at = slot + self.rate_limit_delay;
+            slot
+        };
+        sleep_until(slot).await;
+    }
+
+    /// Like `throttle`, but also keeps search requests `search_delay` apart
+    async fn throttle_search(&self) {
+        let slot = {
+            let mut next_search_at = self.next_search_at.lock().await;
+            let slot = (*next_search_at).max(Instant::now());
+            *next_search_at = slot + self.search_delay;
+            slot
+        };
+        sleep_until(slot).await;
+        self.throttle().await;
+    }
+
+    /// Number of API requests this client has made
+    pub fn api_calls(&self) -> u64 {
+        self.api_calls.load(Ordering::Relaxed)
+    }
+
+    fn convert_state(state: &IssueState) -> &'static str {
         match state {
-            IssueState::Open => Some(octocrab::params::State::Open),
-            IssueState::Closed => Some(octocrab::params::State::Closed),
-            IssueState::All => None,
+            IssueState::Open => "open",
+            IssueState::Closed => "closed",
+            IssueState::All => "all",
         }
     }
 
@@ -67,44 +142,147 @@ impl GitHubClient {
         repo: &Repository,
         filters: &IssueFilters,
         max_issues: Option<usize>,
+    ) -> Result<CollectionResult> {
+        self.fetch_issues_with_filter(repo, filters, &all_of(vec![]), max_issues)
+            .await
+    }
+
+    /// Collect issues that pass both `filters` and a custom `filter`
+    ///
+    /// Only `filters` is pushed down to GitHub; `filter` runs locally on what
+    /// comes back, so it can express anything the APIs cannot.
+    pub async fn fetch_issues_with_filter(
+        &self,
+        repo: &Repository,
+        filters: &IssueFilters,
+        filter: &dyn IssueFilter,
+        max_issues: Option<usize>,
+    ) -> Result<CollectionResult> {
+        let accept = filters.compile().and(filter);
+        let comment_rules = filters.comment_rules();
+        let plan = CollectionPlan::for_filters(repo, filters);
+        let (query, server_side) = match plan {
+            CollectionPlan::List { .. } => {
+                return self
+                    .list_issues(repo, filters, plan, &accept, &comment_rules, max_issues)
+                    .await
+            }
+            CollectionPlan::Search { query, server_side } => (query, server_side),
+        };
+
+        info!("Collecting issues from {} via search", repo.full_name);
+
+        let mut result = self
+            .search_matching(&query, filters.sort, &accept, &comment_rules, max_issues)
+            .await?;
+        // GitHub breaks ties its own way; restore ours before the cut
+        result.issues.sort_by(|a, b| filters.sort.compare(a, b));
+        if let Some(max) = max_issues {
+            result.issues.truncate(max);
+        }
+
+        info!(
+            "Collected {} issues from {}",
+            result.issues.len(),
+            repo.full_name
+        );
+
+        Ok(CollectionResult {
+            repository: repo.clone(),
+            total_collected: result.issues.len(),
+            issues: result.issues,
+            collection_time: Utc::now(),
+            filters_applied: filters.query_terms(),
+            server_side_filters: server_side,
+            stats: result.stats,
+        })
+    }
+
+    /// Like `fetch_issues`, but only lists issues updated at or after `since`
+    /// instead of deriving it from the filters' date range
+    pub async fn fetch_issues_since(
+        &self,
+        repo: &Repository,
+        filters: &IssueFilters,
+        since: Option<DateTime<Utc>>,
+        max_issues: Option<usize>,
+    ) -> Result<CollectionResult> {
+        self.list_issues(
+            repo,
+            filters,
+            CollectionPlan::list(filters, since),
+            &filters.compile(),
+            &filters.comment_rules(),
+            max_issues,
+        )
+        .await
+    }
+
+    /// Page through the repository issue list under a `CollectionPlan::List`,
+    /// keeping issues `accept` and `comment_rules` approve
+    async fn list_issues(
+        &self,
+        repo: &Repository,
+        filters: &IssueFilters,
+        plan: CollectionPlan,
+        accept: &dyn IssueFilter,
+        comment_rules: &[KeywordFilter],
+        max_issues: Option<usize>,
     ) -> Result<CollectionResult> {
         info!("Collecting issues from {}", repo.full_name);
 
+        let CollectionPlan::List {
+            since,
+            server_side: server_side_filters,
+        } = plan
+        else {
+            unreachable!("issue lists are only collected under a list plan");
+        };
         let mut all_issues = Vec::new();
         let mut page = 1u32;
         let per_page = 100u8;
         let mut collected_count = 0;
+        let mut stats = CollectionStats::default();
+        let calls_before = self.api_calls();
 
         loop {
             debug!("Fetching page {} for {}", page, repo.full_name);
 
-            let issues_handler = self.octocrab.issues(&repo.owner, &repo.name);
-            let mut list_builder = issues_handler
-                .list()
-                .sort(octocrab::params::issues::Sort::Updated)
-                .direction(octocrab::params::Direction::Descending)
-                .per_page(per_page)
-                .page(page);
-
-            if let Some(state) = Self::convert_state(&filters.state) {
-                list_builder = list_builder.state(state);
+            let (sort, direction) = filters.sort.api_params();
+            let mut params: Vec<(&str, String)> = vec![
+                ("state", Self::convert_state(&filters.state).to_string()),
+                ("sort", sort.to_string()),
+                ("direction", direction.to_string()),
+                ("per_page", per_page.to_string()),
+                ("page", page.to_string()),
+            ];
+
+            // The list API requires all given labels; `include_labels` wants any
+            if let [label] = filters.include_labels.as_slice() {
+                params.push(("labels", label.clone()));
             }
 
-            if !filters.include_labels.is_empty() {
-                list_builder = list_builder.labels(&filters.include_labels);
+            if let Some(since) = since {
+                params.push(("since", since.to_rfc3339()));
             }
-
-            if let Some(date_range) = &filters.date_range {
-                if let Some(since) = date_range.start {
-                    list_builder = list_builder.since(since);
-                }
+            if let Some(author) = &filters.author {
+                params.push(("creator", author.clone()));
+            }
+            if let Some(assignee) = &filters.assignee {
+                params.push(("assignee", assignee.clone()));
             }
 
-            let issues_page: Page<Issue> = list_builder.send().await.map_err(|e| {
-                GitHubFetchError::ApiError(format!("Failed to fetch issues: {}", e))
-            })?;
+            let route = format!("/repos/{}/{}/issues", repo.owner, repo.name);
+            self.throttle().await;
+            let issues_page: Page<IssueItem> = self
+                .octocrab
+                .get(&route, Some(&params))
+                .await
+                .map_err(|e| {
+                    GitHubFetchError::ApiError(format!("Failed to fetch issues: {}", e))
+                })?;
 
-            sleep(self.rate_limit_delay).await;
+            stats.pages_scanned += 1;
 
             if issues_page.items.is_empty() {
                 break;
@@ -112,8 +290,14 @@ impl GitHubClient {
 
             for issue in issues_page.items {
                 let github_issue = self.convert_issue(issue).await?;
-
-                if filters.matches(&github_issue) {
+                stats.items_seen += 1;
+
+                if let Some(rule) = self
+                    .explain_rejection(&github_issue, accept, comment_rules)
+                    .await?
+                {
+                    stats.record_rejection(rule);
+                } else {
                     all_issues.push(github_issue);
                     collected_count += 1;
 
@@ -151,27 +335,299 @@ impl GitHubClient {
             issues: all_issues,
             total_collected: collected_count,
             collection_time: Utc::now(),
-            filters_applied: self.describe_filters(filters),
+            filters_applied: filters.query_terms(),
+            server_side_filters,
+            stats: CollectionStats {
+                api_calls: self.api_calls() - calls_before,
+                ..stats
+            },
         })
     }
 
-    pub async fn fetch_issue(&self, repo: &Repository, issue_number: u64) -> Result<GitHubIssue> {
-        sleep(self.rate_limit_delay).await;
+    /// Search issues and pull requests with the search API
+    ///
+    /// A query matching more than the 1000 results GitHub will return is split
+    /// into `created:` date windows, halved until each fits under the cap.
+    /// Results come back oldest first. The search API has its own, lower rate
+    /// limit (30 requests per minute when authenticated).
+    pub async fn search_issues(
+        &self,
+        query: &SearchQuery,
+        max_results: Option<usize>,
+    ) -> Result<SearchResult> {
+        let oldest_first = IssueSort::new(SortField::Created, SortDirection::Ascending);
+        self.search_matching(query, oldest_first, &all_of(vec![]), &[], max_results)
+            .await
+    }
 
-        let issue = self
-            .octocrab
-            .issues(&repo.owner, &repo.name)
-            .get(issue_number)
+    /// Search in `sort` order, keeping only results `accept` and
+    /// `comment_rules` approve; `max_results` counts accepted ones
+    ///
+    /// Past the result cap the query is split into windows of the sort key,
+    /// walked in sort order, so `max_results` stops the search early whatever
+    /// the sort.
+    async fn search_matching(
+        &self,
+        query: &SearchQuery,
+        sort: IssueSort,
+        accept: &dyn IssueFilter,
+        comment_rules: &[KeywordFilter],
+        max_results: Option<usize>,
+    ) -> Result<SearchResult> {
+        let query_string = query.to_string();
+        info!("Searching issues: {}", query_string);
+
+        let calls_before = self.api_calls();
+        let first = self.search_page(&query_string, sort, 1).await?;
+        let total = first.total_count.unwrap_or(0);
+
+        let mut result = SearchResult {
+            query: query_string,
+            total_count: total,
+            issues: Vec::new(),
+            windows: 1,
+            incomplete: first.incomplete_results.unwrap_or(false),
+            stats: CollectionStats {
+                pages_scanned: 1,
+                ..Default::default()
+            },
+        };
+        let mut seen = HashSet::new();
+
+        if total <= SEARCH_RESULT_CAP {
+            self.collect_search_pages(
+                &result.query.clone(),
+                sort,
+                first,
+                &mut result,
+                &mut seen,
+                accept,
+                comment_rules,
+                max_results,
+            )
+            .await?;
+            result.stats.api_calls = self.api_calls() - calls_before;
+            return Ok(result);
+        }
+
+        // Bounds the query leaves open come from the first and last match in
+        // sort order; dates are bounded above by now
+        let (low, high) = query.window_bounds(sort.field);
+        let low = match low {
+            Some(low) => low,
+            None => self
+                .edge_key(&result.query, sort, SortDirection::Ascending, &first)
+                .await?
+                .unwrap_or_else(|| Utc::now().timestamp()),
+        };
+        let high = match high {
+            Some(high) => high,
+            None if sort.field == SortField::Comments => self
+                .edge_key(&result.query, sort, SortDirection::Descending, &first)
+                .await?
+                .unwrap_or(low),
+            None => Utc::now().timestamp(),
+        };
+
+        result.total_count = 0;
+        result.windows = 0;
+        let mut windows =
+            SearchWindows::new(low, high, sort.direction == SortDirection::Descending);
+
+        while let Some(bounds) = windows.next_window() {
+            if max_results.is_some_and(|max| result.issues.len() >= max) {
+                break;
+            }
+
+            let window_query = query.with_window(sort.field, bounds).to_string();
+            let page = self.search_page(&window_query, sort, 1).await?;
+            result.stats.pages_scanned += 1;
+            let count = page.total_count.unwrap_or(0);
+
+            if count > SEARCH_RESULT_CAP && windows.split(bounds) {
+                debug!("Splitting search window {:?} ({} results)", bounds, count);
+                continue;
+            }
+
+            if count > SEARCH_RESULT_CAP {
+                warn!(
+                    "Search window {:?} still has {} results, keeping the first {}",
+                    bounds, count, SEARCH_RESULT_CAP
+                );
+                result.incomplete = true;
+            }
+
+            result.windows += 1;
+            result.total_count += count;
+            result.incomplete |= page.incomplete_results.unwrap_or(false);
+            self.collect_search_pages(
+                &window_query,
+                sort,
+                page,
+                &mut result,
+                &mut seen,
+                accept,
+                comment_rules,
+                max_results,
+            )
+            .await?;
+        }
+
+        info!(
+            "Search returned {} of {} results in {} windows",
+            result.issues.len(),
+            result.total_count,
+            result.windows
+        );
+
+        result.stats.api_calls = self.api_calls() - calls_before;
+        Ok(result)
+    }
+
+    /// Sort key of the first match when sorting by `sort.field` in `direction`,
+    /// reusing `first` when it was fetched in that order
+    async fn edge_key(
+        &self,
+        query: &str,
+        sort: IssueSort,
+        direction: SortDirection,
+        first: &Page<IssueItem>,
+    ) -> Result<Option<i64>> {
+        let edge = IssueSort::new(sort.field, direction);
+        let key = |page: &Page<IssueItem>| {
+            page.items
+                .first()
+                .map(|item| window_key(sort.field, &item.issue))
+        };
+        if edge == sort {
+            Ok(key(first))
+        } else {
+            Ok(key(&self.search_page(query, edge, 1).await?))
+        }
+    }
+
+    async fn search_page(
+        &self,
+        query: &str,
+        sort: IssueSort,
+        page: u32,
+    ) -> Result<Page<IssueItem>> {
+        self.throttle_search().await;
+
+        let (sort, order) = sort.api_params();
+        let params = [
+            ("q", query.to_string()),
+            ("sort", sort.to_string()),
+            ("order", order.to_string()),
+            ("per_page", "100".to_string()),
+            ("page", page.to_string()),
+        ];
+        self.octocrab
+            .get("/search/issues", Some(&params))
             .await
-            .map_err(|e| {
-                GitHubFetchError::NotFound(format!("Issue #{} not found: {}", issue_number, e))
-            })?;
+            .map_err(|e| GitHubFetchError::ApiError(format!("Search failed: {}", e)))
+    }
+
+    /// Convert `first` and fetch the following pages of one search window
+    ///
+    /// `seen` holds every repository and issue number already filtered, accepted
+    /// or not, so repeats from overlapping windows are neither returned nor
+    /// counted twice.
+    #[allow(clippy::too_many_arguments)]
+    async fn collect_search_pages(
+        &self,
+        query: &str,
+        sort: IssueSort,
+        first: Page<IssueItem>,
+        result: &mut SearchResult,
+        seen: &mut HashSet<(String, u64)>,
+        accept: &dyn IssueFilter,
+        comment_rules: &[KeywordFilter],
+        max_results: Option<usize>,
+    ) -> Result<()> {
+        let total = first.total_count.unwrap_or(0).min(SEARCH_RESULT_CAP) as usize;
+        let mut fetched = 0usize;
+        let mut page_number = 1u32;
+        let mut page = first;
+
+        loop {
+            if page.items.is_empty() {
+                break;
+            }
+            fetched += page.items.len();
+
+            for issue in page.items {
+                if max_results.is_some_and(|max| result.issues.len() >= max) {
+                    return Ok(());
+                }
+                // Adjacent windows can overlap at their boundary; skip repeats
+                let key = (issue.issue.repository_url.to_string(), issue.issue.number);
+                if !seen.insert(key) {
+                    continue;
+                }
+                let issue = self.convert_issue(issue).await?;
+                result.stats.items_seen += 1;
+                match self
+                    .explain_rejection(&issue, accept, comment_rules)
+                    .await?
+                {
+                    Some(rule) => result.stats.record_rejection(rule),
+                    None => result.issues.push(issue),
+                }
+            }
+
+            if fetched >= total {
+                break;
+            }
+            page_number += 1;
+            page = self.search_page(query, sort, page_number).await?;
+            result.stats.pages_scanned += 1;
+        }
+
+        Ok(())
+    }
+
+    /// First rule rejecting `issue`, fetching its comments only when it passes
+    /// `accept` and some keyword rule searches comments
+    async fn explain_rejection(
+        &self,
+        issue: &GitHubIssue,
+        accept: &dyn IssueFilter,
+        comment_rules: &[KeywordFilter],
+    ) -> Result<Option<String>> {
+        if let Some(rule) = accept.explain(issue) {
+            return Ok(Some(rule));
+        }
+        if comment_rules.is_empty() {
+            return Ok(None);
+        }
+
+        let comments = match issue_repository(&issue.html_url) {
+            Some(repo) if issue.comments > 0 => self.fetch_comments(&repo, issue.number).await?,
+            _ => Vec::new(),
+        };
+        Ok(comment_rules
+            .iter()
+            .find(|rule| !rule.matches_with_comments(issue, &comments))
+            .map(|rule| rule.describe()))
+    }
+
+    pub async fn fetch_issue(&self, repo: &Repository, issue_number: u64) -> Result<GitHubIssue> {
+        self.throttle().await;
+
+        let route = format!(
+            "/repos/{}/{}/issues/{}",
+            repo.owner, repo.name, issue_number
+        );
+        let issue: IssueItem = self.octocrab.get(&route, None::<&()>).await.map_err(|e| {
+            GitHubFetchError::NotFound(format!("Issue #{} not found: {}", issue_number, e))
+        })?;
 
         self.convert_issue(issue).await
     }
 
     pub async fn fetch_pr(&self, repo: &Repository, pr_number: u64) -> Result<GitHubIssue> {
-        sleep(self.rate_limit_delay).await;
+        self.throttle().await;
 
         let pr = self
             .octocrab
@@ -210,12 +666,14 @@ impl GitHubClient {
                     id: user.id.0,
                     login: user.login,
                     avatar_url: user.avatar_url.to_string(),
+                    user_type: user.r#type,
                 }
             } else {
                 GitHubUser {
                     id: 0,
                     login: "unknown".to_string(),
                     avatar_url: "".to_string(),
+                    user_type: String::new(),
                 }
             },
             assignees: pr
@@ -226,15 +684,24 @@ impl GitHubClient {
                     id: assignee.id.0,
                     login: assignee.login,
                     avatar_url: assignee.avatar_url.to_string(),
+                    user_type: assignee.r#type,
                 })
                 .collect(),
-            created_at: pr.created_at.unwrap_or_else(|| Utc::now()),
-            updated_at: pr.updated_at.unwrap_or_else(|| Utc::now()),
+            created_at: pr.created_at.unwrap_or_else(Utc::now),
+            updated_at: pr.updated_at.unwrap_or_else(Utc::now),
             closed_at,
             merged_at,
             html_url: pr.html_url.map(|url| url.to_string()).unwrap_or_default(),
             is_pull_request: true,
             comments: pr.comments.unwrap_or(0) as u32,
+            milestone: pr.milestone.map(|milestone| milestone.title),
+            author_association: pr
+                .author_association
+                .and_then(|association| serde_json::to_value(association).ok())
+                .and_then(|value| value.as_str().map(|s| s.to_string()))
+                .unwrap_or_default(),
+            // The pulls API does not report reactions
+            reactions: None,
         })
     }
 
@@ -252,6 +719,8 @@ impl GitHubClient {
         let mut page = 1u32;
 
         loop {
+            self.throttle().await;
+
             let comments_page = self
                 .octocrab
                 .issues(&repo.owner, &repo.name)
@@ -264,8 +733,6 @@ impl GitHubClient {
                     GitHubFetchError::ApiError(format!("Failed to fetch comments: {}", e))
                 })?;
 
-            sleep(self.rate_limit_delay).await;
-
             if comments_page.items.is_empty() {
                 break;
             }
@@ -277,6 +744,7 @@ impl GitHubClient {
                         id: comment.user.id.0,
                         login: comment.user.login,
                         avatar_url: comment.user.avatar_url.to_string(),
+                        user_type: comment.user.r#type,
                     },
                     body: comment.body.unwrap_or_default(),
                     created_at: comment.created_at,
@@ -288,31 +756,57 @@ impl GitHubClient {
             page += 1;
         }
 
+        self.drop_bot_activity(&mut comments, |comment| &comment.user);
         Ok(comments)
     }
 
+    /// Drop bot-authored items when the configuration asks for it
+    fn drop_bot_activity<T>(&self, items: &mut Vec<T>, user: impl Fn(&T) -> &GitHubUser) {
+        if self.bots.exclude_activity {
+            let dropped = self.bots.classifier.retain_humans(items, user);
+            if dropped > 0 {
+                debug!("Dropped {} items by bots", dropped);
+            }
+        }
+    }
+
+    /// Fetch every changed file of a PR, up to the 3000 GitHub lists
     pub async fn fetch_pr_files(&self, repo: &Repository, pr_number: u64) -> Result<Vec<PrFile>> {
-        sleep(self.rate_limit_delay).await;
+        let route = format!(
+            "/repos/{}/{}/pulls/{}/files",
+            repo.owner, repo.name, pr_number
+        );
+        let mut files = Vec::new();
+        let mut page = 1u32;
 
-        let files = self
-            .octocrab
-            .pulls(&repo.owner, &repo.name)
-            .list_files(pr_number)
-            .await
-            .map_err(|e| GitHubFetchError::ApiError(format!("Failed to fetch PR files: {}", e)))?;
+        loop {
+            self.throttle().await;
 
-        Ok(files
-            .items
-            .into_iter()
-            .map(|file| PrFile {
+            let params = [("per_page", "100".to_string()), ("page", page.to_string())];
+            let files_page: Page<DiffEntry> = self
+                .octocrab
+                .get(&route, Some(&params))
+                .await
+                .map_err(|e| {
+                    GitHubFetchError::ApiError(format!("Failed to fetch PR files: {}", e))
+                })?;
+
+            let count = files_page.items.len();
+            files.extend(files_page.items.into_iter().map(|file| PrFile {
                 filename: file.filename,
                 status: format!("{:?}", file.status),
                 additions: file.additions as u32,
                 deletions: file.deletions as u32,
                 changes: file.changes as u32,
                 patch: file.patch,
-            })
-            .collect())
+            }));
+            if count < 100 {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(files)
     }
 
     /// Fetch all reviews for a PR
@@ -320,13 +814,25 @@ impl GitHubClient {
         &self,
         repo: &Repository,
         pr_number: u64,
+    ) -> Result<Vec<PrReview>> {
+        let mut reviews = self.fetch_all_pr_reviews(repo, pr_number).await?;
+        self.drop_bot_activity(&mut reviews, |review| &review.user);
+        Ok(reviews)
+    }
+
+    /// Reviews of a PR including bots' whatever the configuration, for
+    /// decisions a bot's review takes part in
+    pub(crate) async fn fetch_all_pr_reviews(
+        &self,
+        repo: &Repository,
+        pr_number: u64,
     ) -> Result<Vec<PrReview>> {
         debug!(
             "Fetching reviews for PR #{} in {}",
             pr_number, repo.full_name
         );
 
-        sleep(self.rate_limit_delay).await;
+        self.throttle().await;
 
         let reviews = self
             .octocrab
@@ -336,7 +842,7 @@ impl GitHubClient {
             .await
             .map_err(|e| GitHubFetchError::ApiError(format!("Failed to fetch PR reviews: {}", e)))?;
 
-        Ok(reviews
+        let reviews = reviews
             .items
             .into_iter()
             .map(|review| PrReview {
@@ -353,6 +859,11 @@ impl GitHubClient {
                         .as_ref()
                         .map(|u| u.avatar_url.to_string())
                         .unwrap_or_default(),
+                    user_type: review
+                        .user
+                        .as_ref()
+                        .map(|u| u.r#type.clone())
+                        .unwrap_or_default(),
                 },
                 body: review.body,
                 state: review
@@ -363,7 +874,9 @@ impl GitHubClient {
                 html_url: review.html_url.to_string(),
                 commit_id: review.commit_id,
             })
-            .collect())
+            .collect();
+
+        Ok(reviews)
     }
 
     /// Fetch all review comments (inline comments on diff) for a PR
@@ -381,7 +894,7 @@ impl GitHubClient {
         let mut page = 1u32;
 
         loop {
-            sleep(self.rate_limit_delay).await;
+            self.throttle().await;
 
             let url = format!(
                 "/repos/{}/{}/pulls/{}/comments?per_page=100&page={}",
@@ -409,6 +922,7 @@ impl GitHubClient {
             page += 1;
         }
 
+        self.drop_bot_activity(&mut comments, |comment| &comment.user);
         Ok(comments)
     }
 
@@ -424,6 +938,11 @@ impl GitHubClient {
                 id: user.get("id")?.as_u64()?,
                 login: user.get("login")?.as_str()?.to_string(),
                 avatar_url: user.get("avatar_url")?.as_str()?.to_string(),
+                user_type: user
+                    .get("type")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
             },
             body: comment.get("body")?.as_str()?.to_string(),
             path: comment.get("path")?.as_str()?.to_string(),
@@ -432,6 +951,14 @@ impl GitHubClient {
                 .get("original_line")
                 .and_then(|v| v.as_u64())
                 .map(|v| v as u32),
+            start_line: comment
+                .get("start_line")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32),
+            original_start_line: comment
+                .get("original_start_line")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32),
             diff_hunk: comment
                 .get("diff_hunk")
                 .and_then(|v| v.as_str())
@@ -441,10 +968,27 @@ impl GitHubClient {
                 .get("side")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
+            start_side: comment
+                .get("start_side")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            subject_type: comment
+                .get("subject_type")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            author_association: comment
+                .get("author_association")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
             commit_id: comment
                 .get("commit_id")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
+            original_commit_id: comment
+                .get("original_commit_id")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
             created_at: comment
                 .get("created_at")?
                 .as_str()?
@@ -464,6 +1008,768 @@ impl GitHubClient {
         })
     }
 
+    /// Fetch the base and head refs of a PR
+    pub async fn fetch_pr_refs(&self, repo: &Repository, pr_number: u64) -> Result<PrRefs> {
+        self.throttle().await;
+
+        let pr = self
+            .octocrab
+            .pulls(&repo.owner, &repo.name)
+            .get(pr_number)
+            .await
+            .map_err(|e| {
+                GitHubFetchError::NotFound(format!("PR #{} not found: {}", pr_number, e))
+            })?;
+
+        Ok(PrRefs {
+            base_ref: pr.base.ref_field,
+            base_sha: pr.base.sha,
+            head_ref: pr.head.ref_field,
+            head_sha: pr.head.sha,
+        })
+    }
+
+    /// Fetch mergeability, draft state and requested reviewers of a PR
+    pub async fn fetch_pr_merge_status(
+        &self,
+        repo: &Repository,
+        pr_number: u64,
+    ) -> Result<PrMergeStatus> {
+        self.throttle().await;
+
+        let pr = self
+            .octocrab
+            .pulls(&repo.owner, &repo.name)
+            .get(pr_number)
+            .await
+            .map_err(|e| {
+                GitHubFetchError::NotFound(format!("PR #{} not found: {}", pr_number, e))
+            })?;
+
+        Ok(PrMergeStatus {
+            state: pr
+                .state
+                .map(|s| format!("{:?}", s))
+                .unwrap_or_else(|| "open".to_string()),
+            draft: pr.draft.unwrap_or(false),
+            merged: pr.merged.unwrap_or(false) || pr.merged_at.is_some(),
+            mergeable: pr.mergeable,
+            mergeable_state: pr
+                .mergeable_state
+                .and_then(|state| serde_json::to_value(state).ok())
+                .and_then(|value| value.as_str().map(|s| s.to_string()))
+                .unwrap_or_else(|| "unknown".to_string()),
+            head_sha: pr.head.sha,
+            requested_reviewers: pr
+                .requested_reviewers
+                .unwrap_or_default()
+                .into_iter()
+                .map(|user| user.login)
+                .collect(),
+            requested_teams: pr
+                .requested_teams
+                .unwrap_or_default()
+                .into_iter()
+                .map(|team| team.slug)
+                .collect(),
+        })
+    }
+
+    /// Fetch commit statuses and check runs for a commit and combine them
+    pub async fn fetch_ci_status(&self, repo: &Repository, sha: &str) -> Result<CiStatus> {
+        debug!("Fetching CI status for {} in {}", sha, repo.full_name);
+
+        self.throttle().await;
+
+        let url = format!(
+            "/repos/{}/{}/commits/{}/status?per_page=100",
+            repo.owner, repo.name, sha
+        );
+        let combined: serde_json::Value =
+            self.octocrab.get(&url, None::<&()>).await.map_err(|e| {
+                GitHubFetchError::ApiError(format!("Failed to fetch commit status: {}", e))
+            })?;
+
+        let mut checks: Vec<CiCheck> = combined
+            .get("statuses")
+            .and_then(|v| v.as_array())
+            .map(|statuses| {
+                statuses
+                    .iter()
+                    .filter_map(|status| {
+                        Some(CiCheck {
+                            name: status.get("context")?.as_str()?.to_string(),
+                            state: match status.get("state")?.as_str()? {
+                                "success" => "success",
+                                "pending" => "pending",
+                                _ => "failure",
+                            }
+                            .to_string(),
+                            url: status
+                                .get("target_url")
+                                .and_then(|v| v.as_str())
+                                .map(|s| s.to_string()),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut page = 1u32;
+        loop {
+            self.throttle().await;
+
+            let url = format!(
+                "/repos/{}/{}/commits/{}/check-runs?per_page=100&page={}",
+                repo.owner, repo.name, sha, page
+            );
+            let response: serde_json::Value =
+                self.octocrab.get(&url, None::<&()>).await.map_err(|e| {
+                    GitHubFetchError::ApiError(format!("Failed to fetch check runs: {}", e))
+                })?;
+
+            let runs = response
+                .get("check_runs")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default();
+            if runs.is_empty() {
+                break;
+            }
+
+            checks.extend(runs.iter().filter_map(|run| {
+                let state = if run.get("status").and_then(|v| v.as_str()) != Some("completed") {
+                    "pending"
+                } else {
+                    match run.get("conclusion").and_then(|v| v.as_str()) {
+                        Some("success") | Some("neutral") | Some("skipped") => "success",
+                        _ => "failure",
+                    }
+                };
+
+                Some(CiCheck {
+                    name: run.get("name")?.as_str()?.to_string(),
+                    state: state.to_string(),
+                    url: run
+                        .get("html_url")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string()),
+                })
+            }));
+
+            if runs.len() < 100 {
+                break;
+            }
+            page += 1;
+        }
+
+        let state = if checks.is_empty() {
+            "none"
+        } else if checks.iter().any(|check| check.state == "failure") {
+            "failure"
+        } else if checks.iter().any(|check| check.state == "pending") {
+            "pending"
+        } else {
+            "success"
+        };
+
+        Ok(CiStatus {
+            sha: sha.to_string(),
+            state: state.to_string(),
+            checks,
+        })
+    }
+
+    /// Compare two commits, branches or tags (`base...head`)
+    ///
+    /// Commits are paginated until all `total_commits` are collected. GitHub
+    /// only returns files on the first page, capped at 300 for the comparison.
+    pub async fn compare(&self, repo: &Repository, base: &str, head: &str) -> Result<Comparison> {
+        debug!("Comparing {}...{} in {}", base, head, repo.full_name);
+
+        let mut comparison: Option<Comparison> = None;
+        let mut page = 1u32;
+
+        loop {
+            self.throttle().await;
+
+            let url = format!(
+                "/repos/{}/{}/compare/{}...{}?per_page=100&page={}",
+                repo.owner,
+                repo.name,
+                encode_component(base),
+                encode_component(head),
+                page
+            );
+            let response: serde_json::Value =
+                self.octocrab
+                    .get(&url, None::<&()>)
+                    .await
+                    .map_err(|e| match &e {
+                        octocrab::Error::GitHub { source, .. }
+                            if source.status_code.as_u16() == 404 =>
+                        {
+                            GitHubFetchError::NotFound(format!(
+                                "Cannot compare {}...{}: {}",
+                                base, head, e
+                            ))
+                        }
+                        _ => {
+                            GitHubFetchError::ApiError(format!("Failed to compare commits: {}", e))
+                        }
+                    })?;
+
+            let parsed = Self::parse_comparison(&response, base, head);
+            let done = parsed.commits.is_empty();
+            let comparison = match comparison.as_mut() {
+                Some(comparison) => {
+                    comparison.commits.extend(parsed.commits);
+                    comparison
+                }
+                None => comparison.insert(parsed),
+            };
+
+            if done || comparison.commits.len() >= comparison.total_commits as usize {
+                break;
+            }
+
+            page += 1;
+        }
+
+        Ok(comparison.expect("at least one page was fetched"))
+    }
+
+    fn parse_comparison(response: &serde_json::Value, base: &str, head: &str) -> Comparison {
+        let count = |key: &str| response.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+
+        Comparison {
+            base: base.to_string(),
+            head: head.to_string(),
+            status: response
+                .get("status")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown")
+                .to_string(),
+            ahead_by: count("ahead_by"),
+            behind_by: count("behind_by"),
+            total_commits: count("total_commits"),
+            merge_base_sha: response
+                .get("merge_base_commit")
+                .and_then(|c| c.get("sha"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            commits: response
+                .get("commits")
+                .and_then(|v| v.as_array())
+                .map(|commits| commits.iter().filter_map(Self::parse_commit).collect())
+                .unwrap_or_default(),
+            files: response
+                .get("files")
+                .and_then(|v| v.as_array())
+                .map(|files| files.iter().filter_map(Self::parse_file).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn parse_commit(commit: &serde_json::Value) -> Option<CommitSummary> {
+        let details = commit.get("commit")?;
+
+        Some(CommitSummary {
+            sha: commit.get("sha")?.as_str()?.to_string(),
+            message: details
+                .get("message")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            author: commit
+                .get("author")
+                .and_then(|a| a.get("login"))
+                .or_else(|| details.get("author").and_then(|a| a.get("name")))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            date: details
+                .get("author")
+                .and_then(|a| a.get("date"))
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse().ok()),
+            committed_at: details
+                .get("committer")
+                .and_then(|c| c.get("date"))
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse().ok()),
+            html_url: commit
+                .get("html_url")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            parents: commit
+                .get("parents")
+                .and_then(|v| v.as_array())
+                .map(|parents| {
+                    parents
+                        .iter()
+                        .filter_map(|p| p.get("sha").and_then(|v| v.as_str()))
+                        .map(|s| s.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    fn parse_file(file: &serde_json::Value) -> Option<PrFile> {
+        let count = |key: &str| file.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+
+        Some(PrFile {
+            filename: file.get("filename")?.as_str()?.to_string(),
+            // Match the `{:?}` casing of octocrab's DiffEntryStatus used by fetch_pr_files
+            status: file
+                .get("status")
+                .and_then(|v| v.as_str())
+                .map(|status| {
+                    let mut chars = status.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                })
+                .unwrap_or_default(),
+            additions: count("additions"),
+            deletions: count("deletions"),
+            changes: count("changes"),
+            patch: file
+                .get("patch")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+        })
+    }
+
+    /// Fetch a file's contents at a branch, tag or commit
+    ///
+    /// Uses the contents API, falling back to the git blobs API for files over
+    /// 1 MB, whose contents the contents API omits.
+    pub async fn fetch_file_content(
+        &self,
+        repo: &Repository,
+        path: &str,
+        git_ref: &str,
+    ) -> Result<FileContent> {
+        debug!("Fetching {} at {} in {}", path, git_ref, repo.full_name);
+
+        self.throttle().await;
+
+        let url = format!(
+            "/repos/{}/{}/contents/{}?ref={}",
+            repo.owner,
+            repo.name,
+            encode_path(path),
+            encode_component(git_ref)
+        );
+        let response: serde_json::Value =
+            self.octocrab
+                .get(&url, None::<&()>)
+                .await
+                .map_err(|e| match &e {
+                    octocrab::Error::GitHub { source, .. }
+                        if source.status_code.as_u16() == 404 =>
+                    {
+                        GitHubFetchError::NotFound(format!(
+                            "{} not found at {}: {}",
+                            path, git_ref, e
+                        ))
+                    }
+                    _ => {
+                        GitHubFetchError::ApiError(format!("Failed to fetch file contents: {}", e))
+                    }
+                })?;
+
+        if response.is_array() || response.get("type").and_then(|v| v.as_str()) != Some("file") {
+            return Err(GitHubFetchError::NotFound(format!(
+                "{} at {} is not a file",
+                path, git_ref
+            )));
+        }
+
+        let sha = response
+            .get("sha")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let size = response.get("size").and_then(|v| v.as_u64()).unwrap_or(0);
+        let encoding = response.get("encoding").and_then(|v| v.as_str());
+        let content = response
+            .get("content")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+
+        let bytes = if encoding == Some("base64") && (!content.is_empty() || size == 0) {
+            decode_base64(content)?
+        } else {
+            self.fetch_blob(repo, &sha).await?
+        };
+
+        Ok(FileContent {
+            path: path.to_string(),
+            git_ref: git_ref.to_string(),
+            sha,
+            size,
+            bytes,
+        })
+    }
+
+    /// Fetch raw blob bytes by SHA with the git blobs API
+    pub async fn fetch_blob(&self, repo: &Repository, sha: &str) -> Result<Vec<u8>> {
+        self.throttle().await;
+
+        let url = format!("/repos/{}/{}/git/blobs/{}", repo.owner, repo.name, sha);
+        let response: serde_json::Value =
+            self.octocrab.get(&url, None::<&()>).await.map_err(|e| {
+                GitHubFetchError::ApiError(format!("Failed to fetch blob {}: {}", sha, e))
+            })?;
+
+        let content = response
+            .get("content")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        match response.get("encoding").and_then(|v| v.as_str()) {
+            Some("base64") => decode_base64(content),
+            _ => Ok(content.as_bytes().to_vec()),
+        }
+    }
+
+    /// List every entry of the repository tree at a branch, tag or commit
+    ///
+    /// Uses the recursive git trees API. When GitHub truncates the listing
+    /// (over 100,000 entries or 7 MB), subtrees are walked one by one instead.
+    pub async fn fetch_tree(&self, repo: &Repository, git_ref: &str) -> Result<RepoTree> {
+        debug!("Fetching tree at {} in {}", git_ref, repo.full_name);
+
+        let (sha, mut entries, truncated) = self.fetch_tree_level(repo, git_ref, true).await?;
+        let mut incomplete = false;
+
+        if truncated {
+            warn!(
+                "Tree of {} at {} is truncated, walking subtrees",
+                repo.full_name, git_ref
+            );
+
+            let mut walk = TreeWalk::new(&sha);
+            while let Some((tree_sha, recursive)) = walk.next_listing() {
+                let (_, listing, truncated) =
+                    self.fetch_tree_level(repo, &tree_sha, recursive).await?;
+                walk.add_listing(listing, truncated);
+            }
+            (entries, incomplete) = walk.finish();
+        }
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(RepoTree {
+            repository: repo.clone(),
+            git_ref: git_ref.to_string(),
+            sha,
+            entries,
+            truncated: incomplete,
+        })
+    }
+
+    /// One git trees API call: the tree's SHA, its entries and the truncation flag
+    async fn fetch_tree_level(
+        &self,
+        repo: &Repository,
+        tree: &str,
+        recursive: bool,
+    ) -> Result<(String, Vec<TreeEntry>, bool)> {
+        self.throttle().await;
+
+        let url = format!(
+            "/repos/{}/{}/git/trees/{}{}",
+            repo.owner,
+            repo.name,
+            encode_path(tree),
+            if recursive { "?recursive=1" } else { "" }
+        );
+        let response: serde_json::Value =
+            self.octocrab
+                .get(&url, None::<&()>)
+                .await
+                .map_err(|e| match &e {
+                    octocrab::Error::GitHub { source, .. }
+                        if source.status_code.as_u16() == 404 =>
+                    {
+                        GitHubFetchError::NotFound(format!("Tree {} not found: {}", tree, e))
+                    }
+                    _ => {
+                        GitHubFetchError::ApiError(format!("Failed to fetch tree {}: {}", tree, e))
+                    }
+                })?;
+
+        let sha = response
+            .get("sha")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let truncated = response
+            .get("truncated")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let entries = response
+            .get("tree")
+            .and_then(|v| v.as_array())
+            .map(|entries| entries.iter().filter_map(Self::parse_tree_entry).collect())
+            .unwrap_or_default();
+
+        Ok((sha, entries, truncated))
+    }
+
+    fn parse_tree_entry(entry: &serde_json::Value) -> Option<TreeEntry> {
+        let field = |key: &str| entry.get(key).and_then(|v| v.as_str());
+
+        Some(TreeEntry {
+            path: field("path")?.to_string(),
+            mode: field("mode").unwrap_or("").to_string(),
+            entry_type: TreeEntryType::parse(field("type")?)?,
+            size: entry.get("size").and_then(|v| v.as_u64()),
+            sha: field("sha").unwrap_or("").to_string(),
+        })
+    }
+
+    /// Fetch a single commit by SHA, branch or tag
+    pub async fn fetch_commit(&self, repo: &Repository, git_ref: &str) -> Result<CommitSummary> {
+        self.throttle().await;
+
+        let url = format!(
+            "/repos/{}/{}/commits/{}",
+            repo.owner,
+            repo.name,
+            encode_component(git_ref)
+        );
+        let response: serde_json::Value =
+            self.octocrab
+                .get(&url, None::<&()>)
+                .await
+                .map_err(|e| match &e {
+                    octocrab::Error::GitHub { source, .. }
+                        if source.status_code.as_u16() == 404 =>
+                    {
+                        GitHubFetchError::NotFound(format!("Commit {} not found: {}", git_ref, e))
+                    }
+                    _ => GitHubFetchError::ApiError(format!("Failed to fetch commit: {}", e)),
+                })?;
+
+        Self::parse_commit(&response).ok_or_else(|| {
+            GitHubFetchError::ApiError(format!("Unexpected commit response for {}", git_ref))
+        })
+    }
+
+    /// Fetch all releases, newest first, including drafts visible to the token
+    pub async fn fetch_releases(&self, repo: &Repository) -> Result<Vec<GitHubRelease>> {
+        debug!("Fetching releases for {}", repo.full_name);
+
+        let mut releases = Vec::new();
+        let mut page = 1u32;
+
+        loop {
+            self.throttle().await;
+
+            let url = format!(
+                "/repos/{}/{}/releases?per_page=100&page={}",
+                repo.owner, repo.name, page
+            );
+            let response: Vec<serde_json::Value> =
+                self.octocrab.get(&url, None::<&()>).await.map_err(|e| {
+                    GitHubFetchError::ApiError(format!("Failed to fetch releases: {}", e))
+                })?;
+
+            if response.is_empty() {
+                break;
+            }
+
+            let count = response.len();
+            releases.extend(response.iter().filter_map(Self::parse_release));
+
+            if count < 100 {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(releases)
+    }
+
+    pub async fn fetch_release_by_tag(
+        &self,
+        repo: &Repository,
+        tag: &str,
+    ) -> Result<GitHubRelease> {
+        self.throttle().await;
+
+        let url = format!(
+            "/repos/{}/{}/releases/tags/{}",
+            repo.owner,
+            repo.name,
+            encode_component(tag)
+        );
+        let response: serde_json::Value =
+            self.octocrab.get(&url, None::<&()>).await.map_err(|e| {
+                GitHubFetchError::NotFound(format!("Release {} not found: {}", tag, e))
+            })?;
+
+        Self::parse_release(&response).ok_or_else(|| {
+            GitHubFetchError::ApiError(format!("Unexpected release response for {}", tag))
+        })
+    }
+
+    /// Fetch all tags with the commit each points to
+    pub async fn fetch_tags(&self, repo: &Repository) -> Result<Vec<GitHubTag>> {
+        debug!("Fetching tags for {}", repo.full_name);
+
+        let mut tags = Vec::new();
+        let mut page = 1u32;
+
+        loop {
+            self.throttle().await;
+
+            let url = format!(
+                "/repos/{}/{}/tags?per_page=100&page={}",
+                repo.owner, repo.name, page
+            );
+            let response: Vec<serde_json::Value> =
+                self.octocrab.get(&url, None::<&()>).await.map_err(|e| {
+                    GitHubFetchError::ApiError(format!("Failed to fetch tags: {}", e))
+                })?;
+
+            if response.is_empty() {
+                break;
+            }
+
+            let count = response.len();
+            tags.extend(response.iter().filter_map(|tag| {
+                Some(GitHubTag {
+                    name: tag.get("name")?.as_str()?.to_string(),
+                    commit_sha: tag.get("commit")?.get("sha")?.as_str()?.to_string(),
+                })
+            }));
+
+            if count < 100 {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(tags)
+    }
+
+    fn parse_release(release: &serde_json::Value) -> Option<GitHubRelease> {
+        let text = |key: &str| release.get(key).and_then(|v| v.as_str());
+        let date = |key: &str| text(key).and_then(|s| s.parse().ok());
+
+        Some(GitHubRelease {
+            id: release.get("id")?.as_u64()?,
+            tag_name: text("tag_name")?.to_string(),
+            name: text("name")
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string()),
+            body: text("body").map(|s| s.to_string()),
+            draft: release
+                .get("draft")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            prerelease: release
+                .get("prerelease")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            target_commitish: text("target_commitish").unwrap_or("").to_string(),
+            author: release.get("author").and_then(|user| {
+                Some(GitHubUser {
+                    id: user.get("id")?.as_u64()?,
+                    login: user.get("login")?.as_str()?.to_string(),
+                    avatar_url: user
+                        .get("avatar_url")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string(),
+                    user_type: user
+                        .get("type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string(),
+                })
+            }),
+            created_at: date("created_at")?,
+            published_at: date("published_at"),
+            html_url: text("html_url").unwrap_or("").to_string(),
+            assets: release
+                .get("assets")
+                .and_then(|v| v.as_array())
+                .map(|assets| {
+                    assets
+                        .iter()
+                        .filter_map(|asset| {
+                            let text = |key: &str| asset.get(key).and_then(|v| v.as_str());
+                            Some(ReleaseAsset {
+                                id: asset.get("id")?.as_u64()?,
+                                name: text("name")?.to_string(),
+                                content_type: text("content_type").unwrap_or("").to_string(),
+                                size: asset.get("size").and_then(|v| v.as_u64()).unwrap_or(0),
+                                download_count: asset
+                                    .get("download_count")
+                                    .and_then(|v| v.as_u64())
+                                    .unwrap_or(0),
+                                browser_download_url: text("browser_download_url")
+                                    .unwrap_or("")
+                                    .to_string(),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Name of the repository's default branch
+    pub async fn fetch_default_branch(&self, repo: &Repository) -> Result<String> {
+        self.throttle().await;
+
+        let repository = self
+            .octocrab
+            .repos(&repo.owner, &repo.name)
+            .get()
+            .await
+            .map_err(|e| {
+                GitHubFetchError::ApiError(format!("Failed to fetch repository: {}", e))
+            })?;
+        repository.default_branch.ok_or_else(|| {
+            GitHubFetchError::NotFound(format!("Default branch of {}", repo.full_name))
+        })
+    }
+
+    /// Number of PRs by `login` merged into the repository before `before`
+    pub async fn count_merged_prs_before(
+        &self,
+        repo: &Repository,
+        login: &str,
+        before: DateTime<Utc>,
+    ) -> Result<u64> {
+        self.throttle_search().await;
+
+        let query = format!(
+            "repo:{} is:pr is:merged author:{} merged:<{}",
+            repo.full_name,
+            login,
+            before.format("%Y-%m-%dT%H:%M:%SZ")
+        );
+        let url = format!("/search/issues?q={}&per_page=1", encode_component(&query));
+        let response: serde_json::Value =
+            self.octocrab.get(&url, None::<&()>).await.map_err(|e| {
+                GitHubFetchError::ApiError(format!("Failed to search merged PRs: {}", e))
+            })?;
+
+        Ok(response
+            .get("total_count")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0))
+    }
+
     pub async fn test_connection(&self) -> Result<()> {
         debug!("Testing GitHub API connection");
 
@@ -491,14 +1797,14 @@ impl GitHubClient {
         ))
     }
 
-    async fn convert_issue(&self, issue: Issue) -> Result<GitHubIssue> {
-        let is_pull_request = issue.pull_request.is_some();
-
-        let merged_at = if is_pull_request {
-            self.get_pr_merged_at(&issue).await?
-        } else {
-            None
-        };
+    async fn convert_issue(&self, item: IssueItem) -> Result<GitHubIssue> {
+        let IssueItem {
+            issue,
+            reactions,
+            pull_request,
+        } = item;
+        let is_pull_request = pull_request.is_some();
+        let merged_at = pull_request.and_then(|pr| pr.merged_at);
 
         Ok(GitHubIssue {
             id: issue.id.0,
@@ -520,6 +1826,7 @@ impl GitHubClient {
                 id: issue.user.id.0,
                 login: issue.user.login,
                 avatar_url: issue.user.avatar_url.to_string(),
+                user_type: issue.user.r#type,
             },
             assignees: issue
                 .assignees
@@ -528,6 +1835,7 @@ impl GitHubClient {
                     id: assignee.id.0,
                     login: assignee.login,
                     avatar_url: assignee.avatar_url.to_string(),
+                    user_type: assignee.r#type,
                 })
                 .collect(),
             created_at: issue.created_at,
@@ -537,64 +1845,160 @@ impl GitHubClient {
             html_url: issue.html_url.to_string(),
             is_pull_request,
             comments: issue.comments,
+            milestone: issue.milestone.map(|milestone| milestone.title),
+            author_association: issue.author_association,
+            reactions: reactions.map(|reactions| reactions.total_count),
         })
     }
+}
+
+/// Sort key of an issue in the units search windows are split in
+fn window_key(field: SortField, issue: &Issue) -> i64 {
+    match field {
+        SortField::Created => issue.created_at.timestamp(),
+        SortField::Updated => issue.updated_at.timestamp(),
+        SortField::Comments => i64::from(issue.comments),
+    }
+}
 
-    async fn get_pr_merged_at(
-        &self,
-        issue: &Issue,
-    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
-        if let Some(ref _pr_url) = issue.pull_request {
-            let repo_url_str = issue.repository_url.to_string();
-            let parts: Vec<&str> = repo_url_str.trim_end_matches('/').split('/').collect();
-
-            if parts.len() >= 2 {
-                let owner = parts[parts.len() - 2];
-                let repo = parts[parts.len() - 1];
-
-                match self.octocrab.pulls(owner, repo).get(issue.number).await {
-                    Ok(pr) => {
-                        sleep(self.rate_limit_delay).await;
-                        Ok(pr.merged_at)
-                    }
-                    Err(e) => {
-                        warn!("Failed to fetch PR #{} merged_at: {}", issue.number, e);
-                        Ok(None)
-                    }
-                }
-            } else {
-                Ok(None)
+/// Repository of an issue or pull request from its `html_url`
+fn issue_repository(html_url: &str) -> Option<Repository> {
+    let path = html_url.split("github.com/").nth(1)?;
+    let mut segments = path.split('/');
+    let owner = segments.next().filter(|s| !s.is_empty())?;
+    let name = segments.next().filter(|s| !s.is_empty())?;
+    Some(Repository::new(owner, name))
+}
+
+/// Decode base64 content as returned by GitHub, which wraps lines at 60 characters
+fn decode_base64(content: &str) -> Result<Vec<u8>> {
+    let cleaned: String = content.chars().filter(|c| !c.is_whitespace()).collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(cleaned)
+        .map_err(|e| GitHubFetchError::ApiError(format!("Invalid base64 content: {}", e)))
+}
+
+/// Percent-encode a URL path or query component
+fn encode_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
             }
-        } else {
-            Ok(None)
-        }
-    }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
 
-    fn describe_filters(&self, filters: &IssueFilters) -> Vec<String> {
-        let mut descriptions = Vec::new();
+/// Percent-encode each segment of a repository path, keeping the slashes
+fn encode_path(path: &str) -> String {
+    path.trim_start_matches('/')
+        .split('/')
+        .map(encode_component)
+        .collect::<Vec<_>>()
+        .join("/")
+}
 
-        if !filters.include_labels.is_empty() {
-            descriptions.push(format!("include_labels: {:?}", filters.include_labels));
-        }
-        if !filters.exclude_labels.is_empty() {
-            descriptions.push(format!("exclude_labels: {:?}", filters.exclude_labels));
-        }
-        if filters.rust_errors_only {
-            descriptions.push("rust_errors_only: true".to_string());
-        }
-        if filters.code_blocks_only {
-            descriptions.push("code_blocks_only: true".to_string());
-        }
-        if let Some(min_length) = filters.min_body_length {
-            descriptions.push(format!("min_body_length: {}", min_length));
-        }
-        if !filters.include_pull_requests {
-            descriptions.push("exclude_pull_requests: true".to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_and_encode() {
+        assert_eq!(decode_base64("aGVs\nbG8=\n").unwrap(), b"hello");
+        assert_eq!(encode_path("docs/my file.md"), "docs/my%20file.md");
+        assert_eq!(encode_component("feature/x"), "feature%2Fx");
+        assert_eq!(
+            issue_repository("https://github.com/rust-lang/rust/issues/1")
+                .map(|repo| repo.full_name),
+            Some("rust-lang/rust".to_string())
+        );
+        assert!(issue_repository("https://example.com/issues/1").is_none());
+    }
+
+    #[test]
+    fn test_issue_item_keeps_reactions() {
+        let url = "https://api.github.com/x";
+        let mut user = serde_json::json!({
+            "login": "alice", "id": 1, "node_id": "U1", "gravatar_id": "",
+            "type": "User", "site_admin": false
+        });
+        for key in [
+            "avatar_url",
+            "url",
+            "html_url",
+            "followers_url",
+            "following_url",
+            "gists_url",
+            "starred_url",
+            "subscriptions_url",
+            "organizations_url",
+            "repos_url",
+            "events_url",
+            "received_events_url",
+        ] {
+            user[key] = url.into();
         }
-        if let Some(min_comments) = filters.min_comments {
-            descriptions.push(format!("min_comments: {}", min_comments));
+        let mut issue = serde_json::json!({
+            "id": 7, "node_id": "I7", "number": 7, "state": "open", "title": "ICE",
+            "user": user, "labels": [], "assignees": [], "author_association": "MEMBER",
+            "milestone": null, "locked": false, "comments": 2,
+            "created_at": "2024-01-01T00:00:00Z", "updated_at": "2024-01-02T00:00:00Z",
+            "reactions": { "total_count": 5, "+1": 4, "heart": 1 }
+        });
+        for key in [
+            "url",
+            "repository_url",
+            "labels_url",
+            "comments_url",
+            "events_url",
+            "html_url",
+        ] {
+            issue[key] = url.into();
         }
 
-        descriptions
+        let item: IssueItem = serde_json::from_value(issue.clone()).unwrap();
+        assert_eq!(item.issue.number, 7);
+        assert_eq!(item.issue.author_association, "MEMBER");
+        assert_eq!(item.reactions.map(|r| r.total_count), Some(5));
+        assert!(item.pull_request.is_none());
+
+        issue["pull_request"] = serde_json::json!({
+            "url": url, "html_url": url, "diff_url": url, "patch_url": url,
+            "merged_at": "2024-01-03T00:00:00Z"
+        });
+        let item: IssueItem = serde_json::from_value(issue).unwrap();
+        assert_eq!(
+            item.pull_request.and_then(|pr| pr.merged_at),
+            Some("2024-01-03T00:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_comparison() {
+        let response = serde_json::json!({
+            "status": "diverged",
+            "ahead_by": 2,
+            "behind_by": 1,
+            "total_commits": 2,
+            "merge_base_commit": { "sha": "base123" },
+            "commits": [],
+            "files": [{
+                "filename": "src/lib.rs",
+                "status": "modified",
+                "additions": 3,
+                "deletions": 1,
+                "changes": 4,
+                "patch": "@@ -1 +1 @@"
+            }]
+        });
+
+        let comparison = GitHubClient::parse_comparison(&response, "v1.0", "main");
+        assert_eq!(comparison.status, "diverged");
+        assert_eq!((comparison.ahead_by, comparison.behind_by), (2, 1));
+        assert_eq!(comparison.merge_base_sha.as_deref(), Some("base123"));
+        assert_eq!(comparison.files[0].status, "Modified");
+        assert_eq!(comparison.total_additions(), 3);
     }
 }
@@ -0,0 +1,486 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// What a code block holds, from its info string or its contents
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CodeLanguage {
+    Rust,
+    /// rustc or cargo diagnostics, panics and backtraces
+    CompilerOutput,
+    /// Shell commands or a terminal session
+    Shell,
+    Toml,
+    Diff,
+    /// A tagged language this crate does not classify
+    Other(String),
+    /// Untagged block that no heuristic recognized
+    Unknown,
+}
+
+impl CodeLanguage {
+    /// Language named by an info string's first word; `None` for tags that
+    /// say nothing about the contents, such as `text`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.to_ascii_lowercase();
+        let language = match tag.as_str() {
+            "" | "text" | "txt" | "plain" | "plaintext" | "output" => return None,
+            "rust" | "rs" => Self::Rust,
+            "toml" => Self::Toml,
+            "diff" | "patch" => Self::Diff,
+            "console" | "sh" | "shell" | "bash" | "zsh" | "shell-session" | "shellsession"
+            | "terminal" => Self::Shell,
+            _ => Self::Other(tag),
+        };
+        Some(language)
+    }
+}
+
+/// A fenced or indented code block found in a Markdown body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeBlock {
+    /// Text after the opening fence, such as `rust,ignore`; None for indented
+    /// blocks and bare fences
+    pub info: Option<String>,
+    pub language: CodeLanguage,
+    /// Whether the language came from the info string rather than the contents
+    pub tagged: bool,
+    pub fenced: bool,
+    /// Contents without fences or the block's indentation
+    pub code: String,
+    /// Byte offset where the block, including any opening fence, starts in the body
+    pub start: usize,
+    /// Byte offset just past the block's last line, excluding its line break
+    pub end: usize,
+}
+
+/// Every fenced and indented code block in `markdown`, in order
+///
+/// Fences use three or more backticks or tildes and close with at least as many
+/// of the same character; an unclosed fence runs to the end of the body.
+/// Indented blocks must follow a blank line, and indented lines inside list
+/// items are treated as list content rather than code.
+///
+/// ```
+/// use github_fetch::codeblock::{extract_code_blocks, CodeLanguage};
+///
+/// let body = "Repro:\n\n```rust,ignore\nfn main() {}\n```\n\n- step one\n\n    still step one\n\n```\n$ cargo build\n```";
+/// let blocks = extract_code_blocks(body);
+///
+/// assert_eq!(blocks.len(), 2);
+/// assert_eq!(blocks[0].info.as_deref(), Some("rust,ignore"));
+/// assert_eq!(blocks[0].language, CodeLanguage::Rust);
+/// assert_eq!(&body[blocks[0].start..blocks[0].end], "```rust,ignore\nfn main() {}\n```");
+/// assert_eq!(blocks[1].language, CodeLanguage::Shell);
+/// assert!(!blocks[1].tagged);
+/// ```
+pub fn extract_code_blocks(markdown: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut fence: Option<Fence> = None;
+    let mut indented: Option<Indented> = None;
+    let mut previous_blank = true;
+    let mut in_list = false;
+    let mut offset = 0;
+
+    for raw in markdown.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let line_end = start + line.len();
+        let trimmed = line.trim_start();
+        let blank = trimmed.is_empty();
+
+        if let Some(open) = fence.as_mut() {
+            if open.closes(trimmed) {
+                let open = fence.take().unwrap();
+                blocks.push(open.finish(line_end));
+            } else {
+                open.push(line, line_end);
+            }
+            continue;
+        }
+
+        let indent = indentation(line);
+        if let Some(block) = indented.as_mut() {
+            if indent >= 4 || blank {
+                block.push(line, line_end);
+                previous_blank = blank;
+                continue;
+            }
+            blocks.push(indented.take().unwrap().finish());
+        }
+
+        if let Some(open) = Fence::open(line, start, line_end) {
+            fence = Some(open);
+            previous_blank = false;
+            continue;
+        }
+
+        if indent >= 4 && !blank && previous_blank && !in_list {
+            let mut block = Indented::new(start);
+            block.push(line, line_end);
+            indented = Some(block);
+            previous_blank = false;
+            continue;
+        }
+
+        if list_item_regex().is_match(line) {
+            in_list = true;
+        } else if !blank && indent == 0 && previous_blank {
+            in_list = false;
+        }
+        previous_blank = blank;
+    }
+
+    if let Some(open) = fence {
+        blocks.push(open.finish(markdown.len()));
+    }
+    if let Some(block) = indented {
+        blocks.push(block.finish());
+    }
+
+    blocks
+}
+
+/// Guess what an untagged code block holds
+pub fn classify_code(code: &str) -> CodeLanguage {
+    let lines: Vec<&str> = code
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    if lines.is_empty() {
+        return CodeLanguage::Unknown;
+    }
+
+    if lines.iter().any(|line| prompt_regex().is_match(line)) {
+        CodeLanguage::Shell
+    } else if lines
+        .iter()
+        .any(|line| compiler_output_regex().is_match(line))
+    {
+        CodeLanguage::CompilerOutput
+    } else if lines.iter().any(|line| diff_regex().is_match(line)) {
+        CodeLanguage::Diff
+    } else if is_toml(&lines) {
+        CodeLanguage::Toml
+    } else if lines.iter().any(|line| rust_regex().is_match(line)) {
+        CodeLanguage::Rust
+    } else if lines.iter().all(|line| command_regex().is_match(line)) {
+        CodeLanguage::Shell
+    } else {
+        CodeLanguage::Unknown
+    }
+}
+
+/// Section headers or `key = value` lines, with nothing that looks like Rust
+fn is_toml(lines: &[&str]) -> bool {
+    let mut has_section = false;
+    let mut assignments = 0;
+    for line in lines {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if toml_section_regex().is_match(line) {
+            has_section = true;
+        } else if toml_assignment_regex().is_match(line) {
+            assignments += 1;
+        } else if !line.starts_with(['"', '\'', ']', '}']) {
+            return false;
+        }
+    }
+    has_section || assignments > 0
+}
+
+struct Fence {
+    marker: char,
+    length: usize,
+    indent: usize,
+    info: Option<String>,
+    start: usize,
+    end: usize,
+    code: String,
+}
+
+impl Fence {
+    fn open(line: &str, start: usize, end: usize) -> Option<Self> {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let length = trimmed.chars().take_while(|c| *c == marker).count();
+        let info = trimmed[length..].trim();
+        // A backtick fence's info string cannot contain backticks
+        if length < 3 || (marker == '`' && info.contains('`')) {
+            return None;
+        }
+        Some(Self {
+            marker,
+            length,
+            indent: indentation(line),
+            info: Some(info.to_string()).filter(|info| !info.is_empty()),
+            start,
+            end,
+            code: String::new(),
+        })
+    }
+
+    fn closes(&self, trimmed: &str) -> bool {
+        let length = trimmed.chars().take_while(|c| *c == self.marker).count();
+        length >= self.length && trimmed[length..].trim().is_empty()
+    }
+
+    fn push(&mut self, line: &str, end: usize) {
+        self.code.push_str(strip_indentation(line, self.indent));
+        self.code.push('\n');
+        self.end = end;
+    }
+
+    fn finish(self, end: usize) -> CodeBlock {
+        let tag = self
+            .info
+            .as_deref()
+            .and_then(|info| info.split([',', ' ', '{']).next())
+            .unwrap_or("");
+        let (language, tagged) = match CodeLanguage::from_tag(tag) {
+            Some(language) => (language, true),
+            None => (classify_code(&self.code), false),
+        };
+        CodeBlock {
+            info: self.info,
+            language,
+            tagged,
+            fenced: true,
+            code: self.code,
+            start: self.start,
+            end: end.max(self.end),
+        }
+    }
+}
+
+struct Indented {
+    start: usize,
+    end: usize,
+    /// Code up to the last non-blank line
+    code: String,
+    /// Blank lines not yet known to be inside the block
+    pending_blank: usize,
+}
+
+impl Indented {
+    fn new(start: usize) -> Self {
+        Self {
+            start,
+            end: start,
+            code: String::new(),
+            pending_blank: 0,
+        }
+    }
+
+    fn push(&mut self, line: &str, end: usize) {
+        if line.trim().is_empty() {
+            self.pending_blank += 1;
+            return;
+        }
+        for _ in 0..self.pending_blank {
+            self.code.push('\n');
+        }
+        self.pending_blank = 0;
+        self.code.push_str(strip_indentation(line, 4));
+        self.code.push('\n');
+        self.end = end;
+    }
+
+    fn finish(self) -> CodeBlock {
+        CodeBlock {
+            info: None,
+            language: classify_code(&self.code),
+            tagged: false,
+            fenced: false,
+            code: self.code,
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+/// Leading spaces, with tabs advancing to the next multiple of four
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .fold(0, advance_column)
+}
+
+/// `line` without up to `columns` columns of leading whitespace
+///
+/// A tab reaching past `columns` is kept rather than split.
+fn strip_indentation(line: &str, columns: usize) -> &str {
+    let mut column = 0;
+    for (index, c) in line.char_indices() {
+        if c != ' ' && c != '\t' {
+            return &line[index..];
+        }
+        let next = advance_column(column, c);
+        if next > columns {
+            return &line[index..];
+        }
+        column = next;
+    }
+    ""
+}
+
+fn advance_column(column: usize, c: char) -> usize {
+    if c == '\t' {
+        column + 4 - column % 4
+    } else {
+        column + 1
+    }
+}
+
+macro_rules! static_regex {
+    ($name:ident, $pattern:expr) => {
+        fn $name() -> &'static Regex {
+            static REGEX: OnceLock<Regex> = OnceLock::new();
+            REGEX.get_or_init(|| Regex::new($pattern).unwrap())
+        }
+    };
+}
+
+static_regex!(list_item_regex, r"^\s{0,3}(?:[-*+]|\d{1,9}[.)])(?:\s|$)");
+static_regex!(prompt_regex, r"^\s*(?:\$|PS [^>]*>)\s+\S");
+static_regex!(
+    compiler_output_regex,
+    r"^\s*(?:(?:error|warning)(?:\[[A-Za-z0-9_:]+\])?: |-->\s+\S+:\d+:\d+|thread '[^']*' panicked at|stack backtrace:|\s*Compiling \S+ v\d)"
+);
+static_regex!(
+    diff_regex,
+    r"^(?:@@ -\d+(?:,\d+)? \+\d+(?:,\d+)? @@|diff --git |--- a/|\+\+\+ b/)"
+);
+static_regex!(toml_section_regex, r"^\[\[?[A-Za-z0-9_.\-\x22' ]+\]\]?$");
+static_regex!(
+    toml_assignment_regex,
+    r#"^[A-Za-z0-9_.\-"']+\s*=\s*(?:"|'|\{|\[|true\b|false\b|[0-9])"#
+);
+static_regex!(
+    rust_regex,
+    r"^\s*(?:(?:pub(?:\([^)]*\))?\s+)?(?:async\s+|const\s+|unsafe\s+)*fn\s+\w|(?:pub\s+)?(?:struct|enum|trait|mod|type)\s+\w|impl\b|use\s+[\w:]+|let\s+(?:mut\s+)?\w|#!?\[\w|macro_rules!|extern\s+crate\b)"
+);
+static_regex!(
+    command_regex,
+    r"^\s*(?:cargo|rustc|rustup|rustdoc|git|export|cd|env|RUST\w*=\S*)\b"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let body = "\
+Steps:
+
+1. Create a project
+    with nested text that is not code
+
+    more text for step one
+2. Build it:
+   ~~~~console
+   $ cargo build
+   ~~~~
+
+Output:
+
+    error[E0308]: mismatched types
+     --> src/main.rs:2:18
+
+
+    note: details
+
+```toml
+[dependencies]
+serde = \"1\"
+```
+
+````
+```rust
+fn main() {}
+```
+````
+
+```
+[package]
+name = \"demo\"
+";
+
+        let blocks = extract_code_blocks(body);
+        let summary: Vec<(bool, Option<&str>, &CodeLanguage, bool)> = blocks
+            .iter()
+            .map(|block| {
+                (
+                    block.fenced,
+                    block.info.as_deref(),
+                    &block.language,
+                    block.tagged,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (true, Some("console"), &CodeLanguage::Shell, true),
+                (false, None, &CodeLanguage::CompilerOutput, false),
+                (true, Some("toml"), &CodeLanguage::Toml, true),
+                (true, None, &CodeLanguage::Rust, false),
+                (true, None, &CodeLanguage::Toml, false),
+            ]
+        );
+
+        assert_eq!(blocks[0].code, "$ cargo build\n");
+        assert_eq!(
+            blocks[1].code,
+            "error[E0308]: mismatched types\n --> src/main.rs:2:18\n\n\nnote: details\n"
+        );
+        assert_eq!(
+            &body[blocks[1].start..blocks[1].end],
+            "    error[E0308]: mismatched types\n     --> src/main.rs:2:18\n\n\n    note: details"
+        );
+        assert_eq!(blocks[3].code, "```rust\nfn main() {}\n```\n");
+        assert_eq!(blocks[4].end, body.len());
+
+        // Tabs count as columns, not bytes, when stripping indentation
+        let tabbed = extract_code_blocks("- item\n\n  ```rust\n  fn main() {\n\t\n  }\n  ```\n");
+        assert_eq!(tabbed[0].code, "fn main() {\n\t\n}\n");
+        let tabbed = extract_code_blocks("  ```\n\tlet x = 1;\n  ```");
+        assert_eq!(tabbed[0].code, "\tlet x = 1;\n");
+        let tabbed = extract_code_blocks("Text\n\n\tlet x = 1;\n  \tlet y = 2;\n");
+        assert_eq!(tabbed[0].code, "let x = 1;\nlet y = 2;\n");
+    }
+
+    #[test]
+    fn test_classify_code() {
+        assert_eq!(
+            classify_code("let x = vec![1];\nprintln!(\"{x:?}\");"),
+            CodeLanguage::Rust
+        );
+        assert_eq!(
+            classify_code("thread 'main' panicked at src/main.rs:2:5:\nboom"),
+            CodeLanguage::CompilerOutput
+        );
+        assert_eq!(
+            classify_code("cargo +nightly build\nRUST_BACKTRACE=1 cargo run"),
+            CodeLanguage::Shell
+        );
+        assert_eq!(
+            classify_code("tokio = { version = \"1\", features = [\"full\"] }"),
+            CodeLanguage::Toml
+        );
+        assert_eq!(
+            classify_code("@@ -1,2 +1,2 @@\n-old\n+new"),
+            CodeLanguage::Diff
+        );
+        assert_eq!(classify_code("just some text"), CodeLanguage::Unknown);
+        assert_eq!(
+            CodeLanguage::from_tag("Python"),
+            Some(CodeLanguage::Other("python".to_string()))
+        );
+    }
+}
@@ -1,19 +1,14 @@
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::bots::BotClassifier;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FetchConfig {
     pub github: GitHubConfig,
     pub rate_limiting: RateLimitConfig,
-}
-
-impl Default for FetchConfig {
-    fn default() -> Self {
-        Self {
-            github: GitHubConfig::default(),
-            rate_limiting: RateLimitConfig::default(),
-        }
-    }
+    #[serde(default)]
+    pub bots: BotConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +36,13 @@ pub struct RateLimitConfig {
     pub delay_between_requests_ms: u64,
     pub respect_github_rate_limits: bool,
     pub max_retries: u32,
+    /// The search API allows fewer requests than the rest of the API
+    #[serde(default = "default_search_requests_per_minute")]
+    pub search_requests_per_minute: u32,
+}
+
+fn default_search_requests_per_minute() -> u32 {
+    30
 }
 
 impl Default for RateLimitConfig {
@@ -50,12 +52,27 @@ impl Default for RateLimitConfig {
             delay_between_requests_ms: 1000,
             respect_github_rate_limits: true,
             max_retries: 3,
+            search_requests_per_minute: default_search_requests_per_minute(),
         }
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BotConfig {
+    /// Drop comments, reviews, review comments and discussion comments by bots
+    pub exclude_activity: bool,
+    pub classifier: BotClassifier,
+}
+
 impl RateLimitConfig {
     pub fn delay_duration(&self) -> Duration {
         Duration::from_millis(self.delay_between_requests_ms)
     }
+
+    /// Spacing between search API requests, never shorter than `delay_duration`
+    pub fn search_delay_duration(&self) -> Duration {
+        let per_request =
+            Duration::from_millis(60_000 / u64::from(self.search_requests_per_minute.max(1)));
+        per_request.max(self.delay_duration())
+    }
 }
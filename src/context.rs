@@ -0,0 +1,666 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bundle::PrBundle;
+use crate::diff::{parse_patch, DiffLineKind};
+use crate::markdown::{code_block, preview};
+use crate::types::{Discussion, GitHubComment, GitHubIssue, PrFile, PrReviewComment};
+
+/// Estimates how many tokens a piece of text costs in a model's context window
+pub trait TokenEstimator: Send + Sync {
+    fn estimate(&self, text: &str) -> usize;
+}
+
+impl<F> TokenEstimator for F
+where
+    F: Fn(&str) -> usize + Send + Sync,
+{
+    fn estimate(&self, text: &str) -> usize {
+        self(text)
+    }
+}
+
+/// Rough estimator assuming a fixed number of characters per token
+#[derive(Debug, Clone, Copy)]
+pub struct CharRatioEstimator {
+    pub chars_per_token: f32,
+}
+
+impl Default for CharRatioEstimator {
+    fn default() -> Self {
+        Self {
+            chars_per_token: 4.0,
+        }
+    }
+}
+
+impl TokenEstimator for CharRatioEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        (text.chars().count() as f32 / self.chars_per_token.max(0.1)).ceil() as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContextKind {
+    Title,
+    Description,
+    ReviewThread,
+    CommentedHunk,
+    Hunk,
+    Comment,
+}
+
+/// Default priorities; higher is kept first
+pub mod priority {
+    pub const TITLE: u32 = 100;
+    pub const DESCRIPTION: u32 = 90;
+    pub const UNRESOLVED_THREAD: u32 = 80;
+    pub const COMMENTED_HUNK: u32 = 70;
+    pub const LATEST_COMMENT: u32 = 60;
+    pub const OUTDATED_THREAD: u32 = 40;
+    pub const HUNK: u32 = 30;
+    pub const OLDER_COMMENT: u32 = 20;
+}
+
+/// A unit of content that is either kept whole, replaced by its summary, or elided
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextItem {
+    pub kind: ContextKind,
+    pub priority: u32,
+    pub heading: String,
+    pub content: String,
+    /// Shorter stand-in used when the full content does not fit
+    pub summary: Option<String>,
+}
+
+/// Content to pack, in reading order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextDocument {
+    pub items: Vec<ContextItem>,
+}
+
+/// Number of most recent comments treated as "latest"
+const LATEST_COMMENTS: usize = 5;
+
+impl ContextDocument {
+    pub fn push(&mut self, item: ContextItem) {
+        self.items.push(item);
+    }
+
+    pub fn from_issue(issue: &GitHubIssue, comments: &[GitHubComment]) -> Self {
+        let mut doc = Self::default();
+        doc.push_header(
+            &format!("#{}: {}", issue.number, issue.title),
+            issue.body.as_deref(),
+        );
+
+        let latest = latest_indices(comments.iter().map(|c| c.created_at));
+        for (i, comment) in comments.iter().enumerate() {
+            doc.push_comment(
+                &comment.user.login,
+                &comment.created_at.to_rfc3339(),
+                &comment.body,
+                latest.contains(&i),
+            );
+        }
+
+        doc
+    }
+
+    pub fn from_discussion(discussion: &Discussion) -> Self {
+        let mut doc = Self::default();
+        doc.push_header(
+            &format!("#{}: {}", discussion.number, discussion.title),
+            Some(&discussion.body),
+        );
+
+        let latest = latest_indices(discussion.comments.iter().map(|c| c.created_at));
+        for (i, comment) in discussion.comments.iter().enumerate() {
+            doc.push_comment(
+                &comment.author.login,
+                &comment.created_at.to_rfc3339(),
+                &comment.body,
+                latest.contains(&i),
+            );
+        }
+
+        doc
+    }
+
+    /// Build a document from PR parts
+    ///
+    /// Review threads whose comments are not outdated are treated as
+    /// unresolved, since the REST API does not expose thread resolution.
+    pub fn from_pr(
+        pr: &GitHubIssue,
+        files: &[PrFile],
+        review_comments: &[PrReviewComment],
+        comments: &[GitHubComment],
+    ) -> Self {
+        let mut doc = Self::default();
+        doc.push_header(
+            &format!("PR #{}: {}", pr.number, pr.title),
+            pr.body.as_deref(),
+        );
+
+        // Group replies under their top-level comment
+        let mut threads: BTreeMap<u64, Vec<&PrReviewComment>> = BTreeMap::new();
+        for comment in review_comments {
+            let root = comment.in_reply_to_id.unwrap_or(comment.id);
+            threads.entry(root).or_default().push(comment);
+        }
+        for thread in threads.values_mut() {
+            // The top-level comment first, then replies oldest first
+            thread.sort_by_key(|comment| (comment.in_reply_to_id.is_some(), comment.created_at));
+            let root = thread[0];
+            let mut content = String::new();
+            for comment in thread.iter() {
+                let _ = writeln!(
+                    content,
+                    "**@{}**: {}\n",
+                    comment.user.login,
+                    comment.body.trim()
+                );
+            }
+            let location = match root.line.or(root.original_line) {
+                Some(line) => format!("{}:{}", root.path, line),
+                None => root.path.clone(),
+            };
+            doc.push(ContextItem {
+                kind: ContextKind::ReviewThread,
+                priority: if root.is_outdated() {
+                    priority::OUTDATED_THREAD
+                } else {
+                    priority::UNRESOLVED_THREAD
+                },
+                heading: format!("Review thread on {}", location),
+                content: content.trim_end().to_string(),
+                summary: Some(format!(
+                    "**@{}**: {} ({} comments)",
+                    root.user.login,
+                    preview(root.body.lines().next().unwrap_or(""), 120),
+                    thread.len()
+                )),
+            });
+        }
+
+        for file in files {
+            let patch = match &file.patch {
+                Some(patch) => patch,
+                None => continue,
+            };
+            // Lines on the side of the diff they are numbered in
+            let commented: Vec<(bool, u32)> = review_comments
+                .iter()
+                .filter(|c| c.path == file.filename)
+                .filter_map(|c| c.line.map(|line| (c.is_left_side(), line)))
+                .collect();
+
+            let raw_hunks = split_hunks(patch);
+            for (hunk, raw) in parse_patch(patch).iter().zip(raw_hunks) {
+                // One past the hunk's last line on each side
+                let old_end = hunk.old_start + hunk.old_count;
+                let new_end = hunk.new_start + hunk.new_count;
+                let near_comment = commented.iter().any(|(left, line)| {
+                    if *left {
+                        (hunk.old_start..old_end).contains(line)
+                    } else {
+                        (hunk.new_start..new_end).contains(line)
+                    }
+                });
+                let added = hunk
+                    .lines
+                    .iter()
+                    .filter(|l| l.kind == DiffLineKind::Added)
+                    .count();
+                let removed = hunk
+                    .lines
+                    .iter()
+                    .filter(|l| l.kind == DiffLineKind::Removed)
+                    .count();
+
+                doc.push(ContextItem {
+                    kind: if near_comment {
+                        ContextKind::CommentedHunk
+                    } else {
+                        ContextKind::Hunk
+                    },
+                    priority: if near_comment {
+                        priority::COMMENTED_HUNK
+                    } else {
+                        priority::HUNK
+                    },
+                    heading: format!("{} @ line {}", file.filename, hunk.new_start),
+                    content: code_block(&raw, "diff"),
+                    summary: Some(format!(
+                        "`{}` lines {}-{}: +{} -{}",
+                        file.filename,
+                        hunk.new_start,
+                        new_end.saturating_sub(1).max(hunk.new_start),
+                        added,
+                        removed
+                    )),
+                });
+            }
+        }
+
+        let latest = latest_indices(comments.iter().map(|c| c.created_at));
+        for (i, comment) in comments.iter().enumerate() {
+            doc.push_comment(
+                &comment.user.login,
+                &comment.created_at.to_rfc3339(),
+                &comment.body,
+                latest.contains(&i),
+            );
+        }
+
+        doc
+    }
+
+    /// Build a document from a PR bundle, skipping parts that failed to fetch
+    pub fn from_bundle(bundle: &PrBundle) -> Option<Self> {
+        Some(Self::from_pr(
+            bundle.pr.as_ref()?,
+            bundle.files.as_deref().unwrap_or_default(),
+            bundle.review_comments.as_deref().unwrap_or_default(),
+            bundle.comments.as_deref().unwrap_or_default(),
+        ))
+    }
+
+    fn push_header(&mut self, title: &str, body: Option<&str>) {
+        self.push(ContextItem {
+            kind: ContextKind::Title,
+            priority: priority::TITLE,
+            heading: "Title".to_string(),
+            content: title.to_string(),
+            summary: None,
+        });
+
+        if let Some(body) = body.filter(|b| !b.trim().is_empty()) {
+            let first_paragraph = body.trim().split("\n\n").next().unwrap_or("");
+            self.push(ContextItem {
+                kind: ContextKind::Description,
+                priority: priority::DESCRIPTION,
+                heading: "Description".to_string(),
+                content: body.trim().to_string(),
+                summary: Some(preview(first_paragraph, 400)),
+            });
+        }
+    }
+
+    fn push_comment(&mut self, author: &str, created_at: &str, body: &str, latest: bool) {
+        self.push(ContextItem {
+            kind: ContextKind::Comment,
+            priority: if latest {
+                priority::LATEST_COMMENT
+            } else {
+                priority::OLDER_COMMENT
+            },
+            heading: format!("Comment by @{} at {}", author, created_at),
+            content: body.trim().to_string(),
+            summary: Some(preview(body.trim().lines().next().unwrap_or(""), 120)),
+        });
+    }
+}
+
+/// Indices of the most recent items by timestamp
+fn latest_indices<T: Ord>(timestamps: impl Iterator<Item = T>) -> HashSet<usize> {
+    let mut indexed: Vec<(usize, T)> = timestamps.enumerate().collect();
+    indexed.sort_by(|a, b| b.1.cmp(&a.1));
+    indexed
+        .into_iter()
+        .take(LATEST_COMMENTS)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Raw text of each hunk in a patch, header included
+fn split_hunks(patch: &str) -> Vec<String> {
+    let mut hunks: Vec<String> = Vec::new();
+    for line in patch.lines() {
+        if line.starts_with("@@") {
+            hunks.push(String::new());
+        }
+        if let Some(hunk) = hunks.last_mut() {
+            hunk.push_str(line);
+            hunk.push('\n');
+        }
+    }
+    hunks
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PackStatus {
+    Included,
+    Summarized,
+    Elided,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackedItem {
+    pub kind: ContextKind,
+    pub heading: String,
+    pub status: PackStatus,
+    /// Full content, summary, or elision marker, depending on `status`
+    pub text: String,
+    /// Tokens of the full item, whether or not it was kept
+    pub full_tokens: usize,
+    /// Tokens this item takes up in the packed output
+    pub tokens: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackedContext {
+    pub budget: usize,
+    pub used_tokens: usize,
+    /// Items in reading order
+    pub items: Vec<PackedItem>,
+    /// Items too large even for an elision marker, counted in a trailing marker
+    pub omitted_items: usize,
+    /// Tokens of the full omitted items
+    pub omitted_tokens: usize,
+}
+
+impl PackedContext {
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        for item in &self.items {
+            out.push_str(&render_item(&item.heading, &item.text));
+        }
+        if self.omitted_items > 0 {
+            out.push_str(&omitted_marker(self.omitted_items, self.omitted_tokens));
+            out.push_str("\n\n");
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn count(&self, status: PackStatus) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.status == status)
+            .count()
+    }
+}
+
+fn render_item(heading: &str, text: &str) -> String {
+    format!("### {}\n\n{}\n\n", heading, text)
+}
+
+fn omitted_marker(items: usize, tokens: usize) -> String {
+    let noun = if items == 1 { "item" } else { "items" };
+    format!("_[{} more {} omitted: ~{} tokens]_", items, noun, tokens)
+}
+
+/// Packs a [`ContextDocument`] into a token budget, keeping the highest-priority content
+pub struct ContextPacker {
+    budget: usize,
+    estimator: Box<dyn TokenEstimator>,
+}
+
+impl ContextPacker {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            estimator: Box::new(CharRatioEstimator::default()),
+        }
+    }
+
+    pub fn with_estimator(mut self, estimator: impl TokenEstimator + 'static) -> Self {
+        self.estimator = Box::new(estimator);
+        self
+    }
+
+    /// Pack items by descending priority: each is kept whole if it fits, else
+    /// replaced by its summary, else by a one-line elision marker, else counted
+    /// in a trailing marker that budget is reserved for. Items keep their
+    /// reading order in the output.
+    pub fn pack(&self, document: &ContextDocument) -> PackedContext {
+        let packed = self.pack_within(document, self.budget);
+        if packed.omitted_items == 0 {
+            return packed;
+        }
+
+        // Reserve room for the largest marker the document could need; a
+        // smaller budget only omits more, never a bigger marker
+        let total_tokens = document
+            .items
+            .iter()
+            .map(|item| {
+                self.estimator
+                    .estimate(&render_item(&item.heading, &item.content))
+            })
+            .sum();
+        let reserve = self
+            .estimator
+            .estimate(&omitted_marker(document.items.len(), total_tokens));
+        let mut packed = self.pack_within(document, self.budget.saturating_sub(reserve));
+        packed.used_tokens += self
+            .estimator
+            .estimate(&omitted_marker(packed.omitted_items, packed.omitted_tokens));
+        packed.budget = self.budget;
+        packed
+    }
+
+    fn pack_within(&self, document: &ContextDocument, budget: usize) -> PackedContext {
+        let mut order: Vec<usize> = (0..document.items.len()).collect();
+        order.sort_by(|a, b| {
+            document.items[*b]
+                .priority
+                .cmp(&document.items[*a].priority)
+        });
+
+        let mut packed: Vec<Option<PackedItem>> = vec![None; document.items.len()];
+        let mut used = 0;
+        let mut omitted_tokens = 0;
+
+        for index in order {
+            let item = &document.items[index];
+            let full_tokens = self
+                .estimator
+                .estimate(&render_item(&item.heading, &item.content));
+
+            let summary = item.summary.as_ref().map(|summary| {
+                format!(
+                    "{}\n\n_[summarized: ~{} tokens omitted]_",
+                    summary, full_tokens
+                )
+            });
+            let marker = format!("_[omitted: ~{} tokens]_", full_tokens);
+
+            let candidates = [
+                (PackStatus::Included, Some(item.content.clone())),
+                (PackStatus::Summarized, summary),
+                (PackStatus::Elided, Some(marker)),
+            ];
+            for (status, text) in candidates {
+                let text = match text {
+                    Some(text) => text,
+                    None => continue,
+                };
+                let tokens = if status == PackStatus::Included {
+                    full_tokens
+                } else {
+                    self.estimator.estimate(&render_item(&item.heading, &text))
+                };
+                if used + tokens <= budget {
+                    used += tokens;
+                    packed[index] = Some(PackedItem {
+                        kind: item.kind,
+                        heading: item.heading.clone(),
+                        status,
+                        text,
+                        full_tokens,
+                        tokens,
+                    });
+                    break;
+                }
+            }
+            if packed[index].is_none() {
+                omitted_tokens += full_tokens;
+            }
+        }
+
+        let items: Vec<PackedItem> = packed.into_iter().flatten().collect();
+        PackedContext {
+            budget,
+            used_tokens: used,
+            omitted_items: document.items.len() - items.len(),
+            omitted_tokens,
+            items,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use chrono::{TimeZone, Utc};
+
+    fn item(kind: ContextKind, priority: u32, content: &str) -> ContextItem {
+        ContextItem {
+            kind,
+            priority,
+            heading: format!("{:?}", kind),
+            content: content.to_string(),
+            summary: Some("short".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_pack_by_priority() {
+        let mut doc = ContextDocument::default();
+        doc.push(item(ContextKind::Title, priority::TITLE, "Title"));
+        doc.push(item(
+            ContextKind::Comment,
+            priority::OLDER_COMMENT,
+            &"old ".repeat(50),
+        ));
+        doc.push(item(
+            ContextKind::Description,
+            priority::DESCRIPTION,
+            &"body ".repeat(10),
+        ));
+
+        // One token per word keeps the arithmetic readable
+        let words = |text: &str| text.split_whitespace().count();
+        let packed = ContextPacker::new(40).with_estimator(words).pack(&doc);
+
+        let statuses: Vec<PackStatus> = packed.items.iter().map(|i| i.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                PackStatus::Included,
+                PackStatus::Summarized,
+                PackStatus::Included
+            ]
+        );
+        assert!(packed.used_tokens <= 40);
+        assert!(packed
+            .to_markdown()
+            .contains("_[summarized: ~52 tokens omitted]_"));
+    }
+
+    #[test]
+    fn test_elide_and_omit() {
+        let mut doc = ContextDocument::default();
+        doc.push(ContextItem {
+            summary: None,
+            ..item(ContextKind::Hunk, priority::HUNK, &"x ".repeat(100))
+        });
+        doc.push(ContextItem {
+            heading: "Comment by someone".to_string(),
+            ..item(
+                ContextKind::Comment,
+                priority::OLDER_COMMENT,
+                &"y ".repeat(100),
+            )
+        });
+
+        // 5 tokens for the first elision marker and 6 reserved for the
+        // trailing one; the second elision marker would take 7
+        let words = |text: &str| text.split_whitespace().count();
+        let packed = ContextPacker::new(11).with_estimator(words).pack(&doc);
+
+        assert_eq!(packed.items.len(), 1);
+        assert_eq!(packed.items[0].status, PackStatus::Elided);
+        assert_eq!(packed.items[0].text, "_[omitted: ~102 tokens]_");
+        assert_eq!((packed.omitted_items, packed.omitted_tokens), (1, 104));
+        assert_eq!(packed.used_tokens, 11);
+        assert!(packed
+            .to_markdown()
+            .ends_with("_[1 more item omitted: ~104 tokens]_\n\n"));
+    }
+
+    fn review_comment(id: u64, reply_to: Option<u64>, line: u32, minute: u32) -> PrReviewComment {
+        let at = Utc.with_ymd_and_hms(2024, 5, 1, 12, minute, 0).unwrap();
+        PrReviewComment {
+            user: fixtures::user(&format!("user{}", id)),
+            body: format!("comment {}", id),
+            line: Some(line),
+            original_line: Some(line),
+            created_at: at,
+            updated_at: at,
+            in_reply_to_id: reply_to,
+            ..fixtures::review_comment(id)
+        }
+    }
+
+    #[test]
+    fn test_from_pr_threads_and_hunks() {
+        let pr = GitHubIssue {
+            title: "Refactor".to_string(),
+            ..fixtures::pull_request(7)
+        };
+        // New side covers lines 10 to 12
+        let files = vec![PrFile {
+            filename: "src/lib.rs".to_string(),
+            status: "Modified".to_string(),
+            additions: 1,
+            deletions: 0,
+            changes: 1,
+            patch: Some("@@ -10,2 +10,3 @@\n a\n+b\n c".to_string()),
+        }];
+        // The reply is listed before the comment it answers; line 13 is just
+        // past the hunk
+        let review_comments = vec![
+            review_comment(2, Some(1), 13, 5),
+            review_comment(1, None, 13, 1),
+        ];
+
+        let doc = ContextDocument::from_pr(&pr, &files, &review_comments, &[]);
+        let thread = doc
+            .items
+            .iter()
+            .find(|item| item.kind == ContextKind::ReviewThread)
+            .unwrap();
+        assert!(thread.content.starts_with("**@user1**: comment 1"));
+        assert!(thread.summary.as_deref().unwrap().starts_with("**@user1**"));
+        assert!(doc.items.iter().any(|item| item.kind == ContextKind::Hunk));
+        assert!(!doc
+            .items
+            .iter()
+            .any(|item| item.kind == ContextKind::CommentedHunk));
+
+        // LEFT lines are checked against the old side, lines 10 and 11
+        let left = |line| PrReviewComment {
+            side: Some("LEFT".to_string()),
+            ..review_comment(3, None, line, 2)
+        };
+        let commented_hunk = |comment| {
+            ContextDocument::from_pr(&pr, &files, &[comment], &[])
+                .items
+                .iter()
+                .any(|item| item.kind == ContextKind::CommentedHunk)
+        };
+        assert!(commented_hunk(left(11)));
+        assert!(!commented_hunk(left(12)));
+        assert!(commented_hunk(review_comment(3, None, 12, 2)));
+    }
+}
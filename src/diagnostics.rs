@@ -0,0 +1,773 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+/// Kind of diagnostic an extractor recognizes
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DiagnosticKind {
+    /// rustc error codes such as `E0382`
+    RustcError,
+    /// Clippy lint names such as `clippy::needless_borrow`
+    ClippyLint,
+    /// rustc lint names such as `unused_variables`, from `#[warn(...)]` notes
+    RustcLint,
+    /// `thread '...' panicked at ...` messages
+    Panic,
+    /// `stack backtrace:` dumps
+    Backtrace,
+    /// Cargo errors without an error code, such as `error: failed to select a version`
+    CargoError,
+    /// Kind of a user-registered extractor
+    Custom(String),
+}
+
+impl DiagnosticKind {
+    pub const BUILTIN: [DiagnosticKind; 6] = [
+        Self::RustcError,
+        Self::ClippyLint,
+        Self::RustcLint,
+        Self::Panic,
+        Self::Backtrace,
+        Self::CargoError,
+    ];
+
+    /// Name used in filter queries, e.g. `clippy-lint`
+    pub fn name(&self) -> &str {
+        match self {
+            Self::RustcError => "rustc-error",
+            Self::ClippyLint => "clippy-lint",
+            Self::RustcLint => "rustc-lint",
+            Self::Panic => "panic",
+            Self::Backtrace => "backtrace",
+            Self::CargoError => "cargo-error",
+            Self::Custom(name) => name,
+        }
+    }
+
+    /// Built-in kind with this name, or a custom kind
+    pub fn from_name(name: &str) -> Self {
+        Self::BUILTIN
+            .iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+            .cloned()
+            .unwrap_or_else(|| Self::Custom(name.to_string()))
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One diagnostic found in a text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticMatch {
+    pub kind: DiagnosticKind,
+    /// Identifying text: the error code, lint name, panic message or cargo error
+    pub code: String,
+    /// Byte offset of the match in the text
+    pub offset: usize,
+}
+
+/// Finds diagnostics of one kind in free text
+pub trait DiagnosticExtractor: Send + Sync {
+    fn kind(&self) -> DiagnosticKind;
+
+    fn extract(&self, text: &str) -> Vec<DiagnosticMatch>;
+
+    fn detect(&self, text: &str) -> bool {
+        !self.extract(text).is_empty()
+    }
+}
+
+/// Extractor driven by a regular expression; the first capture group that
+/// matched, or the whole match without one, becomes the diagnostic's code
+#[derive(Debug, Clone)]
+pub struct RegexExtractor {
+    kind: DiagnosticKind,
+    regex: Regex,
+}
+
+impl RegexExtractor {
+    pub fn new(kind: DiagnosticKind, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            kind,
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// Extractor for a user-defined kind
+    pub fn custom(name: impl Into<String>, pattern: &str) -> Result<Self, regex::Error> {
+        Self::new(DiagnosticKind::Custom(name.into()), pattern)
+    }
+}
+
+impl DiagnosticExtractor for RegexExtractor {
+    fn kind(&self) -> DiagnosticKind {
+        self.kind.clone()
+    }
+
+    fn extract(&self, text: &str) -> Vec<DiagnosticMatch> {
+        extract_with(&self.regex, &self.kind, text)
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+fn extract_with(regex: &Regex, kind: &DiagnosticKind, text: &str) -> Vec<DiagnosticMatch> {
+    regex
+        .captures_iter(text)
+        .filter_map(|captures| {
+            let found = captures
+                .iter()
+                .skip(1)
+                .flatten()
+                .next()
+                .or_else(|| captures.get(0))?;
+            Some(DiagnosticMatch {
+                kind: kind.clone(),
+                code: found.as_str().trim().to_string(),
+                offset: found.start(),
+            })
+        })
+        .collect()
+}
+
+macro_rules! builtin_extractor {
+    ($(#[$doc:meta])* $name:ident, $kind:expr, $pattern:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct $name;
+
+        impl $name {
+            fn regex() -> &'static Regex {
+                static REGEX: OnceLock<Regex> = OnceLock::new();
+                REGEX.get_or_init(|| Regex::new($pattern).unwrap())
+            }
+        }
+
+        impl DiagnosticExtractor for $name {
+            fn kind(&self) -> DiagnosticKind {
+                $kind
+            }
+
+            fn extract(&self, text: &str) -> Vec<DiagnosticMatch> {
+                extract_with(Self::regex(), &$kind, text)
+            }
+
+            fn detect(&self, text: &str) -> bool {
+                Self::regex().is_match(text)
+            }
+        }
+    };
+}
+
+builtin_extractor!(
+    /// `E0382`, `error[E0502]`
+    RustcErrorCodes,
+    DiagnosticKind::RustcError,
+    r"\b(E0\d{3,4})\b"
+);
+builtin_extractor!(
+    /// `clippy::needless_borrow`, `#[deny(clippy::all)]`
+    ClippyLints,
+    DiagnosticKind::ClippyLint,
+    r"\b(clippy::[a-z_][a-z0-9_]*)"
+);
+builtin_extractor!(
+    /// The lint named in `#[warn(unused_variables)]` style attributes and notes
+    RustcLints,
+    DiagnosticKind::RustcLint,
+    r"#!?\[(?:warn|deny|allow|forbid|expect)\(([a-z_][a-z0-9_]*)\)\]"
+);
+builtin_extractor!(
+    /// `thread 'main' panicked at src/main.rs:4:5:`, or the older
+    /// `panicked at 'message', src/main.rs:4:5`; the code is the location
+    Panics,
+    DiagnosticKind::Panic,
+    r"thread '[^']*' panicked at (?:'[^\n]*', )?([^\s']+:\d+:\d+)"
+);
+builtin_extractor!(
+    /// `stack backtrace:` headers
+    Backtraces,
+    DiagnosticKind::Backtrace,
+    r"(?m)^\s*stack backtrace:"
+);
+builtin_extractor!(
+    /// `error: failed to ...` and other cargo errors, which carry no code
+    CargoErrors,
+    DiagnosticKind::CargoError,
+    r"(?m)^\s*error: ((?:failed to|could not|no matching package|the lock file|package `|cyclic package|invalid)[^\n]*)"
+);
+
+/// Set of extractors consulted together
+///
+/// ```
+/// use github_fetch::diagnostics::{DiagnosticKind, DiagnosticRegistry, RegexExtractor};
+///
+/// let mut registry = DiagnosticRegistry::builtin();
+/// registry.register(RegexExtractor::custom("miri-ub", r"Undefined Behavior: ([^\n]+)").unwrap());
+///
+/// let text = "error[E0499]: cannot borrow\nerror: Undefined Behavior: out-of-bounds read";
+/// let kinds = registry.kinds(text);
+/// assert!(kinds.contains(&DiagnosticKind::RustcError));
+/// assert!(kinds.contains(&DiagnosticKind::Custom("miri-ub".to_string())));
+/// ```
+#[derive(Default)]
+pub struct DiagnosticRegistry {
+    extractors: Vec<Box<dyn DiagnosticExtractor>>,
+}
+
+impl DiagnosticRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with every built-in extractor
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(RustcErrorCodes);
+        registry.register(ClippyLints);
+        registry.register(RustcLints);
+        registry.register(Panics);
+        registry.register(Backtraces);
+        registry.register(CargoErrors);
+        registry
+    }
+
+    /// Shared built-in registry, used by filters that do not bring their own
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<DiagnosticRegistry>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(Self::builtin())).clone()
+    }
+
+    pub fn register(&mut self, extractor: impl DiagnosticExtractor + 'static) -> &mut Self {
+        self.extractors.push(Box::new(extractor));
+        self
+    }
+
+    /// Every diagnostic in `text`, ordered by position
+    pub fn extract(&self, text: &str) -> Vec<DiagnosticMatch> {
+        let mut matches: Vec<DiagnosticMatch> = self
+            .extractors
+            .iter()
+            .flat_map(|extractor| extractor.extract(text))
+            .collect();
+        matches.sort_by_key(|found| found.offset);
+        matches
+    }
+
+    /// Kinds of diagnostic present in `text`
+    pub fn kinds(&self, text: &str) -> BTreeSet<DiagnosticKind> {
+        self.extractors
+            .iter()
+            .filter(|extractor| extractor.detect(text))
+            .map(|extractor| extractor.kind())
+            .collect()
+    }
+
+    /// Whether `text` holds a diagnostic of any of `kinds`
+    pub fn has_any(&self, text: &str, kinds: &[DiagnosticKind]) -> bool {
+        self.extractors
+            .iter()
+            .filter(|extractor| kinds.contains(&extractor.kind()))
+            .any(|extractor| extractor.detect(text))
+    }
+}
+
+impl fmt::Debug for DiagnosticRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.extractors.iter().map(|extractor| extractor.kind()))
+            .finish()
+    }
+}
+
+/// Severity of a compiler diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+    /// `error: internal compiler error: ...`
+    InternalCompilerError,
+}
+
+/// A source location a diagnostic points at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticSpan {
+    pub file: String,
+    pub line: u32,
+    /// 1-based column
+    pub column: u32,
+    /// Marked with `^` (or given by the `-->` line) rather than `-`
+    pub primary: bool,
+    /// Text next to the span's marker, such as `value moved here`
+    pub label: Option<String>,
+}
+
+/// One diagnostic parsed from pasted rustc or cargo output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustcDiagnostic {
+    pub level: DiagnosticLevel,
+    /// Error code such as `E0382`, when the header carries one
+    pub code: Option<String>,
+    pub message: String,
+    /// Primary spans first come from the main message; spans under a
+    /// `note:` sub-diagnostic are always secondary
+    pub spans: Vec<DiagnosticSpan>,
+    /// `= note:` lines and `note:` sub-diagnostics
+    pub notes: Vec<String>,
+    /// `= help:` lines and `help:` sub-diagnostics
+    pub help: Vec<String>,
+}
+
+impl RustcDiagnostic {
+    pub fn primary_span(&self) -> Option<&DiagnosticSpan> {
+        self.spans.iter().find(|span| span.primary)
+    }
+}
+
+fn header_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"^\s*(error|warning|note|help)(?:\[([A-Za-z0-9_:]+)\])?: (.+)$").unwrap()
+    })
+}
+
+fn summary_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"^(aborting due to|could not compile|\d+ warnings? emitted|build failed|Some errors have detailed explanations|For more information about)",
+        )
+        .unwrap()
+    })
+}
+
+fn location_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^\s*(-->|:::)\s*(.+?):(\d+):(\d+)\s*$").unwrap())
+}
+
+fn gutter_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^\s*(\d+)?\s*\|(.*)$").unwrap())
+}
+
+fn sub_note_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^\s*= (note|help): (.+)$").unwrap())
+}
+
+/// Line with blockquote markers removed, or None for a code fence
+fn unquote(line: &str) -> Option<&str> {
+    let mut line = line.trim_end_matches('\r');
+    while let Some(rest) = line.trim_start().strip_prefix('>') {
+        line = rest.strip_prefix(' ').unwrap_or(rest);
+    }
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+        return None;
+    }
+    Some(line)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Main,
+    Note,
+    Help,
+}
+
+/// Parse rustc diagnostics out of an issue or comment body
+///
+/// Compiler output is found whether it is pasted bare, inside code fences, or
+/// in a blockquote. Summary lines such as `error: aborting due to 2 previous
+/// errors` are skipped.
+///
+/// ```
+/// use github_fetch::diagnostics::{parse_rustc_diagnostics, DiagnosticLevel};
+///
+/// let body = "Building fails:\n\n```\nerror[E0382]: borrow of moved value: `v`\n --> src/main.rs:4:20\n  |\n2 |     let v = vec![1];\n  |         - move occurs because `v` has type `Vec<i32>`\n3 |     let w = v;\n  |             - value moved here\n4 |     println!(\"{:?}\", v);\n  |                    ^ value borrowed here after move\n```";
+///
+/// let diagnostics = parse_rustc_diagnostics(body);
+/// assert_eq!(diagnostics[0].level, DiagnosticLevel::Error);
+/// assert_eq!(diagnostics[0].code.as_deref(), Some("E0382"));
+/// let primary = diagnostics[0].primary_span().unwrap();
+/// assert_eq!((primary.file.as_str(), primary.line, primary.column), ("src/main.rs", 4, 20));
+/// assert_eq!(primary.label.as_deref(), Some("value borrowed here after move"));
+/// ```
+pub fn parse_rustc_diagnostics(text: &str) -> Vec<RustcDiagnostic> {
+    let mut diagnostics: Vec<RustcDiagnostic> = Vec::new();
+    let mut open = false;
+    let mut section = Section::Main;
+    let mut file = String::new();
+    let mut source_line = 0;
+    // Note or help list a wrapped line continues
+    let mut wraps = None;
+
+    for raw in text.lines() {
+        let line = match unquote(raw) {
+            Some(line) if !line.trim().is_empty() => line,
+            _ => {
+                open = false;
+                continue;
+            }
+        };
+
+        let wrapped = wraps.take();
+
+        if let Some(captures) = header_regex().captures(line) {
+            let message = captures[3].trim().to_string();
+            match &captures[1] {
+                "error" | "warning" => {
+                    open = false;
+                    if summary_regex().is_match(&message) {
+                        continue;
+                    }
+                    let (level, message) = match message.strip_prefix("internal compiler error: ") {
+                        Some(rest) => (DiagnosticLevel::InternalCompilerError, rest.to_string()),
+                        None if &captures[1] == "error" => (DiagnosticLevel::Error, message),
+                        None => (DiagnosticLevel::Warning, message),
+                    };
+                    diagnostics.push(RustcDiagnostic {
+                        level,
+                        code: captures.get(2).map(|code| code.as_str().to_string()),
+                        message,
+                        spans: Vec::new(),
+                        notes: Vec::new(),
+                        help: Vec::new(),
+                    });
+                    open = true;
+                    section = Section::Main;
+                    file.clear();
+                }
+                kind if open => {
+                    let diagnostic = diagnostics.last_mut().unwrap();
+                    section = if kind == "note" {
+                        Section::Note
+                    } else {
+                        Section::Help
+                    };
+                    push_text(diagnostic, section, message);
+                    wraps = Some(section);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if !open {
+            continue;
+        }
+        let diagnostic = diagnostics.last_mut().unwrap();
+
+        if let Some(captures) = location_regex().captures(line) {
+            file = captures[2].to_string();
+            source_line = captures[3].parse().unwrap_or(0);
+            if section == Section::Help {
+                continue;
+            }
+            diagnostic.spans.push(DiagnosticSpan {
+                file: file.clone(),
+                line: source_line,
+                column: captures[4].parse().unwrap_or(0),
+                primary: section == Section::Main && &captures[1] == "-->",
+                label: None,
+            });
+        } else if let Some(captures) = sub_note_regex().captures(line) {
+            let kind = if &captures[1] == "note" {
+                Section::Note
+            } else {
+                Section::Help
+            };
+            push_text(diagnostic, kind, captures[2].trim().to_string());
+            wraps = Some(kind);
+        } else if let Some(captures) = gutter_regex().captures(line) {
+            if let Some(number) = captures.get(1) {
+                source_line = number.as_str().parse().unwrap_or(source_line);
+                continue;
+            }
+            if section == Section::Help || file.is_empty() {
+                continue;
+            }
+            let annotation = captures.get(2).unwrap();
+            let content = annotation.as_str().strip_prefix(' ').unwrap_or("");
+            annotate(diagnostic, section, &file, source_line, content);
+        } else if let Some(kind) = wrapped.filter(|_| line.starts_with(char::is_whitespace)) {
+            let last = match kind {
+                Section::Help => diagnostic.help.last_mut(),
+                _ => diagnostic.notes.last_mut(),
+            };
+            if let Some(last) = last {
+                last.push(' ');
+                last.push_str(line.trim());
+            }
+            wraps = Some(kind);
+        }
+    }
+
+    diagnostics
+}
+
+fn push_text(diagnostic: &mut RustcDiagnostic, section: Section, text: String) {
+    match section {
+        Section::Help => diagnostic.help.push(text),
+        _ => diagnostic.notes.push(text),
+    }
+}
+
+/// Record the spans marked on an annotation line below `source_line`
+///
+/// Several spans can be marked on one line; only the rightmost marker has
+/// its label on the same line. The others hang theirs below, on a line that
+/// starts the label in the marker's column after `|` connectors.
+fn annotate(
+    diagnostic: &mut RustcDiagnostic,
+    section: Section,
+    file: &str,
+    source_line: u32,
+    content: &str,
+) {
+    let mut markers: Vec<(u32, bool)> = Vec::new();
+    let mut label = None;
+    let mut chars = content.char_indices().peekable();
+    let mut column = 0;
+
+    while let Some((offset, c)) = chars.next() {
+        column += 1;
+        match c {
+            '^' | '-' => {
+                markers.push((column, c == '^'));
+                while chars.next_if(|&(_, next)| next == c).is_some() {
+                    column += 1;
+                }
+            }
+            // Multi-line spans draw `|`, `_` and `/` connectors around markers
+            ' ' | '\t' | '|' | '_' | '/' | '\\' => {}
+            _ if markers.is_empty() => {
+                let hanging = diagnostic.spans.iter_mut().find(|span| {
+                    span.file == file && span.line == source_line && span.column == column
+                });
+                if let Some(span) = hanging.filter(|span| span.label.is_none()) {
+                    span.label = Some(content[offset..].trim().to_string());
+                }
+                return;
+            }
+            _ => {
+                label = Some(content[offset..].trim().to_string());
+                break;
+            }
+        }
+    }
+
+    let count = markers.len();
+    for (index, (column, caret)) in markers.into_iter().enumerate() {
+        let primary = section == Section::Main && caret;
+        let label = if index + 1 == count {
+            label.take()
+        } else {
+            None
+        };
+        let existing = diagnostic
+            .spans
+            .iter_mut()
+            .find(|span| span.file == file && span.line == source_line && span.column == column);
+        match existing {
+            Some(span) => {
+                span.primary |= primary;
+                if span.label.is_none() {
+                    span.label = label;
+                }
+            }
+            None => diagnostic.spans.push(DiagnosticSpan {
+                file: file.to_string(),
+                line: source_line,
+                column,
+                primary,
+                label,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_extractors() {
+        let text = "\
+error[E0382]: borrow of moved value: `v`
+warning: this expression creates a reference which is immediately dereferenced
+  = note: `#[warn(clippy::needless_borrow)]` on by default
+  = note: `#[warn(unused_variables)]` on by default
+thread 'main' panicked at src/main.rs:4:5:
+index out of bounds
+stack backtrace:
+   0: rust_begin_unwind
+error: failed to select a version for `tokio`.
+error: could not compile `demo` (bin \"demo\") due to 1 previous error";
+
+        let registry = DiagnosticRegistry::builtin();
+        let codes: Vec<(DiagnosticKind, String)> = registry
+            .extract(text)
+            .into_iter()
+            .map(|found| (found.kind, found.code))
+            .collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                (DiagnosticKind::RustcError, "E0382".to_string()),
+                (
+                    DiagnosticKind::ClippyLint,
+                    "clippy::needless_borrow".to_string()
+                ),
+                (DiagnosticKind::RustcLint, "unused_variables".to_string()),
+                (DiagnosticKind::Panic, "src/main.rs:4:5".to_string()),
+                (DiagnosticKind::Backtrace, "stack backtrace:".to_string()),
+                (
+                    DiagnosticKind::CargoError,
+                    "failed to select a version for `tokio`.".to_string()
+                ),
+                (
+                    DiagnosticKind::CargoError,
+                    "could not compile `demo` (bin \"demo\") due to 1 previous error".to_string()
+                ),
+            ]
+        );
+        assert_eq!(registry.kinds(text).len(), DiagnosticKind::BUILTIN.len());
+        assert!(!registry.has_any("all good", &DiagnosticKind::BUILTIN));
+        assert_eq!(
+            DiagnosticKind::from_name("Clippy-Lint"),
+            DiagnosticKind::ClippyLint
+        );
+    }
+
+    #[test]
+    fn test_parse_rustc_diagnostics() {
+        let body = "\
+> ```console
+> error[E0277]: `Rc<u8>` cannot be sent between threads safely
+>    --> src/main.rs:5:19
+>     |
+> 5   |     thread::spawn(move || drop(rc));
+>     |     ------------- ^^^^^^^^^^^^^^^^ `Rc<u8>` cannot be sent between threads safely
+>     |     |
+>     |     required by a bound introduced by this call
+>     |
+>     = help: within `{closure@src/main.rs:5:19}`, the trait `Send` is not implemented
+>             for `Rc<u8>`
+> note: required by a bound in `spawn`
+>    --> /rustc/library/std/src/thread/mod.rs:675:8
+> help: consider using `Arc` instead
+>     |
+> 3   |     let rc = Arc::new(1u8);
+>     |              ~~~
+>
+> warning: unused variable: `x`
+>  --> src/lib.rs:2:9
+>   |
+> 2 |     let x = 1;
+>   |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+>   |
+>   = note: `#[warn(unused_variables)]` on by default
+>
+> error: aborting due to 1 previous error; 1 warning emitted
+> ```
+
+    error: internal compiler error: compiler/rustc_middle/src/ty/mod.rs:123: unexpected type
+";
+
+        let diagnostics = parse_rustc_diagnostics(body);
+        assert_eq!(diagnostics.len(), 3);
+
+        let error = &diagnostics[0];
+        assert_eq!(error.level, DiagnosticLevel::Error);
+        assert_eq!(error.code.as_deref(), Some("E0277"));
+        assert_eq!(
+            error.message,
+            "`Rc<u8>` cannot be sent between threads safely"
+        );
+        assert_eq!(
+            error.spans,
+            vec![
+                DiagnosticSpan {
+                    file: "src/main.rs".to_string(),
+                    line: 5,
+                    column: 19,
+                    primary: true,
+                    label: Some("`Rc<u8>` cannot be sent between threads safely".to_string()),
+                },
+                DiagnosticSpan {
+                    file: "src/main.rs".to_string(),
+                    line: 5,
+                    column: 5,
+                    primary: false,
+                    label: Some("required by a bound introduced by this call".to_string()),
+                },
+                DiagnosticSpan {
+                    file: "/rustc/library/std/src/thread/mod.rs".to_string(),
+                    line: 675,
+                    column: 8,
+                    primary: false,
+                    label: None,
+                },
+            ]
+        );
+        assert_eq!(error.notes, vec!["required by a bound in `spawn`"]);
+        assert_eq!(
+            error.help,
+            vec![
+                "within `{closure@src/main.rs:5:19}`, the trait `Send` is not implemented for `Rc<u8>`",
+                "consider using `Arc` instead",
+            ]
+        );
+
+        let warning = &diagnostics[1];
+        assert_eq!(warning.level, DiagnosticLevel::Warning);
+        assert_eq!(warning.code, None);
+        assert_eq!(warning.spans.len(), 1);
+        assert_eq!(
+            warning.primary_span().unwrap().label.as_deref(),
+            Some("help: if this is intentional, prefix it with an underscore: `_x`")
+        );
+        assert_eq!(
+            warning.notes,
+            vec!["`#[warn(unused_variables)]` on by default"]
+        );
+
+        assert_eq!(diagnostics[2].level, DiagnosticLevel::InternalCompilerError);
+        assert!(diagnostics[2].message.ends_with("unexpected type"));
+
+        // Labels hang below their markers, the leftmost one lowest
+        let stacked = parse_rustc_diagnostics(
+            "error[E0499]: cannot borrow `v` as mutable more than once at a time
+ --> src/main.rs:3:14
+  |
+3 |     let (a, b) = (&mut v, &mut v);
+  |                   ------  ^^^^^^ second mutable borrow occurs here
+  |                   |
+  |                   first mutable borrow occurs here",
+        );
+        let labels: Vec<(u32, Option<&str>)> = stacked[0]
+            .spans
+            .iter()
+            .map(|span| (span.column, span.label.as_deref()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                (14, None),
+                (19, Some("first mutable borrow occurs here")),
+                (27, Some("second mutable borrow occurs here")),
+            ]
+        );
+    }
+}
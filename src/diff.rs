@@ -0,0 +1,130 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// One line of a unified diff with its position on each side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub content: String,
+    /// Line number in the old file (None for added lines)
+    pub old_line: Option<u32>,
+    /// Line number in the new file (None for removed lines)
+    pub new_line: Option<u32>,
+}
+
+impl DiffLine {
+    pub fn is_new_side(&self) -> bool {
+        self.kind != DiffLineKind::Removed
+    }
+
+    pub fn is_old_side(&self) -> bool {
+        self.kind != DiffLineKind::Added
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_count: u32,
+    pub new_start: u32,
+    pub new_count: u32,
+    /// Text after the closing `@@`, usually the enclosing function
+    pub section: String,
+    pub lines: Vec<DiffLine>,
+}
+
+fn hunk_header_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@ ?(.*)$").unwrap())
+}
+
+/// Parse a unified diff patch (as found in `PrFile::patch` or `PrReviewComment::diff_hunk`)
+///
+/// File headers (`---`/`+++`) and `\ No newline at end of file` markers are skipped.
+pub fn parse_patch(patch: &str) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut old_line = 0;
+    let mut new_line = 0;
+
+    for raw in patch.lines() {
+        let raw = raw.trim_end_matches('\r');
+
+        if let Some(captures) = hunk_header_regex().captures(raw) {
+            let number = |i: usize, default: u32| {
+                captures
+                    .get(i)
+                    .and_then(|m| m.as_str().parse().ok())
+                    .unwrap_or(default)
+            };
+            old_line = number(1, 0);
+            new_line = number(3, 0);
+            hunks.push(DiffHunk {
+                old_start: old_line,
+                old_count: number(2, 1),
+                new_start: new_line,
+                new_count: number(4, 1),
+                section: captures.get(5).map_or("", |m| m.as_str()).to_string(),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+
+        let hunk = match hunks.last_mut() {
+            Some(hunk) => hunk,
+            None => continue,
+        };
+
+        let (kind, content) = match raw.chars().next() {
+            Some('+') => (DiffLineKind::Added, &raw[1..]),
+            Some('-') => (DiffLineKind::Removed, &raw[1..]),
+            Some(' ') => (DiffLineKind::Context, &raw[1..]),
+            // `\ No newline at end of file` and anything else that is not diff content
+            Some(_) => continue,
+            None => (DiffLineKind::Context, ""),
+        };
+
+        let line = DiffLine {
+            kind,
+            content: content.to_string(),
+            old_line: (kind != DiffLineKind::Added).then_some(old_line),
+            new_line: (kind != DiffLineKind::Removed).then_some(new_line),
+        };
+        if line.is_old_side() {
+            old_line += 1;
+        }
+        if line.is_new_side() {
+            new_line += 1;
+        }
+        hunk.lines.push(line);
+    }
+
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_patch() {
+        let patch = "@@ -10,3 +10,4 @@ fn main() {\n let a = 1;\n-let b = 2;\n+let b = 3;\n+let c = 4;\n\\ No newline at end of file\n@@ -40 +41 @@\n-x\n+y";
+        let hunks = parse_patch(patch);
+
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].section, "fn main() {");
+        assert_eq!(hunks[0].lines.len(), 4);
+        assert_eq!(hunks[0].lines[1].old_line, Some(11));
+        assert_eq!(hunks[0].lines[1].new_line, None);
+        assert_eq!(hunks[0].lines[3].new_line, Some(12));
+        assert_eq!(hunks[1].old_count, 1);
+        assert_eq!(hunks[1].lines[1].new_line, Some(41));
+    }
+}
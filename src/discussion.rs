@@ -4,6 +4,7 @@ use regex::Regex;
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT};
 use serde_json::json;
 
+use crate::bots::BotClassifier;
 use crate::config::GitHubConfig;
 use crate::error::{GitHubFetchError, Result};
 use crate::types::{Discussion, DiscussionComment, GitHubUser, Repository};
@@ -11,12 +12,24 @@ use crate::types::{Discussion, DiscussionComment, GitHubUser, Repository};
 pub struct DiscussionClient {
     client: reqwest::Client,
     config: GitHubConfig,
+    /// Comments by accounts this flags as bots are dropped
+    bots: Option<BotClassifier>,
 }
 
 impl DiscussionClient {
     pub fn new(config: GitHubConfig) -> Result<Self> {
         let client = reqwest::Client::new();
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            bots: None,
+        })
+    }
+
+    /// Drop discussion comments written by bots
+    pub fn exclude_bots(mut self, classifier: BotClassifier) -> Self {
+        self.bots = Some(classifier);
+        self
     }
 
     pub async fn fetch_discussion(
@@ -76,7 +89,12 @@ impl DiscussionClient {
 
         let response_json: serde_json::Value = response.json().await?;
 
-        self.parse_discussion_response(response_json, repo, discussion_number)
+        let mut discussion =
+            self.parse_discussion_response(response_json, repo, discussion_number)?;
+        if let Some(bots) = &self.bots {
+            bots.retain_humans(&mut discussion.comments, |comment| &comment.author);
+        }
+        Ok(discussion)
     }
 
     pub async fn fetch_discussion_by_url(&self, discussion_url: &str) -> Result<Discussion> {
@@ -116,10 +134,14 @@ impl DiscussionClient {
                 body
                 url
                 author {{
+                    __typename
                     login
+                    avatarUrl
                     ... on User {{
-                        id
-                        avatarUrl
+                        databaseId
+                    }}
+                    ... on Bot {{
+                        databaseId
                     }}
                 }}
                 createdAt
@@ -129,10 +151,14 @@ impl DiscussionClient {
                         id
                         body
                         author {{
+                            __typename
                             login
+                            avatarUrl
                             ... on User {{
-                                id
-                                avatarUrl
+                                databaseId
+                            }}
+                            ... on Bot {{
+                                databaseId
                             }}
                         }}
                         createdAt
@@ -174,15 +200,7 @@ impl DiscussionClient {
                         Some(DiscussionComment {
                             id: comment.get("id")?.as_str()?.to_string(),
                             body: comment.get("body")?.as_str()?.to_string(),
-                            author: GitHubUser {
-                                id: comment.get("author")?.get("id")?.as_str()?.parse().ok()?,
-                                login: comment.get("author")?.get("login")?.as_str()?.to_string(),
-                                avatar_url: comment
-                                    .get("author")?
-                                    .get("avatarUrl")?
-                                    .as_str()?
-                                    .to_string(),
-                            },
+                            author: parse_actor(comment.get("author")),
                             created_at: comment
                                 .get("createdAt")?
                                 .as_str()?
@@ -204,32 +222,7 @@ impl DiscussionClient {
             .and_then(|n| n.as_u64())
             .unwrap_or(discussion_number);
 
-        let author_json = discussion_json.get("author");
-        let author = if let Some(author) = author_json {
-            GitHubUser {
-                id: author
-                    .get("id")
-                    .and_then(|id| id.as_str())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0),
-                login: author
-                    .get("login")
-                    .and_then(|l| l.as_str())
-                    .unwrap_or("unknown")
-                    .to_string(),
-                avatar_url: author
-                    .get("avatarUrl")
-                    .and_then(|u| u.as_str())
-                    .unwrap_or("")
-                    .to_string(),
-            }
-        } else {
-            GitHubUser {
-                id: 0,
-                login: "unknown".to_string(),
-                avatar_url: "".to_string(),
-            }
-        };
+        let author = parse_actor(discussion_json.get("author"));
 
         Ok(Discussion {
             number,
@@ -263,3 +256,21 @@ impl DiscussionClient {
         })
     }
 }
+
+/// A GraphQL `Actor`; deleted accounts come back as `null` and become "unknown"
+fn parse_actor(actor: Option<&serde_json::Value>) -> GitHubUser {
+    let text = |key: &str| {
+        actor
+            .and_then(|actor| actor.get(key))
+            .and_then(|v| v.as_str())
+    };
+    GitHubUser {
+        id: actor
+            .and_then(|actor| actor.get("databaseId"))
+            .and_then(|id| id.as_u64())
+            .unwrap_or(0),
+        login: text("login").unwrap_or("unknown").to_string(),
+        avatar_url: text("avatarUrl").unwrap_or("").to_string(),
+        user_type: text("__typename").unwrap_or("").to_string(),
+    }
+}
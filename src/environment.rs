@@ -0,0 +1,530 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::codeblock::{extract_code_blocks, CodeLanguage};
+
+/// Release channel of a toolchain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Channel {
+    Stable,
+    Beta,
+    Nightly,
+    /// Locally built compilers, which report `-dev`
+    Dev,
+}
+
+impl Channel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Nightly => "nightly",
+            Self::Dev => "dev",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "stable" => Some(Self::Stable),
+            "beta" => Some(Self::Beta),
+            "nightly" => Some(Self::Nightly),
+            "dev" => Some(Self::Dev),
+            _ => None,
+        }
+    }
+
+    /// Channel named by a version's pre-release suffix, e.g. `1.80.0-nightly`
+    fn from_release(release: &str) -> Self {
+        match release.split_once('-').map(|(_, suffix)| suffix) {
+            Some(suffix) if suffix.starts_with("nightly") => Self::Nightly,
+            Some(suffix) if suffix.starts_with("beta") => Self::Beta,
+            Some(suffix) if suffix.starts_with("dev") => Self::Dev,
+            _ => Self::Stable,
+        }
+    }
+}
+
+/// `major.minor.patch`, ordered numerically
+///
+/// Parsing is lenient: a leading `v` or requirement operator, missing minor or
+/// patch numbers and pre-release or build suffixes are accepted, so the
+/// requirement `^1.30` reads as `1.30.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text
+            .trim()
+            .trim_start_matches(['^', '~', '=', '>', '<', ' ', 'v']);
+        let core = text.split(['-', '+', ' ', ',']).next().unwrap_or("");
+        let mut numbers = core.split('.');
+        let major = numbers.next()?.parse().ok()?;
+        let mut next = || match numbers.next() {
+            None | Some("*") | Some("x") => Some(0),
+            Some(number) => number.parse().ok(),
+        };
+        Some(Self::new(major, next()?, next()?))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text).ok_or_else(|| format!("`{}` is not a version", text))
+    }
+}
+
+/// The compiler a report was made against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustcVersion {
+    pub version: Version,
+    pub channel: Channel,
+    pub commit_hash: Option<String>,
+    /// `YYYY-MM-DD`
+    pub commit_date: Option<String>,
+}
+
+/// Environment details found in an issue body
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Environment {
+    pub rustc: Option<RustcVersion>,
+    /// Host target triple, e.g. `x86_64-unknown-linux-gnu`
+    pub host: Option<String>,
+    /// Operating system as written in the report, or derived from the host triple
+    pub os: Option<String>,
+    /// Crate name to version, from `cargo tree` output, Cargo.lock entries and
+    /// Cargo.toml dependencies; exact versions win over requirements
+    pub crates: BTreeMap<String, String>,
+}
+
+impl Environment {
+    pub fn channel(&self) -> Option<Channel> {
+        self.rustc.as_ref().map(|rustc| rustc.channel)
+    }
+
+    /// Version of `name` as far as it can be told, the lowest version for a
+    /// requirement such as `^1.30`
+    pub fn crate_version(&self, name: &str) -> Option<Version> {
+        let normalized = name.replace('_', "-");
+        self.crates
+            .iter()
+            .find(|(krate, _)| krate.replace('_', "-").eq_ignore_ascii_case(&normalized))
+            .and_then(|(_, version)| Version::parse(version))
+    }
+}
+
+/// Comparison between a reported version and a bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VersionOp {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl VersionOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+        }
+    }
+
+    pub fn compare(&self, version: Version, bound: Version) -> bool {
+        match self {
+            Self::Eq => version == bound,
+            Self::Gt => version > bound,
+            Self::Ge => version >= bound,
+            Self::Lt => version < bound,
+            Self::Le => version <= bound,
+        }
+    }
+}
+
+/// A reported crate version must satisfy `op version`, e.g. `tokio >= 1.30`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateVersionReq {
+    pub name: String,
+    pub op: VersionOp,
+    pub version: Version,
+}
+
+impl CrateVersionReq {
+    pub fn new(name: impl Into<String>, op: VersionOp, version: Version) -> Self {
+        Self {
+            name: name.into(),
+            op,
+            version,
+        }
+    }
+
+    /// Whether the environment reports a version of the crate that satisfies
+    /// the bound; reports without the crate never do
+    pub fn matches(&self, environment: &Environment) -> bool {
+        environment
+            .crate_version(&self.name)
+            .is_some_and(|version| self.op.compare(version, self.version))
+    }
+}
+
+impl fmt::Display for CrateVersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.name, self.op.symbol(), self.version)
+    }
+}
+
+impl FromStr for CrateVersionReq {
+    type Err = String;
+
+    /// `tokio>=1.30`, `serde=1.0.200`, `hyper<1`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let split = text
+            .find(['=', '>', '<'])
+            .ok_or_else(|| format!("Expected a bound like `tokio>=1.30`, got `{}`", text))?;
+        let (name, bound) = text.split_at(split);
+        let (op, version) = [
+            (">=", VersionOp::Ge),
+            ("<=", VersionOp::Le),
+            (">", VersionOp::Gt),
+            ("<", VersionOp::Lt),
+            ("=", VersionOp::Eq),
+        ]
+        .iter()
+        .find_map(|(symbol, op)| bound.strip_prefix(symbol).map(|rest| (*op, rest)))
+        .unwrap();
+        if name.trim().is_empty() {
+            return Err(format!("Missing crate name in `{}`", text));
+        }
+        Ok(Self::new(name.trim(), op, version.parse()?))
+    }
+}
+
+macro_rules! static_regex {
+    ($name:ident, $pattern:expr) => {
+        fn $name() -> &'static Regex {
+            static REGEX: OnceLock<Regex> = OnceLock::new();
+            REGEX.get_or_init(|| Regex::new($pattern).unwrap())
+        }
+    };
+}
+
+static_regex!(
+    rustc_regex,
+    r"\brustc (\d+\.\d+\.\d+(?:-[\w.]+)?)(?: \(([0-9a-f]{7,40}) (\d{4}-\d{2}-\d{2})\))?"
+);
+static_regex!(
+    release_regex,
+    r"(?m)^\s*release: (\d+\.\d+\.\d+(?:-[\w.]+)?)\s*$"
+);
+static_regex!(
+    commit_hash_regex,
+    r"(?m)^\s*commit-hash: ([0-9a-f]{7,40})\s*$"
+);
+static_regex!(
+    commit_date_regex,
+    r"(?m)^\s*commit-date: (\d{4}-\d{2}-\d{2})\s*$"
+);
+static_regex!(
+    host_regex,
+    r"(?m)^\s*(?:host|target)\s*[:=]\s*`?([a-z0-9_]+-[a-z0-9_]+-[a-z0-9_]+(?:-[a-z0-9_]+)?)`?\s*$"
+);
+static_regex!(
+    os_regex,
+    r"(?im)^[\s>*_\-|]*(?:os|operating system|platform)(?:\s*version)?[\s*_]*[:=|][\s*_]*([^\n|]+?)[\s*_|]*$"
+);
+static_regex!(
+    tree_regex,
+    r"(?m)(?:^|[\s│├└─|`\-]+)([A-Za-z0-9_][A-Za-z0-9_\-]*) v(\d+\.\d+\.\d+(?:[-+][\w.\-+]+)?)(?:\s|$)"
+);
+static_regex!(
+    toml_section_regex,
+    r"^\[([A-Za-z0-9_.\-\x22' ]+)\]$|^\[\[([A-Za-z0-9_.\-]+)\]\]$"
+);
+static_regex!(
+    dependency_regex,
+    r#"^([A-Za-z0-9_\-]+)\s*=\s*(?:"([^"]+)"|\{(.*)\})"#
+);
+static_regex!(inline_version_regex, r#"\bversion\s*=\s*"([^"]+)""#);
+static_regex!(toml_string_regex, r#"^(name|version)\s*=\s*"([^"]+)""#);
+
+/// Pull the rustc version, host, OS and crate versions out of an issue body
+///
+/// Recognizes `rustc --version --verbose` output (or just its first line),
+/// `OS:` lines, `cargo tree` lines such as `├── tokio v1.36.0`, and Cargo.toml
+/// or Cargo.lock fragments in TOML code blocks.
+///
+/// ````
+/// use github_fetch::environment::{extract_environment, Channel, Version};
+///
+/// let body = "\
+/// **OS**: Ubuntu 22.04
+///
+/// ```
+/// rustc 1.80.0-nightly (ada5e2c7b 2024-05-31)
+/// binary: rustc
+/// host: x86_64-unknown-linux-gnu
+/// ```
+///
+/// ```toml
+/// [dependencies]
+/// tokio = { version = \"1.36\", features = [\"full\"] }
+/// ```";
+///
+/// let environment = extract_environment(body);
+/// let rustc = environment.rustc.as_ref().unwrap();
+/// assert_eq!(rustc.version, Version::new(1, 80, 0));
+/// assert_eq!(rustc.channel, Channel::Nightly);
+/// assert_eq!(rustc.commit_date.as_deref(), Some("2024-05-31"));
+/// assert_eq!(environment.host.as_deref(), Some("x86_64-unknown-linux-gnu"));
+/// assert_eq!(environment.os.as_deref(), Some("Ubuntu 22.04"));
+/// assert_eq!(environment.crate_version("tokio"), Some(Version::new(1, 36, 0)));
+/// ````
+pub fn extract_environment(body: &str) -> Environment {
+    let mut environment = Environment {
+        rustc: extract_rustc(body),
+        host: host_regex()
+            .captures(body)
+            .map(|captures| captures[1].to_string()),
+        os: os_regex()
+            .captures(body)
+            .map(|captures| captures[1].trim().to_string())
+            .filter(|os| !os.is_empty()),
+        crates: BTreeMap::new(),
+    };
+    if environment.os.is_none() {
+        environment.os = environment.host.as_deref().and_then(os_from_triple);
+    }
+
+    for block in extract_code_blocks(body) {
+        if block.language == CodeLanguage::Toml {
+            collect_toml_crates(&block.code, &mut environment.crates);
+        }
+    }
+    // Exact versions from `cargo tree` replace Cargo.toml requirements
+    for captures in tree_regex().captures_iter(body) {
+        environment
+            .crates
+            .insert(captures[1].to_string(), captures[2].to_string());
+    }
+
+    environment
+}
+
+/// The rustc version reported by `rustc --version` output in `body`
+pub(crate) fn extract_rustc(body: &str) -> Option<RustcVersion> {
+    let first_line = rustc_regex().captures(body);
+    let release = release_regex()
+        .captures(body)
+        .map(|captures| captures[1].to_string())
+        .or_else(|| first_line.as_ref().map(|captures| captures[1].to_string()))?;
+    let verbose = |regex: &Regex| regex.captures(body).map(|captures| captures[1].to_string());
+    let from_first_line = |index: usize| {
+        first_line
+            .as_ref()
+            .and_then(|captures| captures.get(index))
+            .map(|m| m.as_str().to_string())
+    };
+
+    Some(RustcVersion {
+        version: Version::parse(&release)?,
+        channel: Channel::from_release(&release),
+        commit_hash: verbose(commit_hash_regex()).or_else(|| from_first_line(2)),
+        commit_date: verbose(commit_date_regex()).or_else(|| from_first_line(3)),
+    })
+}
+
+fn os_from_triple(triple: &str) -> Option<String> {
+    let os = if triple.contains("linux") {
+        "Linux"
+    } else if triple.contains("apple-darwin") {
+        "macOS"
+    } else if triple.contains("windows") {
+        "Windows"
+    } else if triple.contains("freebsd") {
+        "FreeBSD"
+    } else {
+        return None;
+    };
+    Some(os.to_string())
+}
+
+/// Dependencies from a Cargo.toml fragment and packages from a Cargo.lock one;
+/// existing entries are kept
+fn collect_toml_crates(toml: &str, crates: &mut BTreeMap<String, String>) {
+    let mut section = String::new();
+    // `[dependencies.tokio]` or `[[package]]` tables gather name and version over several lines
+    let mut table_name: Option<String> = None;
+    let mut table_version: Option<String> = None;
+
+    for line in toml.lines() {
+        let line = line.trim();
+        if let Some(captures) = toml_section_regex().captures(line) {
+            flush(&mut table_name, &mut table_version, crates);
+            section = captures
+                .get(1)
+                .or_else(|| captures.get(2))
+                .map_or(String::new(), |m| m.as_str().trim().to_string());
+            if let Some((_, name)) = section.rsplit_once("dependencies.") {
+                table_name = Some(name.trim_matches(['"', '\'']).to_string());
+            }
+            continue;
+        }
+
+        if section == "package" {
+            if let Some(captures) = toml_string_regex().captures(line) {
+                match &captures[1] {
+                    "name" => table_name = Some(captures[2].to_string()),
+                    _ => table_version = Some(captures[2].to_string()),
+                }
+            }
+        } else if section.contains("dependencies.") {
+            if let Some(captures) = inline_version_regex().captures(line) {
+                table_version = Some(captures[1].to_string());
+            }
+        } else if section.ends_with("dependencies") {
+            let Some(captures) = dependency_regex().captures(line) else {
+                continue;
+            };
+            let version = captures.get(2).map(|m| m.as_str().to_string()).or_else(|| {
+                captures
+                    .get(3)
+                    .and_then(|table| inline_version_regex().captures(table.as_str()))
+                    .map(|version| version[1].to_string())
+            });
+            if let Some(version) = version {
+                crates.entry(captures[1].to_string()).or_insert(version);
+            }
+        }
+    }
+    flush(&mut table_name, &mut table_version, crates);
+}
+
+fn flush(
+    name: &mut Option<String>,
+    version: &mut Option<String>,
+    crates: &mut BTreeMap<String, String>,
+) {
+    if let (Some(name), Some(version)) = (name.take(), version.take()) {
+        crates.entry(name).or_insert(version);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_environment() {
+        let body = "\
+### Meta
+
+`rustc --version --verbose`:
+```
+rustc 1.79.0 (129f3b996 2024-06-10)
+binary: rustc
+commit-hash: 129f3b9964af4d4a709d1383930ade12dfe7c081
+commit-date: 2024-06-10
+host: aarch64-apple-darwin
+release: 1.79.0
+LLVM version: 18.1.7
+```
+
+```toml
+[dependencies]
+serde = \"1.0\"
+hyper = { version = \"0.14.28\", features = [\"full\"] }
+local = { path = \"../local\" }
+
+[dependencies.tokio]
+version = \"1.30\"
+features = [\"rt\"]
+```
+
+```
+$ cargo tree -i tokio
+tokio v1.38.0
+└── my-app v0.1.0 (/home/me/my-app)
+```
+
+```toml
+[[package]]
+name = \"bytes\"
+version = \"1.6.0\"
+```
+";
+
+        let environment = extract_environment(body);
+        assert_eq!(
+            environment.rustc,
+            Some(RustcVersion {
+                version: Version::new(1, 79, 0),
+                channel: Channel::Stable,
+                commit_hash: Some("129f3b9964af4d4a709d1383930ade12dfe7c081".to_string()),
+                commit_date: Some("2024-06-10".to_string()),
+            })
+        );
+        assert_eq!(environment.os.as_deref(), Some("macOS"));
+        let crates: Vec<(&str, &str)> = environment
+            .crates
+            .iter()
+            .map(|(name, version)| (name.as_str(), version.as_str()))
+            .collect();
+        assert_eq!(
+            crates,
+            vec![
+                ("bytes", "1.6.0"),
+                ("hyper", "0.14.28"),
+                ("my-app", "0.1.0"),
+                ("serde", "1.0"),
+                ("tokio", "1.38.0"),
+            ]
+        );
+
+        let req: CrateVersionReq = "tokio>=1.30".parse().unwrap();
+        assert!(req.matches(&environment));
+        assert!(!"hyper>=1"
+            .parse::<CrateVersionReq>()
+            .unwrap()
+            .matches(&environment));
+        assert!(!"axum>0"
+            .parse::<CrateVersionReq>()
+            .unwrap()
+            .matches(&environment));
+        assert_eq!(req.to_string(), "tokio>=1.30.0");
+        assert!("tokio".parse::<CrateVersionReq>().is_err());
+
+        let beta =
+            extract_environment("rustc 1.81.0-beta.3 (4a765c0a8 2024-07-26)\nOS: Windows 11");
+        assert_eq!(beta.channel(), Some(Channel::Beta));
+        assert_eq!(beta.os.as_deref(), Some("Windows 11"));
+        assert_eq!(Version::parse("^0.3"), Some(Version::new(0, 3, 0)));
+    }
+}
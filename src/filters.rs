@@ -1,8 +1,22 @@
 use chrono::{DateTime, Utc};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::sync::Arc;
 
-use crate::types::GitHubIssue;
+use crate::bots::BotClassifier;
+use crate::codeblock::extract_code_blocks;
+use crate::diagnostics::{
+    DiagnosticExtractor, DiagnosticKind, DiagnosticRegistry, RustcErrorCodes,
+};
+use crate::environment::{Channel, CrateVersionReq};
+use crate::rules::{
+    AllOf, AssignedTo, AuthorAssociationIn, AuthorIsBot, AuthoredBy, BoxedFilter,
+    ContainsAnyKeyword, CrateVersionMatches, DateWithin, HasAnyLabel, HasCodeBlocks,
+    HasDiagnostics, HasRustErrorCodes, HasSelfContainedRepro, InMilestone, IsPullRequest,
+    IssueFilter, KeywordFilter, MinBodyLength, MinComments, MinReactions, OnChannel, State,
+};
+use crate::types::{DateField, GitHubIssue};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum IssueState {
@@ -24,6 +38,48 @@ pub struct IssueFilters {
     pub min_comments: Option<u32>,
     pub required_keywords: Vec<String>,
     pub excluded_keywords: Vec<String>,
+    /// Keyword rules with explicit modes and scopes; all of them must hold
+    #[serde(default)]
+    pub keyword_rules: Vec<KeywordRule>,
+    /// Login of the issue's author
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Login of one of the issue's assignees
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Milestone title
+    #[serde(default)]
+    pub milestone: Option<String>,
+    #[serde(default)]
+    pub min_reactions: Option<u32>,
+    /// Accepted `author_association` values such as `MEMBER`; any when empty
+    #[serde(default)]
+    pub author_associations: Vec<String>,
+    /// Timestamp `date_range` applies to
+    #[serde(default)]
+    pub date_field: DateField,
+    /// Order of the collected issues
+    #[serde(default)]
+    pub sort: IssueSort,
+    /// Skip issues opened by accounts this classifier flags as bots
+    #[serde(default)]
+    pub exclude_bots: Option<BotClassifier>,
+    /// Title or body must hold a diagnostic of at least one of these kinds
+    #[serde(default)]
+    pub diagnostic_kinds: Vec<DiagnosticKind>,
+    /// Extractors `diagnostic_kinds` are looked up in; the built-in ones when
+    /// unset, so custom kinds need a registry that holds them
+    #[serde(skip)]
+    pub diagnostic_registry: Option<Arc<DiagnosticRegistry>>,
+    /// Body must hold a Rust code block that is a complete program
+    #[serde(default)]
+    pub repro_only: bool,
+    /// Release channel of the rustc version the body reports
+    #[serde(default)]
+    pub channel: Option<Channel>,
+    /// Crate versions the body reports must satisfy all of these
+    #[serde(default)]
+    pub crate_versions: Vec<CrateVersionReq>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +88,236 @@ pub struct DateRange {
     pub end: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SortField {
+    Created,
+    Updated,
+    Comments,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// How collected issues are ordered; most recently updated first by default
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssueSort {
+    pub field: SortField,
+    pub direction: SortDirection,
+}
+
+impl IssueSort {
+    pub fn new(field: SortField, direction: SortDirection) -> Self {
+        Self { field, direction }
+    }
+
+    /// Compare two issues in this order, breaking ties by number
+    pub fn compare(&self, a: &GitHubIssue, b: &GitHubIssue) -> Ordering {
+        let ordering = match self.field {
+            SortField::Created => a.created_at.cmp(&b.created_at),
+            SortField::Updated => a.updated_at.cmp(&b.updated_at),
+            SortField::Comments => a.comments.cmp(&b.comments),
+        }
+        .then(a.number.cmp(&b.number));
+        match self.direction {
+            SortDirection::Ascending => ordering,
+            SortDirection::Descending => ordering.reverse(),
+        }
+    }
+
+    /// `sort` and `direction` parameters of the issue list API
+    pub fn api_params(&self) -> (&'static str, &'static str) {
+        let field = match self.field {
+            SortField::Created => "created",
+            SortField::Updated => "updated",
+            SortField::Comments => "comments",
+        };
+        let direction = match self.direction {
+            SortDirection::Ascending => "asc",
+            SortDirection::Descending => "desc",
+        };
+        (field, direction)
+    }
+}
+
+impl Default for IssueSort {
+    fn default() -> Self {
+        Self::new(SortField::Updated, SortDirection::Descending)
+    }
+}
+
+/// Whether a keyword rule needs one or every keyword to appear
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum KeywordMode {
+    #[default]
+    Any,
+    All,
+}
+
+/// How a single keyword is looked for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum KeywordMatch {
+    #[default]
+    Substring,
+    /// The keyword must not be part of a longer word
+    WholeWord,
+    /// The keyword is a regular expression
+    Regex,
+}
+
+/// Parts of an issue a keyword rule searches
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct KeywordScope {
+    pub title: bool,
+    pub body: bool,
+    /// Needs one extra request per candidate issue
+    pub comments: bool,
+}
+
+impl KeywordScope {
+    pub const NONE: Self = Self {
+        title: false,
+        body: false,
+        comments: false,
+    };
+    pub const TITLE: Self = Self {
+        title: true,
+        body: false,
+        comments: false,
+    };
+    pub const BODY: Self = Self {
+        title: false,
+        body: true,
+        comments: false,
+    };
+    pub const TITLE_AND_BODY: Self = Self {
+        title: true,
+        body: true,
+        comments: false,
+    };
+    pub const ALL: Self = Self {
+        title: true,
+        body: true,
+        comments: true,
+    };
+}
+
+impl Default for KeywordScope {
+    fn default() -> Self {
+        Self::TITLE_AND_BODY
+    }
+}
+
+/// A keyword requirement, or exclusion, with its matching options
+///
+/// ```
+/// use github_fetch::{KeywordRule, KeywordScope};
+///
+/// // Drop issues whose title calls them a tracking issue, but keep those
+/// // that only mention tracking in passing
+/// let rule = KeywordRule::any(["tracking issue"])
+///     .whole_word()
+///     .scope(KeywordScope::TITLE)
+///     .excluding();
+/// assert!(rule.exclude);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct KeywordRule {
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub mode: KeywordMode,
+    #[serde(default)]
+    pub matching: KeywordMatch,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub scope: KeywordScope,
+    /// Ignore text inside code blocks, inline code and quotes
+    #[serde(default)]
+    pub prose_only: bool,
+    /// The rule holds when the keywords do not match
+    #[serde(default)]
+    pub exclude: bool,
+}
+
+impl KeywordRule {
+    /// Rule requiring at least one of `keywords`
+    pub fn any<S: Into<String>>(keywords: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            keywords: keywords.into_iter().map(Into::into).collect(),
+            mode: KeywordMode::Any,
+            matching: KeywordMatch::Substring,
+            case_sensitive: false,
+            scope: KeywordScope::default(),
+            prose_only: false,
+            exclude: false,
+        }
+    }
+
+    /// Rule requiring every one of `keywords`
+    pub fn all<S: Into<String>>(keywords: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            mode: KeywordMode::All,
+            ..Self::any(keywords)
+        }
+    }
+
+    pub fn whole_word(mut self) -> Self {
+        self.matching = KeywordMatch::WholeWord;
+        self
+    }
+
+    /// Treat the keywords as regular expressions; check them with `validate`
+    pub fn regex(mut self) -> Self {
+        self.matching = KeywordMatch::Regex;
+        self
+    }
+
+    pub fn case_sensitive(mut self) -> Self {
+        self.case_sensitive = true;
+        self
+    }
+
+    pub fn scope(mut self, scope: KeywordScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn prose_only(mut self) -> Self {
+        self.prose_only = true;
+        self
+    }
+
+    /// Invert the rule so matching issues are rejected
+    pub fn excluding(mut self) -> Self {
+        self.exclude = true;
+        self
+    }
+
+    /// Compile one keyword with this rule's matching options
+    pub fn pattern(&self, keyword: &str) -> Result<Regex, regex::Error> {
+        let pattern = match self.matching {
+            KeywordMatch::Substring => regex::escape(keyword),
+            KeywordMatch::WholeWord => format!(r"(?:^|\W){}(?:\W|$)", regex::escape(keyword)),
+            KeywordMatch::Regex => keyword.to_string(),
+        };
+        if self.case_sensitive {
+            Regex::new(&pattern)
+        } else {
+            Regex::new(&format!("(?i){}", pattern))
+        }
+    }
+
+    /// Check that every keyword compiles, which can only fail for regex rules
+    pub fn validate(&self) -> Result<(), regex::Error> {
+        self.keywords
+            .iter()
+            .try_for_each(|keyword| self.pattern(keyword).map(|_| ()))
+    }
+}
+
 impl Default for IssueFilters {
     fn default() -> Self {
         Self {
@@ -50,11 +336,24 @@ impl Default for IssueFilters {
             include_pull_requests: false,
             min_comments: None,
             required_keywords: vec![],
-            excluded_keywords: vec![
-                "discussion".to_string(),
-                "RFC".to_string(),
-                "tracking".to_string(),
-            ],
+            excluded_keywords: vec![],
+            keyword_rules: vec![KeywordRule::any(["discussion", "RFC", "tracking"])
+                .whole_word()
+                .scope(KeywordScope::TITLE)
+                .excluding()],
+            author: None,
+            assignee: None,
+            milestone: None,
+            min_reactions: None,
+            author_associations: vec![],
+            date_field: DateField::Created,
+            sort: IssueSort::default(),
+            exclude_bots: None,
+            diagnostic_kinds: vec![],
+            diagnostic_registry: None,
+            repro_only: false,
+            channel: None,
+            crate_versions: vec![],
         }
     }
 }
@@ -78,124 +377,191 @@ impl IssueFilters {
     }
 
     pub fn matches(&self, issue: &GitHubIssue) -> bool {
-        match self.state {
-            IssueState::Open => {
-                if issue.state != "Open" {
-                    return false;
-                }
-            }
-            IssueState::Closed => {
-                if issue.state != "Closed" {
-                    return false;
-                }
-            }
-            IssueState::All => {}
-        }
+        self.compile().matches(issue)
+    }
 
-        if !self.include_pull_requests && issue.is_pull_request {
-            return false;
+    /// Like `matches`, but names the first rule the issue fails
+    pub fn match_explain(&self, issue: &GitHubIssue) -> Result<(), Rejection> {
+        match self.compile().explain(issue) {
+            Some(rule) => Err(Rejection { rule }),
+            None => Ok(()),
         }
+    }
 
+    /// Keyword rules that search comments, which `compile` leaves out
+    pub fn comment_rules(&self) -> Vec<KeywordFilter> {
+        self.keyword_rules
+            .iter()
+            .filter(|rule| rule.scope.comments)
+            .map(|rule| KeywordFilter::new(rule.clone()))
+            .collect()
+    }
+
+    /// Build the filter tree these settings stand for, one rule per active setting
+    ///
+    /// Keyword rules that search comments are not included, since an issue
+    /// alone does not carry its comments; see `comment_rules`.
+    pub fn compile(&self) -> AllOf {
+        let mut rules: Vec<BoxedFilter> = Vec::new();
+
+        if self.state != IssueState::All {
+            rules.push(State(self.state.clone()).boxed());
+        }
+        if !self.include_pull_requests {
+            rules.push(IsPullRequest.not().boxed());
+        }
         if !self.include_labels.is_empty() {
-            let has_included_label = issue.labels.iter().any(|label| {
-                self.include_labels
-                    .iter()
-                    .any(|include_label| include_label.to_lowercase() == label.name.to_lowercase())
-            });
-            if !has_included_label {
-                return false;
-            }
+            rules.push(HasAnyLabel(self.include_labels.clone()).boxed());
         }
-
-        if issue.labels.iter().any(|label| {
-            self.exclude_labels
-                .iter()
-                .any(|exclude_label| exclude_label.to_lowercase() == label.name.to_lowercase())
-        }) {
-            return false;
+        if !self.exclude_labels.is_empty() {
+            rules.push(HasAnyLabel(self.exclude_labels.clone()).not().boxed());
         }
-
         if let Some(min_length) = self.min_body_length {
-            if issue.body.as_ref().map_or(0, |b| b.len()) < min_length {
-                return false;
-            }
+            rules.push(MinBodyLength(min_length).boxed());
         }
-
         if let Some(min_comments) = self.min_comments {
-            if issue.comments < min_comments {
-                return false;
-            }
+            rules.push(MinComments(min_comments).boxed());
+        }
+        if let Some(min_reactions) = self.min_reactions {
+            rules.push(MinReactions(min_reactions).boxed());
+        }
+        if let Some(login) = &self.author {
+            rules.push(AuthoredBy(login.clone()).boxed());
+        }
+        if let Some(classifier) = &self.exclude_bots {
+            rules.push(AuthorIsBot(classifier.clone()).not().boxed());
+        }
+        if let Some(login) = &self.assignee {
+            rules.push(AssignedTo(login.clone()).boxed());
+        }
+        if let Some(milestone) = &self.milestone {
+            rules.push(InMilestone(milestone.clone()).boxed());
+        }
+        if !self.author_associations.is_empty() {
+            rules.push(AuthorAssociationIn(self.author_associations.clone()).boxed());
         }
-
         if let Some(date_range) = &self.date_range {
-            if let Some(start) = date_range.start {
-                if issue.created_at < start {
-                    return false;
-                }
-            }
-            if let Some(end) = date_range.end {
-                if issue.created_at > end {
-                    return false;
-                }
-            }
+            rules.push(DateWithin(self.date_field, date_range.clone()).boxed());
         }
-
-        let content =
-            format!("{} {}", issue.title, issue.body.as_deref().unwrap_or("")).to_lowercase();
-
         if !self.required_keywords.is_empty() {
-            if !self
-                .required_keywords
-                .iter()
-                .any(|keyword| content.contains(&keyword.to_lowercase()))
-            {
-                return false;
-            }
+            rules.push(ContainsAnyKeyword(self.required_keywords.clone()).boxed());
         }
-
-        if self
-            .excluded_keywords
+        if !self.excluded_keywords.is_empty() {
+            rules.push(
+                ContainsAnyKeyword(self.excluded_keywords.clone())
+                    .not()
+                    .boxed(),
+            );
+        }
+        for rule in self
+            .keyword_rules
             .iter()
-            .any(|keyword| content.contains(&keyword.to_lowercase()))
+            .filter(|rule| !rule.scope.comments)
         {
-            return false;
+            rules.push(KeywordFilter::new(rule.clone()).boxed());
         }
-
         if self.rust_errors_only {
-            if !has_rust_error_codes(&content) {
-                return false;
-            }
+            rules.push(HasRustErrorCodes.boxed());
+        }
+        if !self.diagnostic_kinds.is_empty() {
+            let registry = self
+                .diagnostic_registry
+                .clone()
+                .unwrap_or_else(DiagnosticRegistry::shared);
+            rules.push(
+                HasDiagnostics::with_registry(self.diagnostic_kinds.clone(), registry).boxed(),
+            );
         }
-
         if self.code_blocks_only {
-            if !has_code_blocks(issue.body.as_deref().unwrap_or("")) {
-                return false;
-            }
+            rules.push(HasCodeBlocks.boxed());
+        }
+        if self.repro_only {
+            rules.push(HasSelfContainedRepro.boxed());
         }
+        if let Some(channel) = self.channel {
+            rules.push(OnChannel(channel).boxed());
+        }
+        for requirement in &self.crate_versions {
+            rules.push(CrateVersionMatches(requirement.clone()).boxed());
+        }
+
+        AllOf(rules)
+    }
+}
+
+impl IssueFilter for IssueFilters {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        IssueFilters::matches(self, issue)
+    }
+
+    fn describe(&self) -> String {
+        self.compile().describe()
+    }
 
-        true
+    fn explain(&self, issue: &GitHubIssue) -> Option<String> {
+        self.compile().explain(issue)
+    }
+}
+
+/// The rule that rejected an issue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rejection {
+    pub rule: String,
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rejected by {}", self.rule)
     }
 }
 
 pub fn has_rust_error_codes(text: &str) -> bool {
-    let error_regex = Regex::new(r"E0\d{3,4}").unwrap();
-    error_regex.is_match(text)
+    RustcErrorCodes.detect(text)
 }
 
 pub fn has_code_blocks(text: &str) -> bool {
-    if text.contains("```") {
-        return true;
+    !extract_code_blocks(text).is_empty()
+}
+
+/// Markdown with fenced and indented code, inline code spans and quotes removed
+pub fn prose_only(markdown: &str) -> String {
+    let mut prose = String::with_capacity(markdown.len());
+    let blocks = extract_code_blocks(markdown);
+    let mut offset = 0;
+
+    for raw in markdown.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        if blocks
+            .iter()
+            .any(|block| block.start <= start && start < block.end)
+        {
+            continue;
+        }
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if line.trim_start().starts_with('>') {
+            continue;
+        }
+
+        // Odd segments between backticks are inline code
+        for (index, segment) in line.split('`').enumerate() {
+            if index % 2 == 0 {
+                prose.push_str(segment);
+            } else {
+                prose.push(' ');
+            }
+        }
+        prose.push('\n');
     }
 
-    text.lines()
-        .any(|line| line.starts_with("    ") && !line.trim().is_empty())
+    prose
 }
 
 pub fn extract_error_codes(text: &str) -> Vec<String> {
-    let error_regex = Regex::new(r"E0\d{3,4}").unwrap();
-    error_regex
-        .find_iter(text)
-        .map(|m| m.as_str().to_string())
+    RustcErrorCodes
+        .extract(text)
+        .into_iter()
+        .map(|found| found.code)
         .collect::<std::collections::HashSet<_>>()
         .into_iter()
         .collect()
@@ -204,6 +570,7 @@ pub fn extract_error_codes(text: &str) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures;
 
     #[test]
     fn test_rust_error_detection() {
@@ -222,5 +589,72 @@ mod tests {
         assert!(has_code_blocks("```rust\nfn main() {}\n```"));
         assert!(has_code_blocks("    let x = 5;\n    println!(\"{}\", x);"));
         assert!(!has_code_blocks("Just regular text without code"));
+        assert!(!has_code_blocks(
+            "- a list item\n\n    continued inside the item"
+        ));
+    }
+
+    #[test]
+    fn test_prose_only() {
+        let markdown = "Tracking down a crash:\n\n```rust\nlet tracking = 1;\n```\n\n\
+                        > tracking issue #123\n\nUse `tracking` here.\n\n    tracking();\n";
+        let prose = prose_only(markdown);
+
+        assert_eq!(prose.matches("racking").count(), 1);
+        assert!(prose.contains("Tracking down a crash:"));
+        assert!(prose.contains("Use   here."));
+
+        // A shorter fence inside a longer one does not close it
+        let nested = "Before\n\n````md\n```rust\nfn inner() {}\n```\ntracking\n````\n\nAfter\n";
+        assert_eq!(prose_only(nested), "Before\n\n\nAfter\n");
+    }
+
+    #[test]
+    fn test_match_explain() {
+        let mut issue = GitHubIssue {
+            title: "Tracking issue for async closures".to_string(),
+            body: Some("x".repeat(80)),
+            ..fixtures::issue(1)
+        };
+        let filters = IssueFilters::default();
+
+        assert_eq!(
+            filters.match_explain(&issue),
+            Err(Rejection {
+                rule: "not keyword in [discussion, RFC, tracking] (whole word, title)".to_string()
+            })
+        );
+
+        // Mentioning a keyword in passing, or as part of another word, is fine
+        issue.title = "Async closures ICE when tracking_caller is set".to_string();
+        issue.body = Some(format!(
+            "Found while working on the tracking issue. {}",
+            "x".repeat(40)
+        ));
+        assert_eq!(filters.match_explain(&issue), Ok(()));
+
+        issue.title = "Async closures ICE".to_string();
+        assert_eq!(filters.match_explain(&issue), Ok(()));
+
+        issue.body = Some("short".to_string());
+        assert_eq!(
+            filters.match_explain(&issue).unwrap_err().rule,
+            "body length >= 50"
+        );
+
+        // Custom diagnostic kinds match through the registry that knows them
+        issue.body = Some(format!(
+            "Miri reports:\nerror: Undefined Behavior: out-of-bounds read\n{}",
+            "x".repeat(40)
+        ));
+        let mut filters: IssueFilters = "diag:miri-ub".parse().unwrap();
+        assert!(!filters.matches(&issue));
+        let mut registry = DiagnosticRegistry::builtin();
+        registry.register(
+            crate::diagnostics::RegexExtractor::custom("miri-ub", r"Undefined Behavior: ([^\n]+)")
+                .unwrap(),
+        );
+        filters.diagnostic_registry = Some(Arc::new(registry));
+        assert!(filters.matches(&issue));
     }
 }
@@ -0,0 +1,86 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::types::{GitHubIssue, GitHubLabel, GitHubUser, PrReviewComment};
+
+/// 2024-05-01 12:00 UTC, when every built item was created
+pub(crate) fn timestamp() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+}
+
+pub(crate) fn user(login: &str) -> GitHubUser {
+    GitHubUser {
+        id: 1,
+        login: login.to_string(),
+        avatar_url: String::new(),
+        user_type: "User".to_string(),
+    }
+}
+
+pub(crate) fn label(name: &str) -> GitHubLabel {
+    GitHubLabel {
+        id: 0,
+        name: name.to_string(),
+        color: String::new(),
+        description: None,
+    }
+}
+
+/// An open issue by `alice` without labels, body or comments
+pub(crate) fn issue(number: u64) -> GitHubIssue {
+    GitHubIssue {
+        id: number,
+        number,
+        title: format!("Issue {}", number),
+        body: None,
+        state: "Open".to_string(),
+        labels: vec![],
+        user: user("alice"),
+        assignees: vec![],
+        created_at: timestamp(),
+        updated_at: timestamp(),
+        closed_at: None,
+        merged_at: None,
+        html_url: format!("https://github.com/o/r/issues/{}", number),
+        is_pull_request: false,
+        comments: 0,
+        milestone: None,
+        author_association: "NONE".to_string(),
+        reactions: Some(0),
+    }
+}
+
+/// An open pull request by `alice`
+pub(crate) fn pull_request(number: u64) -> GitHubIssue {
+    GitHubIssue {
+        html_url: format!("https://github.com/o/r/pull/{}", number),
+        is_pull_request: true,
+        ..issue(number)
+    }
+}
+
+/// A `nit` from `reviewer` on the new side of line 12 of `src/lib.rs`
+pub(crate) fn review_comment(id: u64) -> PrReviewComment {
+    PrReviewComment {
+        id,
+        review_id: None,
+        user: user("reviewer"),
+        body: "nit".to_string(),
+        path: "src/lib.rs".to_string(),
+        line: Some(12),
+        original_line: Some(12),
+        start_line: None,
+        original_start_line: None,
+        diff_hunk: String::new(),
+        side: Some("RIGHT".to_string()),
+        start_side: None,
+        subject_type: Some("line".to_string()),
+        author_association: "NONE".to_string(),
+        commit_id: None,
+        original_commit_id: None,
+        created_at: timestamp(),
+        updated_at: timestamp(),
+        html_url: String::new(),
+        position: None,
+        in_reply_to_id: None,
+    }
+}
@@ -1,22 +1,88 @@
+pub mod bots;
+pub mod bundle;
+pub mod changelog;
 pub mod client;
+pub mod codeblock;
 pub mod config;
+pub mod context;
+pub mod diagnostics;
+pub mod diff;
 pub mod discussion;
+pub mod environment;
 pub mod error;
 pub mod filters;
+pub mod markdown;
+pub mod plan;
+pub mod query;
+pub mod relocation;
+pub mod review;
+pub mod rules;
+pub mod search;
+pub mod snippet;
+pub mod suggestion;
+pub mod tree;
 pub mod types;
 
+/// Builders for the GitHub types tests need; override fields with `..issue(1)` and the like
+#[cfg(test)]
+mod fixtures;
+
+pub use bots::BotClassifier;
+pub use bundle::{BundleError, BundlePart, PrBundle};
+pub use changelog::{
+    Changelog, ChangelogEntry, ChangelogGroup, ChangelogOptions, ChangelogSection,
+};
 pub use client::GitHubClient;
-pub use config::{FetchConfig, GitHubConfig, RateLimitConfig};
+pub use codeblock::{classify_code, extract_code_blocks, CodeBlock, CodeLanguage};
+pub use config::{BotConfig, FetchConfig, GitHubConfig, RateLimitConfig};
+pub use context::{
+    CharRatioEstimator, ContextDocument, ContextItem, ContextKind, ContextPacker, PackStatus,
+    PackedContext, PackedItem, TokenEstimator,
+};
+pub use diagnostics::{
+    parse_rustc_diagnostics, DiagnosticExtractor, DiagnosticKind, DiagnosticLevel, DiagnosticMatch,
+    DiagnosticRegistry, DiagnosticSpan, RegexExtractor, RustcDiagnostic,
+};
+pub use diff::{parse_patch, DiffHunk, DiffLine, DiffLineKind};
 pub use discussion::DiscussionClient;
+pub use environment::{
+    extract_environment, Channel, CrateVersionReq, Environment, RustcVersion, Version, VersionOp,
+};
 pub use error::{GitHubFetchError, Result};
 pub use filters::{
-    extract_error_codes, has_code_blocks, has_rust_error_codes, DateRange, IssueFilters, IssueState,
+    extract_error_codes, has_code_blocks, has_rust_error_codes, prose_only, DateRange,
+    IssueFilters, IssueSort, IssueState, KeywordMatch, KeywordMode, KeywordRule, KeywordScope,
+    Rejection, SortDirection, SortField,
+};
+pub use markdown::{MarkdownOptions, MarkdownRenderer, MarkdownSection, PrReportData};
+pub use plan::CollectionPlan;
+pub use query::QueryParseError;
+pub use relocation::{
+    relocate_comment, relocate_outdated_comments, CommentLocation, RelocatedComment,
+};
+pub use review::{
+    assess_merge_readiness, comments_since, latest_review_by, looks_rebased, summarize_reviews,
+    ChangesSinceReview, MergeReadiness, MergeVerdict, ReviewState, ReviewSummary, ReviewerStatus,
 };
+pub use rules::{all_of, any_of, filter_fn, not, BoxedFilter, IssueFilter, KeywordFilter};
+pub use search::{SearchKind, SearchQuery, SearchResult, SearchState, SEARCH_RESULT_CAP};
+pub use snippet::{
+    analyze_rust_blocks, analyze_snippet, has_self_contained_repro, SnippetAnalysis, SnippetForm,
+};
+pub use suggestion::{
+    extract_suggestions, parse_suggestion_blocks, AppliedSuggestion, ConflictReason, Suggestion,
+    SuggestionApplier, SuggestionConflict, SuggestionReport,
+};
+pub use tree::{Glob, RepoTree, TreeEntry, TreeEntryType};
 pub use types::{
-    CollectionResult, Discussion, DiscussionComment, GitHubComment, GitHubIssue, GitHubLabel,
-    GitHubUser, PrFile, PrReview, PrReviewComment, Repository,
+    CiCheck, CiStatus, CodeContext, CollectionResult, CollectionStats, CommitSummary, Comparison,
+    DateField, Discussion, DiscussionComment, FileContent, GitHubComment, GitHubIssue, GitHubLabel,
+    GitHubRelease, GitHubTag, GitHubUser, PrFile, PrMergeStatus, PrRefs, PrReview, PrReviewComment,
+    ReleaseAsset, Repository,
 };
 
+use bundle::BundleParts;
+
 pub struct GitHubFetcher {
     client: GitHubClient,
     discussion_client: Option<DiscussionClient>,
@@ -36,7 +102,15 @@ impl GitHubFetcher {
 
     pub fn with_config(config: FetchConfig) -> Result<Self> {
         let client = GitHubClient::with_config(config.clone())?;
-        let discussion_client = DiscussionClient::new(config.github).ok();
+        let discussion_client = DiscussionClient::new(config.github)
+            .ok()
+            .map(|discussions| {
+                if config.bots.exclude_activity {
+                    discussions.exclude_bots(config.bots.classifier)
+                } else {
+                    discussions
+                }
+            });
 
         Ok(Self {
             client,
@@ -64,6 +138,45 @@ impl GitHubFetcher {
             .await
     }
 
+    /// Search issues and pull requests across repositories, splitting the
+    /// query by creation date when it matches more than 1000 results
+    pub async fn search_issues(
+        &self,
+        query: &SearchQuery,
+        max_results: Option<usize>,
+    ) -> Result<SearchResult> {
+        self.client.search_issues(query, max_results).await
+    }
+
+    /// Collect issues passing both `filters` and a custom filter tree
+    ///
+    /// ```no_run
+    /// # async fn run(fetcher: &github_fetch::GitHubFetcher, repo: &github_fetch::Repository) -> github_fetch::Result<()> {
+    /// use github_fetch::rules::{filter_fn, HasAnyLabel, IssueFilter};
+    /// use github_fetch::IssueFilters;
+    ///
+    /// let filter = HasAnyLabel::new(["A-borrowck"])
+    ///     .or(HasAnyLabel::new(["A-lifetimes"]))
+    ///     .and(filter_fn("not a bot", |issue| !issue.user.login.ends_with("[bot]")).boxed());
+    /// let filters: IssueFilters = "state:open is:issue".parse().unwrap();
+    /// let result = fetcher
+    ///     .fetch_issues_with_filter(repo, &filters, &filter, Some(50))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_issues_with_filter(
+        &self,
+        repo: &Repository,
+        filters: &IssueFilters,
+        filter: &dyn IssueFilter,
+        max_issues: Option<usize>,
+    ) -> Result<CollectionResult> {
+        self.client
+            .fetch_issues_with_filter(repo, filters, filter, max_issues)
+            .await
+    }
+
     pub async fn fetch_issue(&self, repo: &Repository, number: u64) -> Result<GitHubIssue> {
         self.client.fetch_issue(repo, number).await
     }
@@ -102,6 +215,314 @@ impl GitHubFetcher {
         self.client.fetch_pr_review_comments(repo, pr_number).await
     }
 
+    /// Fetch a PR with its files, reviews, review comments and general comments
+    ///
+    /// The parts are fetched concurrently under the client's rate limiter. The
+    /// head is checked before and after; if it moved mid-fetch, the fetch is
+    /// retried so that every part reflects the same head SHA. A failing part is
+    /// reported in `PrBundle::errors` instead of failing the whole bundle.
+    pub async fn fetch_pr_bundle(&self, repo: &Repository, pr_number: u64) -> Result<PrBundle> {
+        let bundle = PrBundle::fetch_consistent(
+            repo,
+            pr_number,
+            || self.client.fetch_pr_refs(repo, pr_number),
+            || async {
+                let (pr, files, reviews, review_comments, comments) = tokio::join!(
+                    self.client.fetch_pr(repo, pr_number),
+                    self.client.fetch_pr_files(repo, pr_number),
+                    self.client.fetch_pr_reviews(repo, pr_number),
+                    self.client.fetch_pr_review_comments(repo, pr_number),
+                    self.client.fetch_comments(repo, pr_number),
+                );
+                BundleParts {
+                    pr,
+                    files,
+                    reviews,
+                    review_comments,
+                    comments,
+                }
+            },
+        )
+        .await;
+        Ok(bundle)
+    }
+
+    /// Fetch the base and head refs of a PR
+    pub async fn fetch_pr_refs(&self, repo: &Repository, pr_number: u64) -> Result<PrRefs> {
+        self.client.fetch_pr_refs(repo, pr_number).await
+    }
+
+    /// Compare two branches, tags or commits: ahead/behind counts, merge base,
+    /// commits and changed files with patches
+    pub async fn compare(&self, repo: &Repository, base: &str, head: &str) -> Result<Comparison> {
+        self.client.compare(repo, base, head).await
+    }
+
+    /// Fetch mergeability, draft state and requested reviewers of a PR
+    pub async fn fetch_pr_merge_status(
+        &self,
+        repo: &Repository,
+        pr_number: u64,
+    ) -> Result<PrMergeStatus> {
+        self.client.fetch_pr_merge_status(repo, pr_number).await
+    }
+
+    /// Fetch combined commit statuses and check runs for a commit
+    pub async fn fetch_ci_status(&self, repo: &Repository, sha: &str) -> Result<CiStatus> {
+        self.client.fetch_ci_status(repo, sha).await
+    }
+
+    /// Assess whether a PR can be merged from its reviews, mergeability and CI status
+    ///
+    /// Bots' reviews count even when bot activity is excluded.
+    pub async fn fetch_merge_readiness(
+        &self,
+        repo: &Repository,
+        pr_number: u64,
+    ) -> Result<MergeReadiness> {
+        let merge_status = self.client.fetch_pr_merge_status(repo, pr_number).await?;
+        let reviews = self.client.fetch_all_pr_reviews(repo, pr_number).await?;
+        let ci = self
+            .client
+            .fetch_ci_status(repo, &merge_status.head_sha)
+            .await?;
+
+        Ok(assess_merge_readiness(&reviews, merge_status, ci))
+    }
+
+    /// Fetch what changed on a PR since `reviewer` last submitted a review
+    ///
+    /// Diffs the reviewer's latest review commit against the current head and
+    /// flags a likely rebase with `rebased`. If GitHub cannot find the reviewed
+    /// commit, falls back to the full PR diff and sets `reviewed_commit_missing`.
+    pub async fn fetch_changes_since_review(
+        &self,
+        repo: &Repository,
+        pr_number: u64,
+        reviewer: &str,
+    ) -> Result<ChangesSinceReview> {
+        let reviews = self.client.fetch_all_pr_reviews(repo, pr_number).await?;
+        let review = latest_review_by(&reviews, reviewer)
+            .cloned()
+            .ok_or_else(|| {
+                GitHubFetchError::NotFound(format!(
+                    "No submitted review by {} on PR #{}",
+                    reviewer, pr_number
+                ))
+            })?;
+        let reviewed_commit = review.commit_id.clone().ok_or_else(|| {
+            GitHubFetchError::NotFound(format!(
+                "Review {} by {} does not record a commit",
+                review.id, reviewer
+            ))
+        })?;
+        let head_sha = self.client.fetch_pr_refs(repo, pr_number).await?.head_sha;
+
+        let mut reviewed_commit_missing = false;
+        let (comparison, files) = if reviewed_commit == head_sha {
+            (None, Vec::new())
+        } else {
+            match self.client.compare(repo, &reviewed_commit, &head_sha).await {
+                Ok(comparison) => {
+                    let files = comparison.files.clone();
+                    (Some(comparison), files)
+                }
+                Err(GitHubFetchError::NotFound(_)) => {
+                    reviewed_commit_missing = true;
+                    let files = self.client.fetch_pr_files(repo, pr_number).await?;
+                    (None, files)
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        let new_review_comments = match review.submitted_at {
+            Some(submitted_at) => {
+                let comments = self
+                    .client
+                    .fetch_pr_review_comments(repo, pr_number)
+                    .await?;
+                comments_since(&comments, submitted_at)
+            }
+            None => Vec::new(),
+        };
+
+        Ok(ChangesSinceReview {
+            reviewer: reviewer.to_string(),
+            review,
+            reviewed_commit,
+            head_sha,
+            rebased: comparison.as_ref().is_some_and(looks_rebased),
+            reviewed_commit_missing,
+            comparison,
+            files,
+            new_review_comments,
+        })
+    }
+
+    /// Fetch a file's contents at a branch, tag or commit
+    pub async fn fetch_file_content(
+        &self,
+        repo: &Repository,
+        path: &str,
+        git_ref: &str,
+    ) -> Result<FileContent> {
+        self.client.fetch_file_content(repo, path, git_ref).await
+    }
+
+    /// List the repository tree at a branch, tag or commit
+    pub async fn fetch_tree(&self, repo: &Repository, git_ref: &str) -> Result<RepoTree> {
+        self.client.fetch_tree(repo, git_ref).await
+    }
+
+    /// Files at `git_ref` whose path matches a glob such as `**/Cargo.toml`
+    pub async fn find_files(
+        &self,
+        repo: &Repository,
+        git_ref: &str,
+        pattern: &str,
+    ) -> Result<Vec<TreeEntry>> {
+        let glob = Glob::new(pattern)?;
+        let tree = self.client.fetch_tree(repo, git_ref).await?;
+        Ok(tree.glob(&glob).cloned().collect())
+    }
+
+    /// Fetch all releases, newest first
+    pub async fn fetch_releases(&self, repo: &Repository) -> Result<Vec<GitHubRelease>> {
+        self.client.fetch_releases(repo).await
+    }
+
+    pub async fn fetch_release_by_tag(
+        &self,
+        repo: &Repository,
+        tag: &str,
+    ) -> Result<GitHubRelease> {
+        self.client.fetch_release_by_tag(repo, tag).await
+    }
+
+    pub async fn fetch_tags(&self, repo: &Repository) -> Result<Vec<GitHubTag>> {
+        self.client.fetch_tags(repo).await
+    }
+
+    /// Build a changelog of the PRs merged after `from_tag` up to `to_tag`
+    ///
+    /// The range is taken from the tags' commit dates and searched for PRs
+    /// merged into `options.base_branch`, or the default branch. Without
+    /// `from_tag`, every merged PR up to `to_tag` is listed.
+    pub async fn generate_changelog(
+        &self,
+        repo: &Repository,
+        from_tag: Option<&str>,
+        to_tag: &str,
+        options: &ChangelogOptions,
+    ) -> Result<Changelog> {
+        let end = self.client.fetch_commit(repo, to_tag).await?;
+        let end_date = end.committed_at.or(end.date);
+        let start_date = match from_tag {
+            Some(tag) => {
+                let start = self.client.fetch_commit(repo, tag).await?;
+                start.committed_at.or(start.date)
+            }
+            None => None,
+        };
+
+        let branch = match &options.base_branch {
+            Some(branch) => branch.clone(),
+            None => self.client.fetch_default_branch(repo).await?,
+        };
+        let query = changelog::merged_pr_query(repo, &branch, start_date, end_date);
+        let result = self.client.search_issues(&query, None).await?;
+
+        let prs: Vec<GitHubIssue> = result
+            .issues
+            .into_iter()
+            .filter(|issue| match issue.merged_at {
+                Some(merged_at) => {
+                    start_date.map_or(true, |start| merged_at > start)
+                        && end_date.map_or(true, |end| merged_at <= end)
+                }
+                None => false,
+            })
+            .collect();
+
+        let mut first_time = Vec::new();
+        let mut seen: Vec<&str> = Vec::new();
+        for pr in &prs {
+            let login = pr.user.login.as_str();
+            if seen.contains(&login) {
+                continue;
+            }
+            seen.push(login);
+
+            let first_merge = prs
+                .iter()
+                .filter(|other| other.user.login == login)
+                .filter_map(|other| other.merged_at)
+                .min();
+            if let Some(first_merge) = first_merge {
+                let earlier = self
+                    .client
+                    .count_merged_prs_before(repo, login, first_merge)
+                    .await?;
+                if earlier == 0 {
+                    first_time.push(login.to_string());
+                }
+            }
+        }
+
+        Ok(Changelog::build(
+            from_tag,
+            to_tag,
+            end_date,
+            &prs,
+            &first_time,
+            options,
+        ))
+    }
+
+    /// Fetch the code around a review comment of PR `pr_number` at the commit
+    /// its line numbers refer to
+    ///
+    /// Outdated comments are read at their original commit, and LEFT-side
+    /// comments at the merge base of the PR's base branch and that commit.
+    pub async fn fetch_comment_context(
+        &self,
+        repo: &Repository,
+        pr_number: u64,
+        comment: &PrReviewComment,
+        context_lines: u32,
+    ) -> Result<CodeContext> {
+        let anchor = comment.anchor().ok_or_else(|| {
+            GitHubFetchError::NotFound(format!("Review comment {} is not on lines", comment.id))
+        })?;
+        let commit_id = anchor.commit_id.as_deref().ok_or_else(|| {
+            GitHubFetchError::NotFound(format!("Review comment {} has no commit", comment.id))
+        })?;
+        let merge_base = if anchor.on_base {
+            let refs = self.client.fetch_pr_refs(repo, pr_number).await?;
+            let comparison = self.client.compare(repo, &refs.base_sha, commit_id).await?;
+            comparison.merge_base_sha
+        } else {
+            None
+        };
+        let git_ref = anchor.git_ref(merge_base.as_deref()).ok_or_else(|| {
+            GitHubFetchError::NotFound(format!("No merge base for commit {}", commit_id))
+        })?;
+        let file = self
+            .client
+            .fetch_file_content(repo, &comment.path, &git_ref)
+            .await?;
+
+        comment
+            .surrounding_lines(&file, context_lines)
+            .ok_or_else(|| {
+                GitHubFetchError::NotFound(format!(
+                    "No line context for review comment {} in {}",
+                    comment.id, comment.path
+                ))
+            })
+    }
+
     pub async fn fetch_discussion(
         &self,
         repo: &Repository,
@@ -183,6 +604,18 @@ impl GitHubFetcherBuilder {
         self
     }
 
+    /// Drop bot comments, reviews and discussion comments from fetched threads
+    pub fn exclude_bots(mut self) -> Self {
+        self.config.bots.exclude_activity = true;
+        self
+    }
+
+    /// Decide which accounts are bots with `classifier` instead of the defaults
+    pub fn bot_classifier(mut self, classifier: BotClassifier) -> Self {
+        self.config.bots.classifier = classifier;
+        self
+    }
+
     pub fn build(self) -> Result<GitHubFetcher> {
         GitHubFetcher::with_config(self.config)
     }
@@ -0,0 +1,635 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bundle::{BundlePart, PrBundle};
+use crate::review::ReviewState;
+use crate::types::{GitHubComment, GitHubIssue, PrFile, PrReview, PrReviewComment};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkdownSection {
+    Header,
+    Description,
+    Reviews,
+    Files,
+    Diffs,
+    ReviewComments,
+    Comments,
+    ActionItems,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkdownOptions {
+    /// Sections to render, in order
+    pub sections: Vec<MarkdownSection>,
+    /// Maximum lines per file diff; longer diffs are cut with a marker
+    pub max_diff_lines: Option<usize>,
+    /// Wrap each file diff in a `<details>` block
+    pub collapse_diffs: bool,
+    /// Wrap the diff hunk of each inline comment in a `<details>` block
+    pub collapse_comment_hunks: bool,
+    /// Maximum number of general comments to render
+    pub max_comments: Option<usize>,
+    /// Length of the review body preview in the reviews table
+    pub review_preview_chars: usize,
+    /// Length of the comment preview in action items
+    pub action_item_preview_chars: usize,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            sections: vec![
+                MarkdownSection::Header,
+                MarkdownSection::Description,
+                MarkdownSection::Reviews,
+                MarkdownSection::Files,
+                MarkdownSection::Diffs,
+                MarkdownSection::ReviewComments,
+                MarkdownSection::Comments,
+                MarkdownSection::ActionItems,
+            ],
+            max_diff_lines: Some(500),
+            collapse_diffs: false,
+            collapse_comment_hunks: false,
+            max_comments: Some(10),
+            review_preview_chars: 50,
+            action_item_preview_chars: 80,
+        }
+    }
+}
+
+/// Borrowed PR data to render; a `None` part is rendered as unavailable
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrReportData<'a> {
+    pub pr: Option<&'a GitHubIssue>,
+    pub files: Option<&'a [PrFile]>,
+    pub reviews: Option<&'a [PrReview]>,
+    pub review_comments: Option<&'a [PrReviewComment]>,
+    pub comments: Option<&'a [GitHubComment]>,
+}
+
+impl<'a> From<&'a PrBundle> for PrReportData<'a> {
+    fn from(bundle: &'a PrBundle) -> Self {
+        Self {
+            pr: bundle.pr.as_ref(),
+            files: bundle.files.as_deref(),
+            reviews: bundle.reviews.as_deref(),
+            review_comments: bundle.review_comments.as_deref(),
+            comments: bundle.comments.as_deref(),
+        }
+    }
+}
+
+/// Renders PR data into the Markdown review report
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownRenderer {
+    options: MarkdownOptions,
+}
+
+impl MarkdownRenderer {
+    pub fn new(options: MarkdownOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn options(&self) -> &MarkdownOptions {
+        &self.options
+    }
+
+    pub fn render_bundle(&self, bundle: &PrBundle) -> String {
+        let mut out = self.render(PrReportData::from(bundle));
+
+        if !bundle.errors.is_empty() {
+            out.push_str("---\n### Fetch Errors\n\n");
+            for error in &bundle.errors {
+                let _ = writeln!(out, "- {}: {}", part_name(error.part), error.message);
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    pub fn render(&self, data: PrReportData<'_>) -> String {
+        let mut out = String::new();
+
+        for section in &self.options.sections {
+            match section {
+                MarkdownSection::Header => self.render_header(&mut out, data.pr),
+                MarkdownSection::Description => self.render_description(&mut out, data.pr),
+                MarkdownSection::Reviews => self.render_reviews(&mut out, data.reviews),
+                MarkdownSection::Files => self.render_files(&mut out, data.files),
+                MarkdownSection::Diffs => self.render_diffs(&mut out, data.files),
+                MarkdownSection::ReviewComments => {
+                    self.render_review_comments(&mut out, data.review_comments)
+                }
+                MarkdownSection::Comments => self.render_comments(&mut out, data.comments),
+                MarkdownSection::ActionItems => {
+                    self.render_action_items(&mut out, data.reviews, data.review_comments)
+                }
+            }
+        }
+
+        out
+    }
+
+    fn render_header(&self, out: &mut String, pr: Option<&GitHubIssue>) {
+        let pr = match pr {
+            Some(pr) => pr,
+            None => {
+                out.push_str("## PR\n\n_PR details not available._\n\n");
+                return;
+            }
+        };
+
+        let _ = writeln!(out, "## PR #{}: {}\n", pr.number, pr.title);
+        let _ = writeln!(
+            out,
+            "**Author:** @{} | **State:** {}",
+            pr.user.login, pr.state
+        );
+        if !pr.labels.is_empty() {
+            let labels: Vec<&str> = pr.labels.iter().map(|l| l.name.as_str()).collect();
+            let _ = writeln!(out, "**Labels:** {}", labels.join(", "));
+        }
+        let _ = writeln!(out, "**URL:** {}\n", pr.html_url);
+    }
+
+    fn render_description(&self, out: &mut String, pr: Option<&GitHubIssue>) {
+        if let Some(body) = pr.and_then(|pr| pr.body.as_deref()) {
+            if !body.trim().is_empty() {
+                let _ = writeln!(out, "### Description\n\n{}\n", body.trim_end());
+            }
+        }
+    }
+
+    fn render_reviews(&self, out: &mut String, reviews: Option<&[PrReview]>) {
+        out.push_str("---\n### Reviews\n\n");
+
+        match reviews {
+            None => out.push_str("_Reviews not available._\n\n"),
+            Some([]) => out.push_str("No reviews yet.\n\n"),
+            Some(reviews) => {
+                out.push_str("| Reviewer | State | Comment |\n");
+                out.push_str("|----------|-------|---------|\n");
+                for review in reviews {
+                    let preview = review
+                        .body
+                        .as_deref()
+                        .filter(|b| !b.trim().is_empty())
+                        .map(|b| table_cell(&preview(b, self.options.review_preview_chars)))
+                        .unwrap_or_else(|| "-".to_string());
+                    let _ = writeln!(
+                        out,
+                        "| @{} | {} | {} |",
+                        review.user.login, review.state, preview
+                    );
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    fn render_files(&self, out: &mut String, files: Option<&[PrFile]>) {
+        out.push_str("---\n### Files Changed\n\n");
+
+        let files = match files {
+            Some(files) => files,
+            None => {
+                out.push_str("_Files not available._\n\n");
+                return;
+            }
+        };
+
+        let additions: u32 = files.iter().map(|f| f.additions).sum();
+        let deletions: u32 = files.iter().map(|f| f.deletions).sum();
+        let _ = writeln!(
+            out,
+            "**{} files changed**, +{} -{}\n",
+            files.len(),
+            additions,
+            deletions
+        );
+        for file in files {
+            let _ = writeln!(
+                out,
+                "- `{}` (+{} -{}) {}",
+                file.filename, file.additions, file.deletions, file.status
+            );
+        }
+        out.push('\n');
+    }
+
+    fn render_diffs(&self, out: &mut String, files: Option<&[PrFile]>) {
+        out.push_str("---\n### Diffs\n\n");
+
+        let files = match files {
+            Some(files) => files,
+            None => {
+                out.push_str("_Diffs not available._\n\n");
+                return;
+            }
+        };
+
+        for file in files {
+            let patch = match &file.patch {
+                Some(patch) => patch,
+                None => continue,
+            };
+            let (patch, omitted) = truncate_lines(patch, self.options.max_diff_lines);
+
+            if self.options.collapse_diffs {
+                let _ = writeln!(
+                    out,
+                    "<details>\n<summary>{} (+{} -{})</summary>\n",
+                    html_escape(&file.filename),
+                    file.additions,
+                    file.deletions
+                );
+            } else {
+                let _ = writeln!(out, "#### {}\n", file.filename);
+            }
+
+            let _ = writeln!(out, "{}\n", code_block(&patch, "diff"));
+            if omitted > 0 {
+                let _ = writeln!(out, "_Diff truncated: {} more lines._\n", omitted);
+            }
+
+            if self.options.collapse_diffs {
+                out.push_str("</details>\n\n");
+            }
+        }
+    }
+
+    fn render_review_comments(&self, out: &mut String, comments: Option<&[PrReviewComment]>) {
+        out.push_str("---\n### Review Comments (Inline)\n\n");
+
+        let comments = match comments {
+            None => {
+                out.push_str("_Review comments not available._\n\n");
+                return;
+            }
+            Some([]) => {
+                out.push_str("No inline review comments.\n\n");
+                return;
+            }
+            Some(comments) => comments,
+        };
+
+        let mut by_path: BTreeMap<&str, Vec<&PrReviewComment>> = BTreeMap::new();
+        for comment in comments {
+            by_path.entry(&comment.path).or_default().push(comment);
+        }
+
+        for (path, comments) in by_path {
+            let _ = writeln!(out, "#### {}\n", path);
+            for comment in comments {
+                let _ = writeln!(out, "**@{}** ({})", comment.user.login, location(comment));
+                let _ = writeln!(out, "{}\n", quote(&comment.body));
+
+                if comment.diff_hunk.is_empty() {
+                    continue;
+                }
+                let hunk = code_block(&comment.diff_hunk, "diff");
+                if self.options.collapse_comment_hunks {
+                    let _ = writeln!(
+                        out,
+                        "<details>\n<summary>Diff context</summary>\n\n{}\n\n</details>\n",
+                        hunk
+                    );
+                } else {
+                    let _ = writeln!(out, "{}\n", hunk);
+                }
+            }
+        }
+    }
+
+    fn render_comments(&self, out: &mut String, comments: Option<&[GitHubComment]>) {
+        out.push_str("---\n### General Comments\n\n");
+
+        let comments = match comments {
+            None => {
+                out.push_str("_Comments not available._\n\n");
+                return;
+            }
+            Some([]) => {
+                out.push_str("No general comments.\n\n");
+                return;
+            }
+            Some(comments) => comments,
+        };
+
+        let shown = self.options.max_comments.unwrap_or(comments.len());
+        for comment in comments.iter().take(shown) {
+            let _ = writeln!(out, "**@{}** ({})", comment.user.login, comment.created_at);
+            let _ = writeln!(out, "{}\n", comment.body.trim_end());
+        }
+        if comments.len() > shown {
+            let _ = writeln!(out, "... and {} more comments\n", comments.len() - shown);
+        }
+    }
+
+    fn render_action_items(
+        &self,
+        out: &mut String,
+        reviews: Option<&[PrReview]>,
+        review_comments: Option<&[PrReviewComment]>,
+    ) {
+        out.push_str("---\n### Action Items\n\n");
+
+        let mut items = Vec::new();
+        for review in reviews.unwrap_or_default() {
+            if ReviewState::parse(&review.state) != ReviewState::ChangesRequested {
+                continue;
+            }
+            if let Some(first_line) = review.body.as_deref().and_then(|b| b.lines().next()) {
+                items.push(format!("{} (@{})", first_line.trim(), review.user.login));
+            }
+        }
+        for comment in review_comments.unwrap_or_default() {
+            if comment.in_reply_to_id.is_some() {
+                continue;
+            }
+            let first_line = comment.body.lines().next().unwrap_or("");
+            items.push(format!(
+                "{} ({}:{})",
+                preview(first_line, self.options.action_item_preview_chars),
+                comment.path,
+                comment.line.or(comment.original_line).unwrap_or(0)
+            ));
+        }
+
+        if items.is_empty() {
+            out.push_str("No action items - PR looks good!\n\n");
+            return;
+        }
+        for (i, item) in items.iter().enumerate() {
+            let _ = writeln!(out, "{}. [ ] {}", i + 1, item);
+        }
+        out.push('\n');
+    }
+}
+
+fn part_name(part: BundlePart) -> &'static str {
+    match part {
+        BundlePart::Refs => "refs",
+        BundlePart::Pr => "PR",
+        BundlePart::Files => "files",
+        BundlePart::Reviews => "reviews",
+        BundlePart::ReviewComments => "review comments",
+        BundlePart::Comments => "comments",
+    }
+}
+
+fn location(comment: &PrReviewComment) -> String {
+    if comment.is_file_level() {
+        return "File".to_string();
+    }
+    match comment.new_side_range() {
+        Some(range) if range.start() != range.end() => {
+            format!("Lines {}-{}", range.start(), range.end())
+        }
+        Some(range) => format!("Line {}", range.end()),
+        None => match (comment.line, comment.original_line) {
+            (Some(line), _) => format!("Line {}", line),
+            (None, Some(line)) => format!("Outdated, line {}", line),
+            (None, None) => "Outdated".to_string(),
+        },
+    }
+}
+
+/// First `max_chars` characters of `text`, with an ellipsis when cut
+pub(crate) fn preview(text: &str, max_chars: usize) -> String {
+    let mut chars = text.chars();
+    let head: String = chars.by_ref().take(max_chars).collect();
+    if chars.next().is_some() {
+        format!("{}...", head)
+    } else {
+        head
+    }
+}
+
+fn table_cell(text: &str) -> String {
+    text.replace('\r', "")
+        .replace('\n', " ")
+        .replace('|', "\\|")
+}
+
+fn quote(text: &str) -> String {
+    text.trim_end()
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Fenced code block whose fence is longer than any backtick run in the content
+pub(crate) fn code_block(content: &str, lang: &str) -> String {
+    let longest_run = content
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}",
+        fence,
+        lang,
+        content.trim_end_matches('\n'),
+        fence
+    )
+}
+
+/// Keep at most `max_lines` lines, returning the kept text and the number of omitted lines
+pub(crate) fn truncate_lines(text: &str, max_lines: Option<usize>) -> (String, usize) {
+    let total = text.lines().count();
+    match max_lines {
+        Some(max) if total > max => (
+            text.lines().take(max).collect::<Vec<_>>().join("\n"),
+            total - max,
+        ),
+        _ => (text.to_string(), 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{label, pull_request, review_comment, timestamp, user};
+
+    fn sample() -> (
+        GitHubIssue,
+        Vec<PrFile>,
+        Vec<PrReview>,
+        Vec<PrReviewComment>,
+        Vec<GitHubComment>,
+    ) {
+        let at = timestamp();
+        let pr = GitHubIssue {
+            title: "Fix parser".to_string(),
+            body: Some("Fixes the parser.".to_string()),
+            labels: vec![label("C-bug")],
+            user: user("author"),
+            comments: 1,
+            ..pull_request(42)
+        };
+        let files = vec![PrFile {
+            filename: "src/parser.rs".to_string(),
+            status: "Modified".to_string(),
+            additions: 2,
+            deletions: 1,
+            changes: 3,
+            patch: Some(
+                "@@ -1,2 +1,3 @@\n fn parse() {\n-    todo!()\n+    let x = 1;\n+    x".to_string(),
+            ),
+        }];
+        let reviews = vec![PrReview {
+            id: 1,
+            user: user("reviewer"),
+            body: Some("Needs a test | please".to_string()),
+            state: "ChangesRequested".to_string(),
+            submitted_at: Some(at),
+            html_url: String::new(),
+            commit_id: Some("abc".to_string()),
+        }];
+        let review_comments = vec![PrReviewComment {
+            review_id: Some(1),
+            body: "Name this better".to_string(),
+            path: "src/parser.rs".to_string(),
+            line: Some(2),
+            original_line: Some(2),
+            diff_hunk: "@@ -1,2 +1,2 @@\n fn parse() {\n+    let x = 1;".to_string(),
+            commit_id: Some("abc".to_string()),
+            ..review_comment(2)
+        }];
+        let comments = vec![GitHubComment {
+            id: 3,
+            user: user("author"),
+            body: "Thanks!".to_string(),
+            created_at: at,
+            updated_at: at,
+            html_url: String::new(),
+        }];
+        (pr, files, reviews, review_comments, comments)
+    }
+
+    #[test]
+    fn test_render_snapshot() {
+        let (pr, files, reviews, review_comments, comments) = sample();
+        let rendered = MarkdownRenderer::default().render(PrReportData {
+            pr: Some(&pr),
+            files: Some(&files),
+            reviews: Some(&reviews),
+            review_comments: Some(&review_comments),
+            comments: Some(&comments),
+        });
+
+        let expected = "\
+## PR #42: Fix parser
+
+**Author:** @author | **State:** Open
+**Labels:** C-bug
+**URL:** https://github.com/o/r/pull/42
+
+### Description
+
+Fixes the parser.
+
+---
+### Reviews
+
+| Reviewer | State | Comment |
+|----------|-------|---------|
+| @reviewer | ChangesRequested | Needs a test \\| please |
+
+---
+### Files Changed
+
+**1 files changed**, +2 -1
+
+- `src/parser.rs` (+2 -1) Modified
+
+---
+### Diffs
+
+#### src/parser.rs
+
+```diff
+@@ -1,2 +1,3 @@
+ fn parse() {
+-    todo!()
++    let x = 1;
++    x
+```
+
+---
+### Review Comments (Inline)
+
+#### src/parser.rs
+
+**@reviewer** (Line 2)
+> Name this better
+
+```diff
+@@ -1,2 +1,2 @@
+ fn parse() {
++    let x = 1;
+```
+
+---
+### General Comments
+
+**@author** (2024-05-01 12:00:00 UTC)
+Thanks!
+
+---
+### Action Items
+
+1. [ ] Needs a test | please (@reviewer)
+2. [ ] Name this better (src/parser.rs:2)
+
+";
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn test_truncated_collapsible_diff() {
+        let (_, files, _, _, _) = sample();
+        let renderer = MarkdownRenderer::new(MarkdownOptions {
+            sections: vec![MarkdownSection::Diffs],
+            max_diff_lines: Some(2),
+            collapse_diffs: true,
+            ..Default::default()
+        });
+
+        let rendered = renderer.render(PrReportData {
+            files: Some(&files),
+            ..Default::default()
+        });
+        assert_eq!(
+            rendered,
+            "---\n### Diffs\n\n<details>\n<summary>src/parser.rs (+2 -1)</summary>\n\n```diff\n@@ -1,2 +1,3 @@\n fn parse() {\n```\n\n_Diff truncated: 3 more lines._\n\n</details>\n\n"
+        );
+    }
+
+    #[test]
+    fn test_code_block_fence() {
+        assert_eq!(
+            code_block("```rust\nfn main() {}\n```", "markdown"),
+            "````markdown\n```rust\nfn main() {}\n```\n````"
+        );
+    }
+}
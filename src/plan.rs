@@ -0,0 +1,211 @@
+use chrono::{DateTime, Utc};
+
+use crate::filters::{IssueFilters, IssueState};
+use crate::search::{SearchQuery, SearchState};
+use crate::types::Repository;
+
+/// Which API a collection uses and which filters GitHub applies before download
+///
+/// `IssueFilters::matches` still runs on every downloaded issue, so a plan only
+/// decides how much is fetched, never what is returned.
+#[derive(Debug, Clone)]
+pub enum CollectionPlan {
+    /// Repository issue list: state, a single include label, author, assignee and `since`
+    List {
+        since: Option<DateTime<Utc>>,
+        server_side: Vec<String>,
+    },
+    /// Search API, used when some filters only map to search qualifiers
+    Search {
+        query: Box<SearchQuery>,
+        server_side: Vec<String>,
+    },
+}
+
+impl CollectionPlan {
+    pub fn for_filters(repo: &Repository, filters: &IssueFilters) -> Self {
+        let start = filters.date_range.as_ref().and_then(|range| range.start);
+        let end = filters.date_range.as_ref().and_then(|range| range.end);
+
+        // Label exclusion and pull request removal are cheap to do locally and
+        // in the defaults, so they alone never justify the slower search API.
+        // Keywords are never pushed down: GitHub matches whole words while
+        // `matches` looks for substrings, so `borrow` would lose `borrowck`.
+        let needs_search = filters.include_labels.len() > 1
+            || end.is_some()
+            || filters.min_comments.is_some()
+            || filters.min_reactions.is_some()
+            || filters.milestone.is_some();
+
+        if !needs_search {
+            // Closing, merging and creating all bump `updated_at`, so a start
+            // on any date field bounds it from below
+            return Self::list(filters, start);
+        }
+
+        let mut server_side = Vec::new();
+        if filters.state != IssueState::All {
+            server_side.push(format!("state: {:?}", filters.state));
+        }
+
+        let mut query = SearchQuery::new().repo(repo);
+        match filters.state {
+            IssueState::Open => query = query.state(SearchState::Open),
+            IssueState::Closed => query = query.state(SearchState::Closed),
+            IssueState::All => {}
+        }
+        if !filters.include_pull_requests {
+            query = query.issues();
+            server_side.push("exclude_pull_requests: true".to_string());
+        }
+        if !filters.include_labels.is_empty() {
+            query.any_labels = filters.include_labels.clone();
+            server_side.push(format!("include_labels: {:?}", filters.include_labels));
+        }
+        if !filters.exclude_labels.is_empty() {
+            query.exclude_labels = filters.exclude_labels.clone();
+            server_side.push(format!("exclude_labels: {:?}", filters.exclude_labels));
+        }
+        if let Some(range) = &filters.date_range {
+            if range.start.is_some() || range.end.is_some() {
+                query = query.date(filters.date_field, range.clone());
+                server_side.push("date_range".to_string());
+            }
+        }
+        if let Some(min_comments) = filters.min_comments {
+            query = query.min_comments(min_comments);
+            server_side.push(format!("min_comments: {}", min_comments));
+        }
+        if let Some(min_reactions) = filters.min_reactions {
+            query = query.min_reactions(min_reactions);
+            server_side.push(format!("min_reactions: {}", min_reactions));
+        }
+        if let Some(author) = &filters.author {
+            query = query.author(author.clone());
+            server_side.push(format!("author: {}", author));
+        }
+        if let Some(assignee) = &filters.assignee {
+            query = query.assignee(assignee.clone());
+            server_side.push(format!("assignee: {}", assignee));
+        }
+        if let Some(milestone) = &filters.milestone {
+            query = query.milestone(milestone.clone());
+            server_side.push(format!("milestone: {}", milestone));
+        }
+
+        Self::Search {
+            query: Box::new(query),
+            server_side,
+        }
+    }
+
+    /// List plan for issues updated at or after `since`
+    pub fn list(filters: &IssueFilters, since: Option<DateTime<Utc>>) -> Self {
+        let mut server_side = Vec::new();
+        if filters.state != IssueState::All {
+            server_side.push(format!("state: {:?}", filters.state));
+        }
+        if filters.include_labels.len() == 1 {
+            server_side.push(format!("include_labels: {:?}", filters.include_labels));
+        }
+        if let Some(since) = since {
+            server_side.push(format!("updated_since: {}", since.to_rfc3339()));
+        }
+        if let Some(author) = &filters.author {
+            server_side.push(format!("author: {}", author));
+        }
+        if let Some(assignee) = &filters.assignee {
+            server_side.push(format!("assignee: {}", assignee));
+        }
+
+        Self::List { since, server_side }
+    }
+
+    /// Filters GitHub applies under this plan, in `CollectionResult` notation
+    pub fn server_side_filters(&self) -> &[String] {
+        match self {
+            Self::List { server_side, .. } | Self::Search { server_side, .. } => server_side,
+        }
+    }
+
+    pub fn uses_search(&self) -> bool {
+        matches!(self, Self::Search { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::DateRange;
+    use crate::types::DateField;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_plan_selection() {
+        let repo = Repository::new("rust-lang", "rust");
+
+        let list = IssueFilters {
+            state: IssueState::Open,
+            include_labels: vec!["E-easy".to_string()],
+            ..Default::default()
+        };
+        let plan = CollectionPlan::for_filters(&repo, &list);
+        match &plan {
+            CollectionPlan::List { since, .. } => assert!(since.is_none()),
+            plan => panic!("expected list plan, got {:?}", plan),
+        }
+        assert_eq!(
+            plan.server_side_filters(),
+            ["state: Open", "include_labels: [\"E-easy\"]"]
+        );
+
+        // The defaults exclude labels and pull requests, both done locally
+        assert!(!CollectionPlan::for_filters(&repo, &IssueFilters::default()).uses_search());
+
+        // A keyword alone stays local, since search would drop `borrowck`
+        let keyword = IssueFilters {
+            required_keywords: vec!["borrow".to_string()],
+            ..Default::default()
+        };
+        let plan = CollectionPlan::for_filters(&repo, &keyword);
+        assert!(!plan.uses_search());
+        assert!(plan.server_side_filters().is_empty());
+
+        let search = IssueFilters {
+            state: IssueState::Closed,
+            include_labels: vec!["C-bug".to_string(), "A-diagnostics".to_string()],
+            exclude_labels: vec!["duplicate".to_string()],
+            date_range: Some(DateRange {
+                start: None,
+                end: Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+            }),
+            min_comments: Some(2),
+            required_keywords: vec!["E0502".to_string()],
+            milestone: Some("1.80".to_string()),
+            date_field: DateField::Closed,
+            ..Default::default()
+        };
+        let plan = CollectionPlan::for_filters(&repo, &search);
+        assert!(plan.uses_search());
+        match &plan {
+            CollectionPlan::Search { query, .. } => assert_eq!(
+                query.to_string(),
+                "repo:rust-lang/rust is:issue is:closed label:C-bug,A-diagnostics \
+                 -label:duplicate milestone:1.80 closed:<=2024-01-01T00:00:00Z comments:>=2"
+            ),
+            plan => panic!("expected search plan, got {:?}", plan),
+        }
+        assert_eq!(
+            plan.server_side_filters(),
+            [
+                "state: Closed",
+                "exclude_pull_requests: true",
+                "include_labels: [\"C-bug\", \"A-diagnostics\"]",
+                "exclude_labels: [\"duplicate\"]",
+                "date_range",
+                "min_comments: 2",
+                "milestone: 1.80",
+            ]
+        );
+    }
+}
@@ -0,0 +1,997 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::bots::BotClassifier;
+use crate::diagnostics::DiagnosticKind;
+use crate::environment::{Channel, CrateVersionReq};
+use crate::filters::{
+    DateRange, IssueFilters, IssueSort, IssueState, KeywordMatch, KeywordMode, KeywordRule,
+    KeywordScope, SortDirection, SortField,
+};
+use crate::types::DateField;
+
+/// Error from parsing a filter query, pointing at the offending term
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at column {column}")]
+pub struct QueryParseError {
+    /// 1-based character column where the term starts
+    pub column: usize,
+    pub message: String,
+}
+
+impl QueryParseError {
+    fn new(column: usize, message: impl Into<String>) -> Self {
+        Self {
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+/// One whitespace-separated term of a query
+#[derive(Debug)]
+struct Term {
+    column: usize,
+    negated: bool,
+    key: Option<String>,
+    value: String,
+    /// `value` split at commas outside quotes, without unquoted empty parts
+    parts: Vec<String>,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Term>, QueryParseError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut terms = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let column = i + 1;
+        let negated = chars[i] == '-';
+        if negated {
+            i += 1;
+        }
+
+        let mut key = None;
+        let mut text = String::new();
+        let mut parts = Vec::new();
+        let mut part = String::new();
+        let mut quoted = false;
+        let mut part_quoted = false;
+
+        while i < chars.len() && !chars[i].is_whitespace() {
+            match chars[i] {
+                '"' => {
+                    let start = i;
+                    i += 1;
+                    loop {
+                        match chars.get(i) {
+                            None => return Err(QueryParseError::new(start + 1, "Unclosed quote")),
+                            Some('"') => break,
+                            Some('\\') if chars.get(i + 1) == Some(&'"') => {
+                                text.push('"');
+                                part.push('"');
+                                i += 2;
+                            }
+                            Some(&c) => {
+                                text.push(c);
+                                part.push(c);
+                                i += 1;
+                            }
+                        }
+                    }
+                    quoted = true;
+                    part_quoted = true;
+                }
+                ':' if key.is_none() && !quoted => {
+                    key = Some(std::mem::take(&mut text).to_lowercase());
+                    part.clear();
+                }
+                ',' => {
+                    text.push(',');
+                    if !part.is_empty() || part_quoted {
+                        parts.push(std::mem::take(&mut part));
+                    }
+                    part_quoted = false;
+                }
+                c => {
+                    text.push(c);
+                    part.push(c);
+                }
+            }
+            i += 1;
+        }
+
+        if text.is_empty() && !quoted {
+            let message = match &key {
+                Some(key) => format!("Missing value for `{}:`", key),
+                None => "Expected a term after `-`".to_string(),
+            };
+            return Err(QueryParseError::new(column, message));
+        }
+
+        if !part.is_empty() || part_quoted {
+            parts.push(part);
+        }
+        terms.push(Term {
+            column,
+            negated,
+            key,
+            value: text,
+            parts,
+        });
+    }
+
+    Ok(terms)
+}
+
+fn parse_count(term: &Term) -> Result<u32, QueryParseError> {
+    let value = term.value.as_str();
+    let (offset, digits) = if let Some(rest) = value.strip_prefix(">=") {
+        (0, rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (1, rest)
+    } else if value.starts_with('<') {
+        return Err(QueryParseError::new(
+            term.column,
+            format!("Only lower bounds are supported for `{}:`", key_name(term)),
+        ));
+    } else {
+        (0, value)
+    };
+
+    let n = digits.parse::<u32>().map_err(|_| {
+        QueryParseError::new(
+            term.column,
+            format!(
+                "Expected a number like `>=3` for `{}:`, got `{}`",
+                key_name(term),
+                value
+            ),
+        )
+    })?;
+    n.checked_add(offset).ok_or_else(|| {
+        QueryParseError::new(
+            term.column,
+            format!("`{}` is out of range for `{}:`", value, key_name(term)),
+        )
+    })
+}
+
+/// `created-desc`, `updated-asc`, `comments-desc`, ...
+fn parse_sort(term: &Term) -> Result<IssueSort, QueryParseError> {
+    let value = term.value.to_lowercase();
+    let (field, direction) = value.split_once('-').unwrap_or((value.as_str(), "desc"));
+    let field = match field {
+        "created" => Some(SortField::Created),
+        "updated" => Some(SortField::Updated),
+        "comments" => Some(SortField::Comments),
+        _ => None,
+    };
+    let direction = match direction {
+        "asc" => Some(SortDirection::Ascending),
+        "desc" => Some(SortDirection::Descending),
+        _ => None,
+    };
+    match (field, direction) {
+        (Some(field), Some(direction)) => Ok(IssueSort::new(field, direction)),
+        _ => Err(QueryParseError::new(
+            term.column,
+            format!(
+                "Unknown sort `{}`, expected created, updated or comments with -asc or -desc",
+                term.value
+            ),
+        )),
+    }
+}
+
+fn sort_query(sort: &IssueSort) -> String {
+    let (field, direction) = sort.api_params();
+    format!("sort:{}-{}", field, direction)
+}
+
+/// `kw.<options>:<patterns>`, e.g. `-kw.word.title:RFC,tracking`; `kw:,` has no patterns
+fn parse_keyword_rule(term: &Term, options: &str) -> Result<KeywordRule, QueryParseError> {
+    let mut rule = KeywordRule::any(&term.parts);
+    let mut scope: Option<KeywordScope> = None;
+    for option in options.split('.').filter(|option| !option.is_empty()) {
+        let mut scope_part = |set: fn(&mut KeywordScope)| {
+            let scope = scope.get_or_insert(KeywordScope::NONE);
+            set(scope);
+        };
+        match option {
+            "any" => rule.mode = KeywordMode::Any,
+            "all" => rule.mode = KeywordMode::All,
+            "word" => rule.matching = KeywordMatch::WholeWord,
+            "regex" => rule.matching = KeywordMatch::Regex,
+            "case" => rule.case_sensitive = true,
+            "prose" => rule.prose_only = true,
+            "title" => scope_part(|scope| scope.title = true),
+            "body" => scope_part(|scope| scope.body = true),
+            "comments" => scope_part(|scope| scope.comments = true),
+            "none" => scope_part(|_| {}),
+            _ => {
+                return Err(QueryParseError::new(
+                    term.column,
+                    format!(
+                        "Unknown keyword option `{}`, expected any, all, word, regex, case, \
+                         prose, title, body, comments or none",
+                        option
+                    ),
+                ))
+            }
+        }
+    }
+    if let Some(scope) = scope {
+        rule.scope = scope;
+    }
+    rule.exclude = term.negated;
+
+    if let Some(keyword) = rule
+        .keywords
+        .iter()
+        .find(|keyword| rule.pattern(keyword).is_err())
+    {
+        return Err(QueryParseError::new(
+            term.column,
+            format!("Invalid regex `{}` for `{}:`", keyword, key_name(term)),
+        ));
+    }
+
+    Ok(rule)
+}
+
+fn keyword_rule_query(rule: &KeywordRule) -> String {
+    let mut key = String::from("kw");
+    if rule.mode == KeywordMode::All {
+        key.push_str(".all");
+    }
+    match rule.matching {
+        KeywordMatch::Substring => {}
+        KeywordMatch::WholeWord => key.push_str(".word"),
+        KeywordMatch::Regex => key.push_str(".regex"),
+    }
+    if rule.case_sensitive {
+        key.push_str(".case");
+    }
+    if rule.scope == KeywordScope::NONE {
+        key.push_str(".none");
+    } else if rule.scope != KeywordScope::default() {
+        for (enabled, name) in [
+            (rule.scope.title, ".title"),
+            (rule.scope.body, ".body"),
+            (rule.scope.comments, ".comments"),
+        ] {
+            if enabled {
+                key.push_str(name);
+            }
+        }
+    }
+    if rule.prose_only {
+        key.push_str(".prose");
+    }
+
+    format!(
+        "{}{}:{}",
+        if rule.exclude { "-" } else { "" },
+        key,
+        list_query(&rule.keywords)
+    )
+}
+
+/// The bot classifier `-is:bot` and `bot-*:` terms configure
+fn bots(filters: &mut IssueFilters) -> &mut BotClassifier {
+    filters
+        .exclude_bots
+        .get_or_insert_with(BotClassifier::default)
+}
+
+fn key_name(term: &Term) -> &str {
+    term.key.as_deref().unwrap_or("")
+}
+
+/// A date (`2024-01-31`) or timestamp (`2024-01-31T12:00:00Z`); dates cover the whole day
+fn parse_date(text: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        let time = if end_of_day {
+            NaiveTime::from_hms_opt(23, 59, 59)?
+        } else {
+            NaiveTime::from_hms_opt(0, 0, 0)?
+        };
+        return Some(Utc.from_utc_datetime(&date.and_time(time)));
+    }
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn parse_date_range(term: &Term) -> Result<DateRange, QueryParseError> {
+    let value = term.value.as_str();
+    let date = |text: &str, end_of_day: bool| {
+        if text == "*" {
+            return Ok(None);
+        }
+        parse_date(text, end_of_day).map(Some).ok_or_else(|| {
+            QueryParseError::new(
+                term.column,
+                format!(
+                    "Invalid date `{}` for `{}:`, expected YYYY-MM-DD or RFC 3339",
+                    text,
+                    key_name(term)
+                ),
+            )
+        })
+    };
+
+    let (start, end) = if let Some((start, end)) = value.split_once("..") {
+        (date(start, false)?, date(end, true)?)
+    } else if let Some(start) = value.strip_prefix(">=") {
+        (date(start, false)?, None)
+    } else if let Some(end) = value.strip_prefix("<=") {
+        (None, date(end, true)?)
+    } else {
+        (date(value, false)?, date(value, true)?)
+    };
+
+    if let (Some(start), Some(end)) = (start, end) {
+        if start > end {
+            return Err(QueryParseError::new(
+                term.column,
+                format!("Date range `{}` ends before it starts", value),
+            ));
+        }
+    }
+
+    Ok(DateRange { start, end })
+}
+
+impl IssueFilters {
+    /// Parse a filter query such as
+    /// `state:closed label:A-borrowck -label:duplicate comments:>=3 has:code "lifetime"`
+    ///
+    /// Supported terms:
+    /// - `state:open|closed|all`
+    /// - `is:issue` (exclude pull requests), `is:any`, `-is:bot` (exclude bot
+    ///   authors with the default `BotClassifier`)
+    /// - `bot-allow:a,b`, `bot-deny:a,b` and `bot-signals:type,suffix` replace
+    ///   the classifier's allow list, deny list and signals, `,` for none
+    /// - `label:X` (any of), `-label:X`
+    /// - `comments:>=N`, `body:>=N` (minimum body length)
+    /// - `author:LOGIN`, `assignee:LOGIN`, `milestone:TITLE`, `reactions:>=N`
+    /// - `association:member,owner` (any of the `author_association` values)
+    /// - `created:A..B`, `created:>=A`, `created:<=B`, `created:A` with
+    ///   `YYYY-MM-DD` or RFC 3339 dates, `*` for an open end; `updated:`,
+    ///   `closed:` or `merged:` instead of `created:` pick another timestamp
+    /// - `sort:created|updated|comments-asc|desc`
+    /// - `has:code`, `has:rust-error`, `has:repro`
+    /// - `channel:nightly`, `crate:tokio>=1.30` (versions the body reports)
+    /// - `diag:panic,clippy-lint` (any of the diagnostic kinds; other names are
+    ///   custom kinds, found once `diagnostic_registry` holds their extractors)
+    /// - bare or quoted keywords (any of), `-keyword` to exclude
+    /// - `kw.<options>:a,b` keyword rules, `-kw...` to exclude; options are
+    ///   `any`/`all`, `word`/`regex`, `case`, `prose` and any of `title`,
+    ///   `body`, `comments` (title and body when none is given) or `none`;
+    ///   `kw:,` is a rule without keywords
+    /// - `""` for an empty value
+    ///
+    /// Unlike `IssueFilters::default()`, nothing is filtered unless the query
+    /// says so; `to_query` writes the inverse.
+    pub fn parse(query: &str) -> Result<Self, QueryParseError> {
+        let mut filters = Self {
+            state: IssueState::All,
+            include_labels: vec![],
+            exclude_labels: vec![],
+            rust_errors_only: false,
+            code_blocks_only: false,
+            min_body_length: None,
+            date_range: None,
+            include_pull_requests: true,
+            min_comments: None,
+            required_keywords: vec![],
+            excluded_keywords: vec![],
+            keyword_rules: vec![],
+            author: None,
+            assignee: None,
+            milestone: None,
+            min_reactions: None,
+            author_associations: vec![],
+            date_field: DateField::Created,
+            sort: IssueSort::default(),
+            exclude_bots: None,
+            diagnostic_kinds: vec![],
+            diagnostic_registry: None,
+            repro_only: false,
+            channel: None,
+            crate_versions: vec![],
+        };
+
+        for term in tokenize(query)? {
+            let Some(key) = term.key.as_deref() else {
+                if term.negated {
+                    filters.excluded_keywords.push(term.value);
+                } else {
+                    filters.required_keywords.push(term.value);
+                }
+                continue;
+            };
+
+            if let Some(options) = key
+                .strip_prefix("kw")
+                .filter(|rest| rest.is_empty() || rest.starts_with('.'))
+            {
+                filters
+                    .keyword_rules
+                    .push(parse_keyword_rule(&term, options)?);
+                continue;
+            }
+
+            let negatable = key == "label" || (key == "is" && term.value == "bot");
+            if term.negated && !negatable {
+                return Err(QueryParseError::new(
+                    term.column,
+                    format!("`{}:` cannot be negated", key),
+                ));
+            }
+
+            let value = term.value.to_lowercase();
+            match key {
+                "state" => {
+                    filters.state = match value.as_str() {
+                        "open" => IssueState::Open,
+                        "closed" => IssueState::Closed,
+                        "all" => IssueState::All,
+                        _ => {
+                            return Err(QueryParseError::new(
+                                term.column,
+                                format!(
+                                    "Unknown state `{}`, expected open, closed or all",
+                                    term.value
+                                ),
+                            ))
+                        }
+                    }
+                }
+                "is" => match value.as_str() {
+                    "issue" => filters.include_pull_requests = false,
+                    "any" => filters.include_pull_requests = true,
+                    "bot" if term.negated => {
+                        bots(&mut filters);
+                    }
+                    "bot" => {
+                        return Err(QueryParseError::new(
+                            term.column,
+                            "`is:bot` is only supported negated, as `-is:bot`",
+                        ))
+                    }
+                    _ => {
+                        return Err(QueryParseError::new(
+                            term.column,
+                            format!(
+                                "Unknown `is:` value `{}`, expected issue, any or bot",
+                                term.value
+                            ),
+                        ))
+                    }
+                },
+                "bot-allow" => bots(&mut filters).allow = term.parts,
+                "bot-deny" => bots(&mut filters).deny = term.parts,
+                "bot-signals" => {
+                    let bots = bots(&mut filters);
+                    bots.use_user_type = false;
+                    bots.use_login_suffix = false;
+                    for signal in &term.parts {
+                        match signal.to_lowercase().as_str() {
+                            "type" => bots.use_user_type = true,
+                            "suffix" => bots.use_login_suffix = true,
+                            _ => {
+                                return Err(QueryParseError::new(
+                                    term.column,
+                                    format!(
+                                        "Unknown bot signal `{}`, expected type or suffix",
+                                        signal
+                                    ),
+                                ))
+                            }
+                        }
+                    }
+                }
+                "label" if term.negated => filters.exclude_labels.push(term.value),
+                "label" => filters.include_labels.push(term.value),
+                "comments" => filters.min_comments = Some(parse_count(&term)?),
+                "body" => filters.min_body_length = Some(parse_count(&term)? as usize),
+                "created" | "updated" | "closed" | "merged" => {
+                    let field = match key {
+                        "created" => DateField::Created,
+                        "updated" => DateField::Updated,
+                        "closed" => DateField::Closed,
+                        _ => DateField::Merged,
+                    };
+                    if filters.date_range.is_some() {
+                        return Err(QueryParseError::new(
+                            term.column,
+                            "Only one date range is supported",
+                        ));
+                    }
+                    filters.date_range = Some(parse_date_range(&term)?);
+                    filters.date_field = field;
+                }
+                "author" => filters.author = Some(term.value),
+                "assignee" => filters.assignee = Some(term.value),
+                "milestone" => filters.milestone = Some(term.value),
+                "reactions" => filters.min_reactions = Some(parse_count(&term)?),
+                "association" => filters
+                    .author_associations
+                    .extend(term.parts.iter().map(|part| part.to_uppercase())),
+                "sort" => filters.sort = parse_sort(&term)?,
+                "channel" => {
+                    filters.channel = Some(Channel::from_name(&value).ok_or_else(|| {
+                        QueryParseError::new(
+                            term.column,
+                            format!(
+                                "Unknown channel `{}`, expected stable, beta, nightly or dev",
+                                term.value
+                            ),
+                        )
+                    })?)
+                }
+                "crate" => filters.crate_versions.push(
+                    term.value
+                        .parse::<CrateVersionReq>()
+                        .map_err(|message| QueryParseError::new(term.column, message))?,
+                ),
+                "diag" => filters.diagnostic_kinds.extend(
+                    term.parts
+                        .iter()
+                        .map(|part| DiagnosticKind::from_name(part)),
+                ),
+                "has" => match value.as_str() {
+                    "code" => filters.code_blocks_only = true,
+                    "rust-error" => filters.rust_errors_only = true,
+                    "repro" => filters.repro_only = true,
+                    _ => {
+                        return Err(QueryParseError::new(
+                            term.column,
+                            format!(
+                                "Unknown `has:` value `{}`, expected code, rust-error or repro",
+                                term.value
+                            ),
+                        ))
+                    }
+                },
+                _ => {
+                    return Err(QueryParseError::new(
+                        term.column,
+                        format!("Unknown qualifier `{}:`", key),
+                    ))
+                }
+            }
+        }
+
+        Ok(filters)
+    }
+
+    /// Write the filters as a query that `IssueFilters::parse` turns back into them
+    pub fn to_query(&self) -> String {
+        self.query_terms().join(" ")
+    }
+
+    /// The terms of `to_query`, one per filter setting
+    pub fn query_terms(&self) -> Vec<String> {
+        let mut parts: Vec<String> = Vec::new();
+
+        match self.state {
+            IssueState::Open => parts.push("state:open".to_string()),
+            IssueState::Closed => parts.push("state:closed".to_string()),
+            IssueState::All => {}
+        }
+        if !self.include_pull_requests {
+            parts.push("is:issue".to_string());
+        }
+        if let Some(bots) = &self.exclude_bots {
+            parts.push("-is:bot".to_string());
+            let defaults = BotClassifier::default();
+            if bots.allow != defaults.allow {
+                parts.push(format!("bot-allow:{}", list_query(&bots.allow)));
+            }
+            if bots.deny != defaults.deny {
+                parts.push(format!("bot-deny:{}", list_query(&bots.deny)));
+            }
+            if (bots.use_user_type, bots.use_login_suffix)
+                != (defaults.use_user_type, defaults.use_login_suffix)
+            {
+                let signals: Vec<&str> = [
+                    (bots.use_user_type, "type"),
+                    (bots.use_login_suffix, "suffix"),
+                ]
+                .iter()
+                .filter(|(enabled, _)| *enabled)
+                .map(|(_, name)| *name)
+                .collect();
+                parts.push(format!("bot-signals:{}", list_query(&signals)));
+            }
+        }
+        parts.extend(
+            self.include_labels
+                .iter()
+                .map(|label| format!("label:{}", quote(label))),
+        );
+        parts.extend(
+            self.exclude_labels
+                .iter()
+                .map(|label| format!("-label:{}", quote(label))),
+        );
+        if let Some(author) = &self.author {
+            parts.push(format!("author:{}", quote(author)));
+        }
+        if let Some(assignee) = &self.assignee {
+            parts.push(format!("assignee:{}", quote(assignee)));
+        }
+        if let Some(milestone) = &self.milestone {
+            parts.push(format!("milestone:{}", quote(milestone)));
+        }
+        if let Some(min_comments) = self.min_comments {
+            parts.push(format!("comments:>={}", min_comments));
+        }
+        if let Some(min_reactions) = self.min_reactions {
+            parts.push(format!("reactions:>={}", min_reactions));
+        }
+        if !self.author_associations.is_empty() {
+            let associations: Vec<String> = self
+                .author_associations
+                .iter()
+                .map(|association| association.to_lowercase())
+                .collect();
+            parts.push(format!("association:{}", list_query(&associations)));
+        }
+        if let Some(min_length) = self.min_body_length {
+            parts.push(format!("body:>={}", min_length));
+        }
+        if let Some(range) = &self.date_range {
+            let start = range.start.map(|date| format_date(date, false));
+            let end = range.end.map(|date| format_date(date, true));
+            let key = self.date_field.name();
+            match (start, end) {
+                (Some(start), Some(end)) => parts.push(format!("{}:{}..{}", key, start, end)),
+                (Some(start), None) => parts.push(format!("{}:>={}", key, start)),
+                (None, Some(end)) => parts.push(format!("{}:<={}", key, end)),
+                (None, None) => {}
+            }
+        }
+        if self.code_blocks_only {
+            parts.push("has:code".to_string());
+        }
+        if self.rust_errors_only {
+            parts.push("has:rust-error".to_string());
+        }
+        if self.repro_only {
+            parts.push("has:repro".to_string());
+        }
+        if let Some(channel) = self.channel {
+            parts.push(format!("channel:{}", channel.name()));
+        }
+        parts.extend(
+            self.crate_versions
+                .iter()
+                .map(|requirement| format!("crate:{}", quote(&requirement.to_string()))),
+        );
+        if !self.diagnostic_kinds.is_empty() {
+            let kinds: Vec<&str> = self
+                .diagnostic_kinds
+                .iter()
+                .map(|kind| kind.name())
+                .collect();
+            parts.push(format!("diag:{}", list_query(&kinds)));
+        }
+        parts.extend(self.required_keywords.iter().map(|keyword| quote(keyword)));
+        parts.extend(
+            self.excluded_keywords
+                .iter()
+                .map(|keyword| format!("-{}", quote(keyword))),
+        );
+        parts.extend(self.keyword_rules.iter().map(keyword_rule_query));
+        if self.sort != IssueSort::default() {
+            parts.push(sort_query(&self.sort));
+        }
+
+        parts
+    }
+}
+
+impl FromStr for IssueFilters {
+    type Err = QueryParseError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        Self::parse(query)
+    }
+}
+
+impl fmt::Display for IssueFilters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_query())
+    }
+}
+
+/// Quote a value that would otherwise be split or read as a qualifier
+fn quote(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.starts_with('-')
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == ':');
+    if needs_quotes {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Comma-separated values, quoting the ones with commas; `,` for none
+fn list_query<S: AsRef<str>>(values: &[S]) -> String {
+    if values.is_empty() {
+        return ",".to_string();
+    }
+    values
+        .iter()
+        .map(|value| {
+            let value = value.as_ref();
+            if value.contains(',') {
+                format!("\"{}\"", value.replace('"', "\\\""))
+            } else {
+                quote(value)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Whole days as `YYYY-MM-DD`, anything else as RFC 3339
+fn format_date(date: DateTime<Utc>, end_of_day: bool) -> String {
+    let whole_day = if end_of_day {
+        (date.hour(), date.minute(), date.second()) == (23, 59, 59) && date.nanosecond() == 0
+    } else {
+        date.num_seconds_from_midnight() == 0 && date.nanosecond() == 0
+    };
+    if whole_day {
+        date.format("%Y-%m-%d").to_string()
+    } else {
+        date.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::{Version, VersionOp};
+
+    #[test]
+    fn test_parse_query() {
+        let filters = IssueFilters::parse(
+            "state:closed label:A-borrowck -label:duplicate comments:>=3 \
+             created:2024-01-01..2024-06-30 has:code has:rust-error has:repro diag:panic,miri-ub \
+             \"lifetime\" -\"help wanted\"",
+        )
+        .unwrap();
+
+        assert_eq!(filters.state, IssueState::Closed);
+        assert_eq!(filters.include_labels, vec!["A-borrowck"]);
+        assert_eq!(filters.exclude_labels, vec!["duplicate"]);
+        assert_eq!(filters.min_comments, Some(3));
+        assert!(filters.code_blocks_only && filters.rust_errors_only && filters.repro_only);
+        assert_eq!(
+            filters.diagnostic_kinds,
+            vec![
+                DiagnosticKind::Panic,
+                DiagnosticKind::Custom("miri-ub".to_string())
+            ]
+        );
+        assert!(filters.include_pull_requests);
+        assert_eq!(filters.required_keywords, vec!["lifetime"]);
+        assert_eq!(filters.excluded_keywords, vec!["help wanted"]);
+
+        let range = filters.date_range.as_ref().unwrap();
+        assert_eq!(
+            range.start,
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            range.end,
+            Some(Utc.with_ymd_and_hms(2024, 6, 30, 23, 59, 59).unwrap())
+        );
+
+        assert_eq!(
+            filters.to_query(),
+            "state:closed label:A-borrowck -label:duplicate comments:>=3 \
+             created:2024-01-01..2024-06-30 has:code has:rust-error has:repro diag:panic,miri-ub \
+             lifetime -\"help wanted\""
+        );
+    }
+
+    #[test]
+    fn test_environment_terms() {
+        let filters =
+            IssueFilters::parse("channel:Nightly crate:tokio>=1.30 crate:hyper<1").unwrap();
+        assert_eq!(filters.channel, Some(Channel::Nightly));
+        assert_eq!(
+            filters.crate_versions,
+            vec![
+                CrateVersionReq::new("tokio", VersionOp::Ge, Version::new(1, 30, 0)),
+                CrateVersionReq::new("hyper", VersionOp::Lt, Version::new(1, 0, 0)),
+            ]
+        );
+        assert!(filters
+            .to_query()
+            .ends_with("channel:nightly crate:tokio>=1.30.0 crate:hyper<1.0.0"));
+
+        assert!(IssueFilters::parse("channel:canary").is_err());
+        assert!(IssueFilters::parse("crate:tokio").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_default() {
+        let defaults = IssueFilters::default();
+        let query = defaults.to_query();
+        assert_eq!(
+            query,
+            "is:issue -label:duplicate -label:invalid -label:wontfix -label:question \
+             body:>=50 -kw.word.title:discussion,RFC,tracking"
+        );
+
+        let parsed = IssueFilters::parse(&query).unwrap();
+        assert_eq!(parsed.to_query(), query);
+        assert_eq!(parsed.exclude_labels, defaults.exclude_labels);
+        assert_eq!(parsed.min_body_length, Some(50));
+        assert_eq!(parsed.keyword_rules, defaults.keyword_rules);
+    }
+
+    #[test]
+    fn test_round_trip_edge_cases() {
+        let mut filters = IssueFilters::parse("").unwrap();
+        filters.exclude_bots = Some(
+            BotClassifier {
+                deny: vec![],
+                ..BotClassifier::deny_list_only()
+            }
+            .allow("bors")
+            .allow("a,b"),
+        );
+        filters.required_keywords = vec![String::new()];
+        filters.excluded_keywords = vec![String::new()];
+        filters.keyword_rules = vec![
+            KeywordRule::any(Vec::<String>::new()),
+            KeywordRule::any(["", "x"]).excluding(),
+            KeywordRule {
+                scope: KeywordScope::NONE,
+                ..KeywordRule::any(["y"])
+            },
+        ];
+
+        let query = filters.to_query();
+        assert_eq!(
+            query,
+            r#"-is:bot bot-allow:bors,"a,b" bot-deny:, bot-signals:, "" -"" kw:, -kw:"",x kw.none:y"#
+        );
+        let parsed = IssueFilters::parse(&query).unwrap();
+        assert_eq!(parsed.exclude_bots, filters.exclude_bots);
+        assert_eq!(parsed.required_keywords, filters.required_keywords);
+        assert_eq!(parsed.excluded_keywords, filters.excluded_keywords);
+        assert_eq!(parsed.keyword_rules, filters.keyword_rules);
+        assert_eq!(parsed.to_query(), query);
+
+        let custom = IssueFilters::parse("bot-deny:my-ci -is:bot").unwrap();
+        assert_eq!(
+            custom.exclude_bots,
+            Some(BotClassifier {
+                deny: vec!["my-ci".to_string()],
+                ..BotClassifier::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_people_and_dates() {
+        let query = "-is:bot author:alice assignee:bob milestone:\"1.80 beta\" reactions:>=5 \
+                     association:first_time_contributor,contributor \
+                     merged:>=2024-05-01 sort:comments-asc";
+        let filters = IssueFilters::parse(query).unwrap();
+
+        assert_eq!(filters.exclude_bots, Some(BotClassifier::default()));
+        assert_eq!(filters.author.as_deref(), Some("alice"));
+        assert_eq!(filters.assignee.as_deref(), Some("bob"));
+        assert_eq!(filters.milestone.as_deref(), Some("1.80 beta"));
+        assert_eq!(filters.min_reactions, Some(5));
+        assert_eq!(
+            filters.author_associations,
+            vec!["FIRST_TIME_CONTRIBUTOR", "CONTRIBUTOR"]
+        );
+        assert_eq!(filters.date_field, DateField::Merged);
+        assert_eq!(
+            filters.sort,
+            IssueSort::new(SortField::Comments, SortDirection::Ascending)
+        );
+        assert_eq!(filters.to_query(), query);
+
+        assert_eq!(
+            IssueFilters::parse("created:2024-01-01 closed:2024-02-01")
+                .unwrap_err()
+                .to_string(),
+            "Only one date range is supported at column 20"
+        );
+        assert!(IssueFilters::parse("sort:reactions").is_err());
+    }
+
+    #[test]
+    fn test_parse_keyword_rules() {
+        let query = r#"kw.all.regex.case.body.comments.prose:E0\d{3},"a, b" -kw:wip"#;
+        let filters = IssueFilters::parse(query).unwrap();
+
+        let rule = &filters.keyword_rules[0];
+        assert_eq!(rule.keywords, vec![r"E0\d{3}", "a, b"]);
+        assert_eq!(rule.mode, KeywordMode::All);
+        assert_eq!(rule.matching, KeywordMatch::Regex);
+        assert!(rule.case_sensitive && rule.prose_only && !rule.exclude);
+        assert_eq!(
+            rule.scope,
+            KeywordScope {
+                title: false,
+                body: true,
+                comments: true
+            }
+        );
+        assert_eq!(
+            filters.keyword_rules[1],
+            KeywordRule::any(["wip"]).excluding()
+        );
+        assert_eq!(filters.to_query(), query);
+        assert_eq!(filters.comment_rules().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |query: &str| IssueFilters::parse(query).unwrap_err().to_string();
+
+        assert_eq!(
+            error("state:closed comments:many"),
+            "Expected a number like `>=3` for `comments:`, got `many` at column 14"
+        );
+        assert_eq!(
+            error("label:bug created:2024-13-01"),
+            "Invalid date `2024-13-01` for `created:`, expected YYYY-MM-DD or RFC 3339 at column 11"
+        );
+        assert_eq!(
+            error("project:1.0"),
+            "Unknown qualifier `project:` at column 1"
+        );
+        assert_eq!(
+            error("-state:open"),
+            "`state:` cannot be negated at column 1"
+        );
+        assert_eq!(error("label:\"good first"), "Unclosed quote at column 7");
+        assert_eq!(
+            error("comments:"),
+            "Missing value for `comments:` at column 1"
+        );
+        assert_eq!(
+            error("kw.fuzzy:async"),
+            "Unknown keyword option `fuzzy`, expected any, all, word, regex, case, \
+             prose, title, body, comments or none at column 1"
+        );
+        assert_eq!(
+            error("bot-signals:login"),
+            "Unknown bot signal `login`, expected type or suffix at column 1"
+        );
+        assert_eq!(
+            error("state:open reactions:>4294967295"),
+            "`>4294967295` is out of range for `reactions:` at column 12"
+        );
+        assert_eq!(
+            error("state:open -kw.regex:(,wip"),
+            "Invalid regex `(` for `kw.regex:` at column 12"
+        );
+    }
+}
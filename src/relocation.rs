@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+
+use crate::diff::{parse_patch, DiffLine, DiffLineKind};
+use crate::types::{PrFile, PrReviewComment};
+
+/// Number of lines before the commented line used to disambiguate matches
+const FINGERPRINT_CONTEXT: usize = 3;
+
+/// Where an outdated review comment's code lives in the current diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommentLocation {
+    /// The commented line was found at exactly one best-matching place
+    Relocated {
+        line: u32,
+        /// Side of the current diff: LEFT or RIGHT
+        side: String,
+        /// How many preceding lines of the original hunk also matched
+        context_matches: usize,
+    },
+    /// The commented line matches several places equally well
+    Ambiguous { lines: Vec<u32>, side: String },
+    /// The commented line is not in any changed region of the current diff;
+    /// it may still exist unchanged outside them
+    OutsideDiff,
+    /// The current diff removes the commented line, or the whole file
+    Deleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelocatedComment {
+    pub comment: PrReviewComment,
+    pub location: CommentLocation,
+}
+
+/// Relocate every comment made on a commit other than `head_sha` onto the
+/// current PR files, skipping file-level comments
+pub fn relocate_outdated_comments(
+    comments: &[PrReviewComment],
+    files: &[PrFile],
+    head_sha: &str,
+) -> Vec<RelocatedComment> {
+    comments
+        .iter()
+        .filter(|comment| {
+            !comment.is_file_level() && comment.commit_id.as_deref() != Some(head_sha)
+        })
+        .map(|comment| RelocatedComment {
+            comment: comment.clone(),
+            location: relocate_comment(comment, files),
+        })
+        .collect()
+}
+
+/// Find where a comment's code lives in the current diff by matching its `diff_hunk`
+///
+/// The last line of the hunk is the commented line; up to three lines before it
+/// on the same side act as a fingerprint to break ties. Only the changed regions
+/// in `PrFile::patch` are searched, so code that is now outside them is
+/// reported as [`CommentLocation::OutsideDiff`]; [`CommentLocation::Deleted`]
+/// means the diff visibly removes the line or the file.
+pub fn relocate_comment(comment: &PrReviewComment, files: &[PrFile]) -> CommentLocation {
+    let left = comment
+        .side
+        .as_deref()
+        .is_some_and(|side| side.eq_ignore_ascii_case("LEFT"));
+    let on_side = |line: &DiffLine| {
+        if left {
+            line.is_old_side()
+        } else {
+            line.is_new_side()
+        }
+    };
+
+    let original: Vec<String> = parse_patch(&comment.diff_hunk)
+        .into_iter()
+        .flat_map(|hunk| hunk.lines)
+        .filter(|line| on_side(line))
+        .map(|line| line.content)
+        .collect();
+    let (anchor, context) = match original.split_last() {
+        Some(split) => split,
+        None => return CommentLocation::OutsideDiff,
+    };
+    let context = &context[context.len().saturating_sub(FINGERPRINT_CONTEXT)..];
+
+    // A file missing from the PR files no longer differs from the base
+    let file = match files.iter().find(|file| file.filename == comment.path) {
+        Some(file) => file,
+        None => return CommentLocation::OutsideDiff,
+    };
+    if file.status.eq_ignore_ascii_case("removed") {
+        return CommentLocation::Deleted;
+    }
+    let patch = match file.patch.as_deref() {
+        Some(patch) => patch,
+        None => return CommentLocation::OutsideDiff,
+    };
+
+    let mut candidates: Vec<(u32, usize)> = Vec::new();
+    let mut removed = false;
+    for hunk in parse_patch(patch) {
+        removed |= !left
+            && hunk.lines.iter().any(|line| {
+                line.kind == DiffLineKind::Removed && line.content.trim() == anchor.trim()
+            });
+
+        let lines: Vec<&DiffLine> = hunk.lines.iter().filter(|line| on_side(line)).collect();
+
+        for (i, line) in lines.iter().enumerate() {
+            if line.content.trim() != anchor.trim() {
+                continue;
+            }
+
+            let context_matches = context
+                .iter()
+                .rev()
+                .zip(lines[..i].iter().rev())
+                .take_while(|(expected, actual)| expected.trim() == actual.content.trim())
+                .count();
+            // A blank anchor line says nothing on its own
+            if anchor.trim().is_empty() && context_matches == 0 {
+                continue;
+            }
+
+            let number = if left { line.old_line } else { line.new_line };
+            if let Some(number) = number {
+                candidates.push((number, context_matches));
+            }
+        }
+    }
+
+    let best = match candidates.iter().map(|(_, score)| *score).max() {
+        Some(best) => best,
+        None if removed && !anchor.trim().is_empty() => return CommentLocation::Deleted,
+        None => return CommentLocation::OutsideDiff,
+    };
+    let lines: Vec<u32> = candidates
+        .iter()
+        .filter(|(_, score)| *score == best)
+        .map(|(line, _)| *line)
+        .collect();
+    let side = if left { "LEFT" } else { "RIGHT" }.to_string();
+
+    match lines.as_slice() {
+        [line] => CommentLocation::Relocated {
+            line: *line,
+            side,
+            context_matches: best,
+        },
+        _ => CommentLocation::Ambiguous { lines, side },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::review_comment;
+
+    fn outdated_comment(diff_hunk: &str) -> PrReviewComment {
+        PrReviewComment {
+            body: "Handle the error here".to_string(),
+            line: None,
+            diff_hunk: diff_hunk.to_string(),
+            commit_id: Some("old".to_string()),
+            original_commit_id: Some("old".to_string()),
+            ..review_comment(7)
+        }
+    }
+
+    fn file(patch: &str) -> PrFile {
+        PrFile {
+            filename: "src/lib.rs".to_string(),
+            status: "Modified".to_string(),
+            additions: 0,
+            deletions: 0,
+            changes: 0,
+            patch: Some(patch.to_string()),
+        }
+    }
+
+    const HUNK: &str = "@@ -10,2 +10,3 @@ fn load() {\n let path = config();\n+let data = read(path);\n+let value = data.unwrap();";
+
+    #[test]
+    fn test_relocated() {
+        let files = vec![file(
+            "@@ -30,2 +30,5 @@\n+let value = other.unwrap();\n let path = config();\n+let data = read(path);\n+let value = data.unwrap();\n+done();",
+        )];
+        let current = PrReviewComment {
+            commit_id: Some("head".to_string()),
+            ..outdated_comment(HUNK)
+        };
+        let relocated =
+            relocate_outdated_comments(&[outdated_comment(HUNK), current], &files, "head");
+
+        assert_eq!(relocated.len(), 1);
+        assert_eq!(
+            relocated[0].location,
+            CommentLocation::Relocated {
+                line: 33,
+                side: "RIGHT".to_string(),
+                context_matches: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_outside_and_deleted() {
+        let ambiguous = vec![file(
+            "@@ -1,1 +1,3 @@\n+let value = data.unwrap();\n x\n+let value = data.unwrap();",
+        )];
+        assert_eq!(
+            relocate_comment(&outdated_comment(HUNK), &ambiguous),
+            CommentLocation::Ambiguous {
+                lines: vec![1, 3],
+                side: "RIGHT".to_string(),
+            }
+        );
+
+        let deleted = vec![file(
+            "@@ -1,1 +1,1 @@\n-let value = data.unwrap();\n+let value = data?;",
+        )];
+        assert_eq!(
+            relocate_comment(&outdated_comment(HUNK), &deleted),
+            CommentLocation::Deleted
+        );
+
+        // The line still exists, just not in a changed region
+        let elsewhere = vec![file(
+            "@@ -80,1 +80,2 @@
+ fn other() {}
++other();",
+        )];
+        assert_eq!(
+            relocate_comment(&outdated_comment(HUNK), &elsewhere),
+            CommentLocation::OutsideDiff
+        );
+        assert_eq!(
+            relocate_comment(&outdated_comment(HUNK), &[]),
+            CommentLocation::OutsideDiff
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{CiStatus, Comparison, PrFile, PrMergeStatus, PrReview, PrReviewComment};
+
+/// What changed on a PR since a reviewer last looked at it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangesSinceReview {
+    pub reviewer: String,
+    /// The reviewer's latest submitted review
+    pub review: PrReview,
+    pub reviewed_commit: String,
+    pub head_sha: String,
+    /// Heuristic: the comparison shows the reviewed commit is no longer an
+    /// ancestor of the head, which usually means the branch was rebased or
+    /// force-pushed. See [`looks_rebased`].
+    pub rebased: bool,
+    /// GitHub could not compare the reviewed commit with the head, typically
+    /// because a force-push left it unreachable; `files` is then the whole PR diff
+    pub reviewed_commit_missing: bool,
+    /// Compare result between the reviewed commit and the head, if the commit still exists
+    pub comparison: Option<Comparison>,
+    /// Files changed since the review; the whole PR diff when the reviewed commit is missing
+    pub files: Vec<PrFile>,
+    /// Review comments from anyone posted after the review was submitted
+    pub new_review_comments: Vec<PrReviewComment>,
+}
+
+impl ChangesSinceReview {
+    pub fn is_up_to_date(&self) -> bool {
+        self.reviewed_commit == self.head_sha
+    }
+}
+
+/// Whether a comparison from a reviewed commit to the head suggests history
+/// was rewritten rather than extended
+///
+/// A head that only gained commits, including merges of the base branch, is
+/// `ahead`; a rebase leaves the reviewed commit behind, so the two have
+/// `diverged`. This is a heuristic: it cannot tell a rebase from a force-push
+/// of unrelated commits.
+pub fn looks_rebased(comparison: &Comparison) -> bool {
+    comparison.status == "diverged" || comparison.behind_by > 0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReviewState {
+    Approved,
+    ChangesRequested,
+    Commented,
+    Dismissed,
+    Pending,
+    Unknown,
+}
+
+impl ReviewState {
+    /// Parse `PrReview::state`, accepting both `ChangesRequested` and `CHANGES_REQUESTED` spellings
+    pub fn parse(state: &str) -> Self {
+        match state.replace('_', "").to_lowercase().as_str() {
+            "approved" => Self::Approved,
+            "changesrequested" => Self::ChangesRequested,
+            "commented" => Self::Commented,
+            "dismissed" => Self::Dismissed,
+            "pending" => Self::Pending,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Latest submitted review by `login` that records the commit it was made against
+pub fn latest_review_by<'a>(reviews: &'a [PrReview], login: &str) -> Option<&'a PrReview> {
+    reviews
+        .iter()
+        .filter(|review| review.user.login.eq_ignore_ascii_case(login))
+        .filter(|review| {
+            review.commit_id.is_some() && ReviewState::parse(&review.state) != ReviewState::Pending
+        })
+        .max_by_key(|review| review.submitted_at)
+}
+
+/// Review comments created strictly after `since`, in their original order
+pub fn comments_since(comments: &[PrReviewComment], since: DateTime<Utc>) -> Vec<PrReviewComment> {
+    comments
+        .iter()
+        .filter(|comment| comment.created_at > since)
+        .cloned()
+        .collect()
+}
+
+/// A reviewer's standing on a PR after all of their reviews are taken into account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewerStatus {
+    pub login: String,
+    pub state: ReviewState,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub commit_id: Option<String>,
+    /// Review that set the effective state
+    pub review_id: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReviewSummary {
+    /// Effective state per reviewer, in order of first review
+    pub reviewers: Vec<ReviewerStatus>,
+    /// Users whose review is requested and not yet given (or re-requested)
+    pub pending_reviewers: Vec<String>,
+    pub pending_teams: Vec<String>,
+}
+
+impl ReviewSummary {
+    pub fn approvals(&self) -> impl Iterator<Item = &ReviewerStatus> {
+        self.reviewers
+            .iter()
+            .filter(|r| r.state == ReviewState::Approved)
+    }
+
+    pub fn outstanding_change_requests(&self) -> impl Iterator<Item = &ReviewerStatus> {
+        self.reviewers
+            .iter()
+            .filter(|r| r.state == ReviewState::ChangesRequested)
+    }
+}
+
+/// Compute each reviewer's effective state the way GitHub does
+///
+/// Reviews are replayed in submission order. An approval or change request
+/// replaces the previous verdict, a plain comment does not, and a dismissal
+/// clears it. Pending (unsubmitted) reviews are ignored.
+pub fn summarize_reviews(reviews: &[PrReview], status: &PrMergeStatus) -> ReviewSummary {
+    let mut ordered: Vec<&PrReview> = reviews.iter().collect();
+    ordered.sort_by_key(|review| review.submitted_at);
+
+    let mut reviewers: Vec<ReviewerStatus> = Vec::new();
+    for review in ordered {
+        let state = ReviewState::parse(&review.state);
+        if state == ReviewState::Pending {
+            continue;
+        }
+
+        let index = match reviewers
+            .iter()
+            .position(|r| r.login.eq_ignore_ascii_case(&review.user.login))
+        {
+            Some(index) => index,
+            None => {
+                reviewers.push(ReviewerStatus {
+                    login: review.user.login.clone(),
+                    state: ReviewState::Commented,
+                    submitted_at: None,
+                    commit_id: None,
+                    review_id: review.id,
+                });
+                reviewers.len() - 1
+            }
+        };
+
+        let reviewer = &mut reviewers[index];
+        let keeps_verdict = state == ReviewState::Commented
+            && matches!(
+                reviewer.state,
+                ReviewState::Approved | ReviewState::ChangesRequested
+            );
+        if !keeps_verdict {
+            reviewer.state = state;
+            reviewer.review_id = review.id;
+        }
+        reviewer.submitted_at = review.submitted_at;
+        reviewer.commit_id = review.commit_id.clone();
+    }
+
+    ReviewSummary {
+        reviewers,
+        pending_reviewers: status.requested_reviewers.clone(),
+        pending_teams: status.requested_teams.clone(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeVerdict {
+    /// Nothing stands in the way of merging
+    Ready,
+    /// Waiting on something that may resolve by itself: CI, requested reviews, mergeability
+    Waiting,
+    /// Needs action: change requests, conflicts, failing CI, branch protection, draft, closed
+    Blocked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeReadiness {
+    pub verdict: MergeVerdict,
+    /// Human-readable reasons for a non-ready verdict
+    pub reasons: Vec<String>,
+    pub reviews: ReviewSummary,
+    pub merge_status: PrMergeStatus,
+    pub ci: CiStatus,
+}
+
+/// Combine review states, mergeability and CI into a single verdict
+pub fn assess_merge_readiness(
+    reviews: &[PrReview],
+    merge_status: PrMergeStatus,
+    ci: CiStatus,
+) -> MergeReadiness {
+    let summary = summarize_reviews(reviews, &merge_status);
+    let mut blocked = Vec::new();
+    let mut waiting = Vec::new();
+
+    if merge_status.merged {
+        blocked.push("PR is already merged".to_string());
+    } else if !merge_status.state.eq_ignore_ascii_case("open") {
+        blocked.push("PR is closed".to_string());
+    }
+    if merge_status.draft || merge_status.mergeable_state == "draft" {
+        blocked.push("PR is a draft".to_string());
+    }
+
+    for reviewer in summary.outstanding_change_requests() {
+        blocked.push(format!("@{} requested changes", reviewer.login));
+    }
+
+    match (
+        merge_status.mergeable,
+        merge_status.mergeable_state.as_str(),
+    ) {
+        (Some(false), _) | (_, "dirty") => blocked.push("PR has merge conflicts".to_string()),
+        (_, "behind") => blocked.push("Head branch is behind the base branch".to_string()),
+        (_, "blocked") => blocked.push(
+            "Branch protection blocks merging: required reviews or checks are missing".to_string(),
+        ),
+        (_, "unstable") => waiting.push("GitHub reports checks that are not passing".to_string()),
+        (None, _) | (_, "unknown") => {
+            waiting.push("Mergeability is still being computed".to_string())
+        }
+        _ => {}
+    }
+
+    let failing: Vec<&str> = ci.failing().map(|check| check.name.as_str()).collect();
+    let pending: Vec<&str> = ci.pending().map(|check| check.name.as_str()).collect();
+    if !failing.is_empty() {
+        blocked.push(format!("Failing checks: {}", failing.join(", ")));
+    }
+    if !pending.is_empty() {
+        waiting.push(format!("Pending checks: {}", pending.join(", ")));
+    }
+
+    for login in &summary.pending_reviewers {
+        waiting.push(format!("Review requested from @{}", login));
+    }
+    for team in &summary.pending_teams {
+        waiting.push(format!("Review requested from team {}", team));
+    }
+    if summary.approvals().next().is_none() {
+        waiting.push("No approving reviews".to_string());
+    }
+
+    let verdict = if !blocked.is_empty() {
+        MergeVerdict::Blocked
+    } else if !waiting.is_empty() {
+        MergeVerdict::Waiting
+    } else {
+        MergeVerdict::Ready
+    };
+    blocked.extend(waiting);
+
+    MergeReadiness {
+        verdict,
+        reasons: blocked,
+        reviews: summary,
+        merge_status,
+        ci,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::user;
+    use chrono::TimeZone;
+
+    fn review(login: &str, state: &str, day: u32, commit: Option<&str>) -> PrReview {
+        PrReview {
+            id: day as u64,
+            user: user(login),
+            body: None,
+            state: state.to_string(),
+            submitted_at: Some(Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap()),
+            html_url: String::new(),
+            commit_id: commit.map(|c| c.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_looks_rebased() {
+        let comparison = |status: &str, ahead_by: u32, behind_by: u32| Comparison {
+            base: "reviewed".to_string(),
+            head: "head".to_string(),
+            status: status.to_string(),
+            ahead_by,
+            behind_by,
+            total_commits: ahead_by,
+            merge_base_sha: None,
+            commits: vec![],
+            files: vec![],
+        };
+
+        assert!(!looks_rebased(&comparison("ahead", 3, 0)));
+        assert!(looks_rebased(&comparison("diverged", 2, 2)));
+        assert!(looks_rebased(&comparison("behind", 0, 1)));
+    }
+
+    #[test]
+    fn test_latest_review_by() {
+        let reviews = vec![
+            review("alice", "ChangesRequested", 1, Some("aaa")),
+            review("bob", "Approved", 3, Some("bbb")),
+            review("Alice", "Commented", 2, Some("ccc")),
+            review("alice", "Pending", 4, Some("ddd")),
+        ];
+
+        let latest = latest_review_by(&reviews, "alice").unwrap();
+        assert_eq!(latest.commit_id.as_deref(), Some("ccc"));
+        assert!(latest_review_by(&reviews, "carol").is_none());
+    }
+
+    fn merge_status() -> PrMergeStatus {
+        PrMergeStatus {
+            state: "Open".to_string(),
+            draft: false,
+            merged: false,
+            mergeable: Some(true),
+            mergeable_state: "clean".to_string(),
+            head_sha: "head".to_string(),
+            requested_reviewers: vec![],
+            requested_teams: vec![],
+        }
+    }
+
+    fn ci(state: &str) -> CiStatus {
+        CiStatus {
+            sha: "head".to_string(),
+            state: state.to_string(),
+            checks: vec![crate::types::CiCheck {
+                name: "test".to_string(),
+                state: state.to_string(),
+                url: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_effective_review_state() {
+        let reviews = vec![
+            review("alice", "CHANGES_REQUESTED", 1, Some("a")),
+            review("alice", "Approved", 2, Some("b")),
+            review("alice", "Commented", 3, Some("c")),
+            review("bob", "Approved", 1, Some("a")),
+            review("bob", "Dismissed", 4, Some("c")),
+            review("carol", "Commented", 2, Some("b")),
+        ];
+        let summary = summarize_reviews(&reviews, &merge_status());
+
+        let states: Vec<(&str, ReviewState)> = summary
+            .reviewers
+            .iter()
+            .map(|r| (r.login.as_str(), r.state))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ("alice", ReviewState::Approved),
+                ("bob", ReviewState::Dismissed),
+                ("carol", ReviewState::Commented),
+            ]
+        );
+        assert_eq!(summary.reviewers[0].commit_id.as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn test_merge_readiness() {
+        let approved = vec![review("alice", "Approved", 1, Some("head"))];
+        let ready = assess_merge_readiness(&approved, merge_status(), ci("success"));
+        assert_eq!(ready.verdict, MergeVerdict::Ready);
+        assert!(ready.reasons.is_empty());
+
+        let waiting = assess_merge_readiness(&approved, merge_status(), ci("pending"));
+        assert_eq!(waiting.verdict, MergeVerdict::Waiting);
+        assert_eq!(waiting.reasons, vec!["Pending checks: test".to_string()]);
+
+        let changes = vec![review("bob", "ChangesRequested", 2, Some("head"))];
+        let blocked = assess_merge_readiness(&changes, merge_status(), ci("failure"));
+        assert_eq!(blocked.verdict, MergeVerdict::Blocked);
+        assert_eq!(
+            blocked.reasons,
+            vec![
+                "@bob requested changes".to_string(),
+                "Failing checks: test".to_string(),
+                "No approving reviews".to_string(),
+            ]
+        );
+
+        // GitHub's own verdict counts even when reviews and CI look fine
+        let protected = PrMergeStatus {
+            mergeable_state: "blocked".to_string(),
+            ..merge_status()
+        };
+        let blocked = assess_merge_readiness(&approved, protected, ci("success"));
+        assert_eq!(blocked.verdict, MergeVerdict::Blocked);
+        assert_eq!(
+            blocked.reasons,
+            vec!["Branch protection blocks merging: required reviews or checks are missing"]
+        );
+
+        let unstable = PrMergeStatus {
+            mergeable_state: "unstable".to_string(),
+            ..merge_status()
+        };
+        let waiting = assess_merge_readiness(&approved, unstable, ci("success"));
+        assert_eq!(waiting.verdict, MergeVerdict::Waiting);
+        assert_eq!(
+            waiting.reasons,
+            vec!["GitHub reports checks that are not passing"]
+        );
+
+        let draft = PrMergeStatus {
+            mergeable_state: "draft".to_string(),
+            ..merge_status()
+        };
+        let blocked = assess_merge_readiness(&approved, draft, ci("success"));
+        assert_eq!(blocked.verdict, MergeVerdict::Blocked);
+        assert_eq!(blocked.reasons, vec!["PR is a draft"]);
+    }
+}
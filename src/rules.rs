@@ -0,0 +1,883 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::bots::BotClassifier;
+use crate::diagnostics::{DiagnosticKind, DiagnosticRegistry};
+use crate::environment::{extract_environment, Channel, CrateVersionReq};
+use crate::filters::{
+    has_code_blocks, has_rust_error_codes, prose_only, DateRange, IssueState, KeywordMatch,
+    KeywordMode, KeywordRule,
+};
+use crate::snippet::has_self_contained_repro;
+use crate::types::{DateField, GitHubComment, GitHubIssue};
+
+/// A predicate over issues that can be combined with others
+///
+/// ```
+/// use github_fetch::rules::{filter_fn, HasAnyLabel, IsPullRequest, IssueFilter};
+///
+/// let filter = HasAnyLabel::new(["C-bug"])
+///     .or(HasAnyLabel::new(["I-crash"]))
+///     .and(IsPullRequest.not())
+///     .and(filter_fn("short title", |issue| issue.title.len() < 80));
+/// assert_eq!(
+///     filter.describe(),
+///     "(((label in [C-bug] or label in [I-crash]) and not is:pr) and short title)"
+/// );
+/// ```
+pub trait IssueFilter: Send + Sync {
+    fn matches(&self, issue: &GitHubIssue) -> bool;
+
+    /// Short human-readable form, used in logs and reports
+    fn describe(&self) -> String;
+
+    /// Description of the rule that rejects `issue`, or None when it matches
+    ///
+    /// Conjunctions report the first failing part, so the answer names the
+    /// smallest rule responsible rather than the whole tree.
+    fn explain(&self, issue: &GitHubIssue) -> Option<String> {
+        if self.matches(issue) {
+            None
+        } else {
+            Some(self.describe())
+        }
+    }
+
+    fn and<F: IssueFilter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    fn or<F: IssueFilter>(self, other: F) -> Or<Self, F>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+
+    fn boxed(self) -> BoxedFilter
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
+}
+
+pub type BoxedFilter = Box<dyn IssueFilter>;
+
+impl IssueFilter for BoxedFilter {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        (**self).matches(issue)
+    }
+
+    fn describe(&self) -> String {
+        (**self).describe()
+    }
+
+    fn explain(&self, issue: &GitHubIssue) -> Option<String> {
+        (**self).explain(issue)
+    }
+}
+
+impl<T: IssueFilter + ?Sized> IssueFilter for &T {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        (**self).matches(issue)
+    }
+
+    fn describe(&self) -> String {
+        (**self).describe()
+    }
+
+    fn explain(&self, issue: &GitHubIssue) -> Option<String> {
+        (**self).explain(issue)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct And<A, B>(pub A, pub B);
+
+impl<A: IssueFilter, B: IssueFilter> IssueFilter for And<A, B> {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.0.matches(issue) && self.1.matches(issue)
+    }
+
+    fn describe(&self) -> String {
+        format!("({} and {})", self.0.describe(), self.1.describe())
+    }
+
+    fn explain(&self, issue: &GitHubIssue) -> Option<String> {
+        self.0.explain(issue).or_else(|| self.1.explain(issue))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(pub A, pub B);
+
+impl<A: IssueFilter, B: IssueFilter> IssueFilter for Or<A, B> {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.0.matches(issue) || self.1.matches(issue)
+    }
+
+    fn describe(&self) -> String {
+        format!("({} or {})", self.0.describe(), self.1.describe())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Not<A>(pub A);
+
+impl<A: IssueFilter> IssueFilter for Not<A> {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        !self.0.matches(issue)
+    }
+
+    fn describe(&self) -> String {
+        format!("not {}", self.0.describe())
+    }
+}
+
+/// Matches when every filter matches; an empty list matches everything
+pub struct AllOf(pub Vec<BoxedFilter>);
+
+impl IssueFilter for AllOf {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.0.iter().all(|filter| filter.matches(issue))
+    }
+
+    fn describe(&self) -> String {
+        join(&self.0, " and ", "any")
+    }
+
+    fn explain(&self, issue: &GitHubIssue) -> Option<String> {
+        self.0.iter().find_map(|filter| filter.explain(issue))
+    }
+}
+
+/// Matches when at least one filter matches; an empty list matches nothing
+pub struct AnyOf(pub Vec<BoxedFilter>);
+
+impl IssueFilter for AnyOf {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.0.iter().any(|filter| filter.matches(issue))
+    }
+
+    fn describe(&self) -> String {
+        join(&self.0, " or ", "none")
+    }
+}
+
+fn join(filters: &[BoxedFilter], separator: &str, empty: &str) -> String {
+    match filters {
+        [] => empty.to_string(),
+        [filter] => filter.describe(),
+        _ => format!(
+            "({})",
+            filters
+                .iter()
+                .map(|filter| filter.describe())
+                .collect::<Vec<_>>()
+                .join(separator)
+        ),
+    }
+}
+
+pub fn all_of(filters: Vec<BoxedFilter>) -> AllOf {
+    AllOf(filters)
+}
+
+pub fn any_of(filters: Vec<BoxedFilter>) -> AnyOf {
+    AnyOf(filters)
+}
+
+pub fn not<F: IssueFilter>(filter: F) -> Not<F> {
+    Not(filter)
+}
+
+/// A named closure used as a filter
+pub struct FnFilter<F> {
+    name: String,
+    predicate: F,
+}
+
+impl<F> fmt::Debug for FnFilter<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnFilter")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl<F: Fn(&GitHubIssue) -> bool + Send + Sync> IssueFilter for FnFilter<F> {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        (self.predicate)(issue)
+    }
+
+    fn describe(&self) -> String {
+        self.name.clone()
+    }
+}
+
+pub fn filter_fn<F>(name: impl Into<String>, predicate: F) -> FnFilter<F>
+where
+    F: Fn(&GitHubIssue) -> bool + Send + Sync,
+{
+    FnFilter {
+        name: name.into(),
+        predicate,
+    }
+}
+
+/// Title and body, lowercased, as keyword rules see them
+fn content(issue: &GitHubIssue) -> String {
+    format!("{} {}", issue.title, issue.body.as_deref().unwrap_or("")).to_lowercase()
+}
+
+#[derive(Debug, Clone)]
+pub struct State(pub IssueState);
+
+impl IssueFilter for State {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        match self.0 {
+            IssueState::Open => issue.state == "Open",
+            IssueState::Closed => issue.state == "Closed",
+            IssueState::All => true,
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("state: {:?}", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IsPullRequest;
+
+impl IssueFilter for IsPullRequest {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        issue.is_pull_request
+    }
+
+    fn describe(&self) -> String {
+        "is:pr".to_string()
+    }
+}
+
+/// Carries at least one of the labels, compared case-insensitively
+#[derive(Debug, Clone)]
+pub struct HasAnyLabel(pub Vec<String>);
+
+impl HasAnyLabel {
+    pub fn new<S: Into<String>>(labels: impl IntoIterator<Item = S>) -> Self {
+        Self(labels.into_iter().map(Into::into).collect())
+    }
+}
+
+impl IssueFilter for HasAnyLabel {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        issue.labels.iter().any(|label| {
+            self.0
+                .iter()
+                .any(|wanted| wanted.to_lowercase() == label.name.to_lowercase())
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!("label in [{}]", self.0.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MinBodyLength(pub usize);
+
+impl IssueFilter for MinBodyLength {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        issue.body.as_ref().map_or(0, |b| b.len()) >= self.0
+    }
+
+    fn describe(&self) -> String {
+        format!("body length >= {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MinComments(pub u32);
+
+impl IssueFilter for MinComments {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        issue.comments >= self.0
+    }
+
+    fn describe(&self) -> String {
+        format!("comments >= {}", self.0)
+    }
+}
+
+/// The issue's `field` timestamp lies within the range; an issue never closed
+/// or merged fails any range on that field
+#[derive(Debug, Clone)]
+pub struct DateWithin(pub DateField, pub DateRange);
+
+impl IssueFilter for DateWithin {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        let Some(date) = issue.date(self.0) else {
+            return false;
+        };
+        self.1.start.map_or(true, |start| date >= start)
+            && self.1.end.map_or(true, |end| date <= end)
+    }
+
+    fn describe(&self) -> String {
+        let bound = |date: Option<chrono::DateTime<chrono::Utc>>| {
+            date.map(|d| d.to_rfc3339())
+                .unwrap_or_else(|| "*".to_string())
+        };
+        format!(
+            "{} {}..{}",
+            self.0.name(),
+            bound(self.1.start),
+            bound(self.1.end)
+        )
+    }
+}
+
+/// Opened by `login`, compared case-insensitively
+#[derive(Debug, Clone)]
+pub struct AuthoredBy(pub String);
+
+impl IssueFilter for AuthoredBy {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        issue.user.login.eq_ignore_ascii_case(&self.0)
+    }
+
+    fn describe(&self) -> String {
+        format!("author:{}", self.0)
+    }
+}
+
+/// Opened by an account the classifier flags as a bot
+#[derive(Debug, Clone)]
+pub struct AuthorIsBot(pub BotClassifier);
+
+impl IssueFilter for AuthorIsBot {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.0.is_bot(&issue.user)
+    }
+
+    fn describe(&self) -> String {
+        "is:bot".to_string()
+    }
+}
+
+/// `login` is one of the assignees
+#[derive(Debug, Clone)]
+pub struct AssignedTo(pub String);
+
+impl IssueFilter for AssignedTo {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        issue
+            .assignees
+            .iter()
+            .any(|assignee| assignee.login.eq_ignore_ascii_case(&self.0))
+    }
+
+    fn describe(&self) -> String {
+        format!("assignee:{}", self.0)
+    }
+}
+
+/// Belongs to the milestone with this title
+#[derive(Debug, Clone)]
+pub struct InMilestone(pub String);
+
+impl IssueFilter for InMilestone {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        issue
+            .milestone
+            .as_deref()
+            .is_some_and(|title| title.eq_ignore_ascii_case(&self.0))
+    }
+
+    fn describe(&self) -> String {
+        format!("milestone:{}", self.0)
+    }
+}
+
+/// At least this many reactions; fails when the reaction count is unknown
+#[derive(Debug, Clone)]
+pub struct MinReactions(pub u32);
+
+impl IssueFilter for MinReactions {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        issue.reactions.is_some_and(|count| count >= self.0)
+    }
+
+    fn describe(&self) -> String {
+        format!("reactions >= {}", self.0)
+    }
+}
+
+/// The author's `author_association` is one of these, e.g. `FIRST_TIME_CONTRIBUTOR`
+#[derive(Debug, Clone)]
+pub struct AuthorAssociationIn(pub Vec<String>);
+
+impl IssueFilter for AuthorAssociationIn {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.0
+            .iter()
+            .any(|association| association.eq_ignore_ascii_case(&issue.author_association))
+    }
+
+    fn describe(&self) -> String {
+        format!("association in [{}]", self.0.join(", "))
+    }
+}
+
+/// Title or body contains at least one of the keywords, case-insensitively
+#[derive(Debug, Clone)]
+pub struct ContainsAnyKeyword(pub Vec<String>);
+
+impl ContainsAnyKeyword {
+    pub fn new<S: Into<String>>(keywords: impl IntoIterator<Item = S>) -> Self {
+        Self(keywords.into_iter().map(Into::into).collect())
+    }
+}
+
+impl IssueFilter for ContainsAnyKeyword {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        let content = content(issue);
+        self.0
+            .iter()
+            .any(|keyword| content.contains(&keyword.to_lowercase()))
+    }
+
+    fn describe(&self) -> String {
+        format!("keyword in [{}]", self.0.join(", "))
+    }
+}
+
+/// A `KeywordRule` with its keywords compiled to regular expressions
+///
+/// As an `IssueFilter` it only sees the title and body; rules whose scope
+/// includes comments are checked with `matches_with_comments`. A rule with an
+/// invalid pattern rejects every issue, whether or not it excludes.
+#[derive(Debug, Clone)]
+pub struct KeywordFilter {
+    rule: KeywordRule,
+    patterns: Option<Vec<Regex>>,
+}
+
+impl KeywordFilter {
+    /// Compile `rule`, reusing the patterns of an equal rule compiled before
+    ///
+    /// `IssueFilters` compiles its rules for every issue it checks, so the
+    /// patterns, and the warning for an invalid one, come once per rule.
+    pub fn new(rule: KeywordRule) -> Self {
+        const MAX_CACHED: usize = 256;
+        type Cache = Mutex<HashMap<KeywordRule, Option<Vec<Regex>>>>;
+        static COMPILED: OnceLock<Cache> = OnceLock::new();
+
+        let mut compiled = COMPILED
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if compiled.len() >= MAX_CACHED && !compiled.contains_key(&rule) {
+            compiled.clear();
+        }
+        let patterns = compiled
+            .entry(rule.clone())
+            .or_insert_with(|| {
+                rule.keywords
+                    .iter()
+                    .map(|keyword| rule.pattern(keyword))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| log::warn!("Keyword rule {:?} rejects every issue: {}", rule, e))
+                    .ok()
+            })
+            .clone();
+
+        Self { rule, patterns }
+    }
+
+    pub fn rule(&self) -> &KeywordRule {
+        &self.rule
+    }
+
+    pub fn needs_comments(&self) -> bool {
+        self.rule.scope.comments
+    }
+
+    pub fn matches_with_comments(&self, issue: &GitHubIssue, comments: &[GitHubComment]) -> bool {
+        let scope = self.rule.scope;
+        let mut texts: Vec<String> = Vec::new();
+        if scope.title {
+            texts.push(issue.title.clone());
+        }
+        if scope.body {
+            texts.push(self.prose(issue.body.as_deref().unwrap_or("")));
+        }
+        if scope.comments {
+            texts.extend(comments.iter().map(|comment| self.prose(&comment.body)));
+        }
+
+        let Some(patterns) = &self.patterns else {
+            return false;
+        };
+        let found = |regex: &Regex| texts.iter().any(|text| regex.is_match(text));
+        let held = match self.rule.mode {
+            KeywordMode::Any => patterns.iter().any(found),
+            KeywordMode::All => patterns.iter().all(found),
+        };
+
+        held != self.rule.exclude
+    }
+
+    fn prose(&self, markdown: &str) -> String {
+        if self.rule.prose_only {
+            prose_only(markdown)
+        } else {
+            markdown.to_string()
+        }
+    }
+}
+
+impl IssueFilter for KeywordFilter {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.matches_with_comments(issue, &[])
+    }
+
+    fn describe(&self) -> String {
+        let rule = &self.rule;
+        let mut options: Vec<&str> = Vec::new();
+        match rule.matching {
+            KeywordMatch::Substring => {}
+            KeywordMatch::WholeWord => options.push("whole word"),
+            KeywordMatch::Regex => options.push("regex"),
+        }
+        if rule.case_sensitive {
+            options.push("case-sensitive");
+        }
+        let scope: Vec<&str> = [
+            (rule.scope.title, "title"),
+            (rule.scope.body, "body"),
+            (rule.scope.comments, "comments"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| *name)
+        .collect();
+        let scope = scope.join("+");
+        if rule.scope != Default::default() {
+            options.push(&scope);
+        }
+        if rule.prose_only {
+            options.push("prose only");
+        }
+
+        let mut description = format!(
+            "{}{} in [{}]",
+            if rule.exclude { "not " } else { "" },
+            match rule.mode {
+                KeywordMode::Any => "keyword",
+                KeywordMode::All => "all keywords",
+            },
+            rule.keywords.join(", ")
+        );
+        if !options.is_empty() {
+            description.push_str(&format!(" ({})", options.join(", ")));
+        }
+        description
+    }
+}
+
+/// Title or body mentions a rustc error code such as `E0502`
+#[derive(Debug, Clone)]
+pub struct HasRustErrorCodes;
+
+impl IssueFilter for HasRustErrorCodes {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        has_rust_error_codes(&issue.title)
+            || has_rust_error_codes(issue.body.as_deref().unwrap_or(""))
+    }
+
+    fn describe(&self) -> String {
+        "has:rust-error".to_string()
+    }
+}
+
+/// Title or body holds a diagnostic of at least one of the kinds
+#[derive(Debug, Clone)]
+pub struct HasDiagnostics {
+    kinds: Vec<DiagnosticKind>,
+    registry: Arc<DiagnosticRegistry>,
+}
+
+impl HasDiagnostics {
+    /// Look for `kinds` with the built-in extractors
+    pub fn new(kinds: Vec<DiagnosticKind>) -> Self {
+        Self::with_registry(kinds, DiagnosticRegistry::shared())
+    }
+
+    /// Look for `kinds` with a registry that may hold custom extractors
+    pub fn with_registry(kinds: Vec<DiagnosticKind>, registry: Arc<DiagnosticRegistry>) -> Self {
+        Self { kinds, registry }
+    }
+}
+
+impl IssueFilter for HasDiagnostics {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.registry.has_any(&issue.title, &self.kinds)
+            || self
+                .registry
+                .has_any(issue.body.as_deref().unwrap_or(""), &self.kinds)
+    }
+
+    fn describe(&self) -> String {
+        let kinds: Vec<&str> = self.kinds.iter().map(|kind| kind.name()).collect();
+        format!("diagnostic in [{}]", kinds.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HasCodeBlocks;
+
+impl IssueFilter for HasCodeBlocks {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        has_code_blocks(issue.body.as_deref().unwrap_or(""))
+    }
+
+    fn describe(&self) -> String {
+        "has:code".to_string()
+    }
+}
+
+/// The rustc version in the body is on this channel; fails when none is reported
+#[derive(Debug, Clone)]
+pub struct OnChannel(pub Channel);
+
+impl IssueFilter for OnChannel {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        extract_environment(issue.body.as_deref().unwrap_or("")).channel() == Some(self.0)
+    }
+
+    fn describe(&self) -> String {
+        format!("channel:{}", self.0.name())
+    }
+}
+
+/// The body reports a version of the crate that satisfies the requirement
+#[derive(Debug, Clone)]
+pub struct CrateVersionMatches(pub CrateVersionReq);
+
+impl IssueFilter for CrateVersionMatches {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        self.0
+            .matches(&extract_environment(issue.body.as_deref().unwrap_or("")))
+    }
+
+    fn describe(&self) -> String {
+        format!("crate:{}", self.0)
+    }
+}
+
+/// Body holds a Rust code block that parses as a program with `fn main`
+#[derive(Debug, Clone)]
+pub struct HasSelfContainedRepro;
+
+impl IssueFilter for HasSelfContainedRepro {
+    fn matches(&self, issue: &GitHubIssue) -> bool {
+        has_self_contained_repro(issue.body.as_deref().unwrap_or(""))
+    }
+
+    fn describe(&self) -> String {
+        "has:repro".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::IssueFilters;
+    use crate::fixtures::{self, label};
+    use chrono::Utc;
+
+    fn issue(labels: &[&str], is_pull_request: bool, body: &str) -> GitHubIssue {
+        GitHubIssue {
+            title: "Borrow checker rejects valid code".to_string(),
+            body: Some(body.to_string()),
+            labels: labels.iter().map(|name| label(name)).collect(),
+            is_pull_request,
+            comments: 4,
+            ..fixtures::issue(1)
+        }
+    }
+
+    #[test]
+    fn test_combinators() {
+        let filter = any_of(vec![
+            HasAnyLabel::new(["A-borrowck"]).boxed(),
+            HasAnyLabel::new(["A-lifetimes"]).boxed(),
+        ])
+        .and(not(IsPullRequest))
+        .and(filter_fn("mentions E0502", |issue: &GitHubIssue| {
+            issue.body.as_deref().is_some_and(|b| b.contains("E0502"))
+        }));
+
+        assert!(filter.matches(&issue(&["A-lifetimes"], false, "error[E0502]")));
+        assert!(!filter.matches(&issue(&["A-lifetimes"], true, "error[E0502]")));
+        assert!(!filter.matches(&issue(&["A-diagnostics"], false, "error[E0502]")));
+        assert!(!filter.matches(&issue(&["A-borrowck"], false, "no code")));
+
+        assert!(all_of(vec![]).matches(&issue(&[], false, "")));
+        assert!(!any_of(vec![]).matches(&issue(&[], false, "")));
+    }
+
+    #[test]
+    fn test_compiled_preset() {
+        let filters = IssueFilters {
+            include_labels: vec!["A-borrowck".to_string()],
+            min_body_length: Some(10),
+            ..Default::default()
+        };
+        let compiled = filters.compile();
+
+        assert_eq!(
+            compiled.describe(),
+            "(not is:pr and label in [A-borrowck] and not label in [duplicate, invalid, wontfix, question] \
+             and body length >= 10 and not keyword in [discussion, RFC, tracking] (whole word, title))"
+        );
+        let candidate = issue(&["A-borrowck"], false, "fn main() { let r = &mut x; }");
+        assert!(compiled.matches(&candidate));
+        assert_eq!(filters.matches(&candidate), compiled.matches(&candidate));
+    }
+
+    #[test]
+    fn test_people_and_dates() {
+        use chrono::TimeZone;
+
+        let mut candidate = issue(&[], true, "");
+        candidate.milestone = Some("1.80".to_string());
+        candidate.author_association = "FIRST_TIME_CONTRIBUTOR".to_string();
+        candidate.assignees = vec![candidate.user.clone()];
+        candidate.reactions = Some(12);
+
+        assert!(AuthoredBy("Alice".to_string()).matches(&candidate));
+        assert!(!AuthorIsBot(BotClassifier::default()).matches(&candidate));
+        candidate.user.login = "renovate[bot]".to_string();
+        assert!(AuthorIsBot(BotClassifier::default()).matches(&candidate));
+        candidate.user.login = "alice".to_string();
+        assert!(AssignedTo("alice".to_string()).matches(&candidate));
+        assert!(!InMilestone("1.81".to_string()).matches(&candidate));
+        assert!(MinReactions(10).matches(&candidate));
+        assert!(
+            AuthorAssociationIn(vec!["first_time_contributor".to_string()]).matches(&candidate)
+        );
+
+        candidate.reactions = None;
+        assert!(!MinReactions(0).matches(&candidate));
+
+        let range = DateRange {
+            start: Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+            end: None,
+        };
+        assert!(DateWithin(DateField::Created, range.clone()).matches(&candidate));
+        assert!(!DateWithin(DateField::Merged, range.clone()).matches(&candidate));
+        candidate.merged_at = Some(Utc::now());
+        assert!(DateWithin(DateField::Merged, range).matches(&candidate));
+    }
+
+    #[test]
+    fn test_diagnostic_rules() {
+        use crate::diagnostics::RegexExtractor;
+
+        let candidate = issue(
+            &[],
+            false,
+            "```\nerror[E0502]: cannot borrow `v` as mutable\n```",
+        );
+        assert!(HasRustErrorCodes.matches(&candidate));
+        assert!(HasDiagnostics::new(vec![DiagnosticKind::RustcError]).matches(&candidate));
+        assert!(!HasDiagnostics::new(vec![DiagnosticKind::Panic]).matches(&candidate));
+
+        let mut registry = DiagnosticRegistry::new();
+        registry.register(RegexExtractor::custom("borrowck", r"cannot borrow").unwrap());
+        let custom = HasDiagnostics::with_registry(
+            vec![DiagnosticKind::Custom("borrowck".to_string())],
+            Arc::new(registry),
+        );
+        assert!(custom.matches(&candidate));
+        assert_eq!(custom.describe(), "diagnostic in [borrowck]");
+    }
+
+    #[test]
+    fn test_environment_rules() {
+        let candidate = issue(
+            &[],
+            false,
+            "rustc 1.82.0-nightly (2f3ddd9f5 2024-08-11)\n\n```toml\n[dependencies]\ntokio = \"1.39\"\n```",
+        );
+        assert!(OnChannel(Channel::Nightly).matches(&candidate));
+        assert!(!OnChannel(Channel::Stable).matches(&candidate));
+
+        let requirement: CrateVersionReq = "tokio>=1.30".parse().unwrap();
+        assert!(CrateVersionMatches(requirement.clone()).matches(&candidate));
+        assert!(!CrateVersionMatches(requirement).matches(&issue(&[], false, "no versions")));
+        assert!(!CrateVersionMatches("tokio<1".parse().unwrap()).matches(&candidate));
+    }
+
+    #[test]
+    fn test_keyword_rules() {
+        use crate::filters::{KeywordRule, KeywordScope};
+
+        let body = "Regression since 1.79.\n\n```rust\nfn unsafe_cell() {}\n```\n\n> see the RFC";
+        let candidate = issue(&[], false, body);
+        let holds = |rule: KeywordRule| KeywordFilter::new(rule).matches(&candidate);
+
+        assert!(holds(KeywordRule::any(["regression", "ice"])));
+        assert!(!holds(KeywordRule::all(["regression", "ice"])));
+        assert!(holds(KeywordRule::all(["regression", "borrow"])));
+        assert!(holds(KeywordRule::any(["unsafe"])));
+        assert!(!holds(KeywordRule::any(["unsafe"]).whole_word()));
+        assert!(!holds(KeywordRule::any(["regression"]).case_sensitive()));
+        assert!(holds(KeywordRule::any([r"1\.\d+"]).regex()));
+        assert!(!holds(KeywordRule::any(["(unclosed"]).regex()));
+        assert!(!holds(KeywordRule::any(["(unclosed"]).regex().excluding()));
+        assert!(KeywordRule::any(["(unclosed"]).regex().validate().is_err());
+        assert!(KeywordRule::any(["(unclosed"]).validate().is_ok());
+        assert!(!holds(
+            KeywordRule::any(["regression"]).scope(KeywordScope::TITLE)
+        ));
+        assert!(holds(KeywordRule::any(["rfc"])));
+        assert!(!holds(
+            KeywordRule::any(["rfc", "unsafe_cell"]).prose_only()
+        ));
+        assert!(holds(KeywordRule::any(["rfc"]).prose_only().excluding()));
+
+        let comment = GitHubComment {
+            id: 1,
+            body: "Bisected to a rollup, this is a regression".to_string(),
+            user: candidate.user.clone(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            html_url: String::new(),
+        };
+        let filter = KeywordFilter::new(KeywordRule::any(["bisected"]).scope(KeywordScope::ALL));
+        assert!(filter.needs_comments());
+        assert!(!filter.matches(&candidate));
+        assert!(filter.matches_with_comments(&candidate, &[comment]));
+        assert_eq!(
+            filter.describe(),
+            "keyword in [bisected] (title+body+comments)"
+        );
+    }
+}
@@ -0,0 +1,545 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::filters::{DateRange, SortField};
+use crate::types::{CollectionStats, DateField, GitHubIssue, Repository};
+
+/// The search API never returns more than this many results for one query
+pub const SEARCH_RESULT_CAP: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchKind {
+    Issue,
+    PullRequest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchState {
+    Open,
+    Closed,
+    /// Pull requests only
+    Merged,
+    /// Pull requests only
+    Unmerged,
+    Draft,
+}
+
+impl SearchState {
+    fn qualifier(&self) -> &'static str {
+        match self {
+            Self::Open => "is:open",
+            Self::Closed => "is:closed",
+            Self::Merged => "is:merged",
+            Self::Unmerged => "is:unmerged",
+            Self::Draft => "draft:true",
+        }
+    }
+}
+
+/// Typed query for the issue and pull request search API
+///
+/// ```
+/// use github_fetch::{Repository, SearchQuery, SearchState};
+///
+/// let query = SearchQuery::new()
+///     .repo(&Repository::new("rust-lang", "rust"))
+///     .issues()
+///     .state(SearchState::Open)
+///     .label("E-easy")
+///     .exclude_label("S-blocked")
+///     .min_comments(3);
+/// assert_eq!(
+///     query.to_string(),
+///     "repo:rust-lang/rust is:issue is:open label:E-easy -label:S-blocked comments:>=3"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub repos: Vec<String>,
+    pub orgs: Vec<String>,
+    pub kind: Option<SearchKind>,
+    pub states: Vec<SearchState>,
+    /// Every label must be present
+    pub labels: Vec<String>,
+    /// At least one of these labels must be present
+    pub any_labels: Vec<String>,
+    pub exclude_labels: Vec<String>,
+    pub author: Option<String>,
+    pub involves: Option<String>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Milestone title
+    #[serde(default)]
+    pub milestone: Option<String>,
+    /// Branch a pull request targets
+    #[serde(default)]
+    pub base: Option<String>,
+    pub created: Option<DateRange>,
+    pub updated: Option<DateRange>,
+    #[serde(default)]
+    pub closed: Option<DateRange>,
+    /// Pull requests only
+    #[serde(default)]
+    pub merged: Option<DateRange>,
+    pub min_comments: Option<u32>,
+    #[serde(default)]
+    pub max_comments: Option<u32>,
+    pub min_reactions: Option<u32>,
+    /// Issues linked to a pull request that closes them
+    pub linked_pr: bool,
+    /// Free-text terms, matched against title and body
+    pub keywords: Vec<String>,
+    /// Terms that must not appear
+    pub excluded_keywords: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn repo(mut self, repo: &Repository) -> Self {
+        self.repos.push(repo.full_name.clone());
+        self
+    }
+
+    pub fn org(mut self, org: impl Into<String>) -> Self {
+        self.orgs.push(org.into());
+        self
+    }
+
+    pub fn issues(mut self) -> Self {
+        self.kind = Some(SearchKind::Issue);
+        self
+    }
+
+    pub fn pull_requests(mut self) -> Self {
+        self.kind = Some(SearchKind::PullRequest);
+        self
+    }
+
+    pub fn state(mut self, state: SearchState) -> Self {
+        self.states.push(state);
+        self
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.labels.push(label.into());
+        self
+    }
+
+    /// Require at least one of `labels`
+    pub fn any_label(mut self, labels: &[&str]) -> Self {
+        self.any_labels = labels.iter().map(|label| label.to_string()).collect();
+        self
+    }
+
+    pub fn exclude_label(mut self, label: impl Into<String>) -> Self {
+        self.exclude_labels.push(label.into());
+        self
+    }
+
+    pub fn author(mut self, login: impl Into<String>) -> Self {
+        self.author = Some(login.into());
+        self
+    }
+
+    /// Author, assignee, commenter or mentioned user
+    pub fn involves(mut self, login: impl Into<String>) -> Self {
+        self.involves = Some(login.into());
+        self
+    }
+
+    pub fn assignee(mut self, login: impl Into<String>) -> Self {
+        self.assignee = Some(login.into());
+        self
+    }
+
+    pub fn milestone(mut self, title: impl Into<String>) -> Self {
+        self.milestone = Some(title.into());
+        self
+    }
+
+    pub fn base(mut self, branch: impl Into<String>) -> Self {
+        self.base = Some(branch.into());
+        self
+    }
+
+    /// Restrict the timestamp `field` to `range`
+    pub fn date(self, field: DateField, range: DateRange) -> Self {
+        match field {
+            DateField::Created => self.created(range),
+            DateField::Updated => self.updated(range),
+            DateField::Closed => self.closed(range),
+            DateField::Merged => self.merged(range),
+        }
+    }
+
+    pub fn created(mut self, range: DateRange) -> Self {
+        self.created = Some(range);
+        self
+    }
+
+    pub fn updated(mut self, range: DateRange) -> Self {
+        self.updated = Some(range);
+        self
+    }
+
+    pub fn closed(mut self, range: DateRange) -> Self {
+        self.closed = Some(range);
+        self
+    }
+
+    pub fn merged(mut self, range: DateRange) -> Self {
+        self.merged = Some(range);
+        self
+    }
+
+    pub fn min_comments(mut self, count: u32) -> Self {
+        self.min_comments = Some(count);
+        self
+    }
+
+    pub fn max_comments(mut self, count: u32) -> Self {
+        self.max_comments = Some(count);
+        self
+    }
+
+    pub fn min_reactions(mut self, count: u32) -> Self {
+        self.min_reactions = Some(count);
+        self
+    }
+
+    pub fn linked_pr(mut self) -> Self {
+        self.linked_pr = true;
+        self
+    }
+
+    pub fn keyword(mut self, keyword: impl Into<String>) -> Self {
+        self.keywords.push(keyword.into());
+        self
+    }
+
+    pub fn exclude_keyword(mut self, keyword: impl Into<String>) -> Self {
+        self.excluded_keywords.push(keyword.into());
+        self
+    }
+
+    /// Copy of the query restricted to issues created within `range`
+    pub fn with_created(&self, range: DateRange) -> Self {
+        Self {
+            created: Some(range),
+            ..self.clone()
+        }
+    }
+
+    /// Bounds the query already puts on a sort key, in window units: seconds
+    /// since the epoch for dates, the count for comments
+    pub(crate) fn window_bounds(&self, field: SortField) -> (Option<i64>, Option<i64>) {
+        let dates = |range: &Option<DateRange>| {
+            let range = range.as_ref();
+            (
+                range
+                    .and_then(|range| range.start)
+                    .map(|date| date.timestamp()),
+                range
+                    .and_then(|range| range.end)
+                    .map(|date| date.timestamp()),
+            )
+        };
+        match field {
+            SortField::Created => dates(&self.created),
+            SortField::Updated => dates(&self.updated),
+            SortField::Comments => (
+                self.min_comments.map(i64::from),
+                self.max_comments.map(i64::from),
+            ),
+        }
+    }
+
+    /// Copy of the query restricted to a window of the sort key
+    pub(crate) fn with_window(&self, field: SortField, (low, high): (i64, i64)) -> Self {
+        let dates = || DateRange {
+            start: DateTime::from_timestamp(low, 0),
+            end: DateTime::from_timestamp(high, 0),
+        };
+        match field {
+            SortField::Created => self.with_created(dates()),
+            SortField::Updated => Self {
+                updated: Some(dates()),
+                ..self.clone()
+            },
+            SortField::Comments => Self {
+                min_comments: u32::try_from(low).ok(),
+                max_comments: u32::try_from(high).ok(),
+                ..self.clone()
+            },
+        }
+    }
+}
+
+/// Windows of a sort key a search is split into to stay under the result cap
+///
+/// Windows are inclusive ranges of sort key values and come out in sort
+/// order, so results collected window by window are already sorted and a
+/// search can stop once it has enough.
+#[derive(Debug)]
+pub(crate) struct SearchWindows {
+    pending: Vec<(i64, i64)>,
+    descending: bool,
+}
+
+impl SearchWindows {
+    pub(crate) fn new(low: i64, high: i64, descending: bool) -> Self {
+        Self {
+            pending: vec![(low, high.max(low))],
+            descending,
+        }
+    }
+
+    pub(crate) fn next_window(&mut self) -> Option<(i64, i64)> {
+        self.pending.pop()
+    }
+
+    /// Halve a window holding too many results, both halves coming before
+    /// any window still pending; false when it is a single key value
+    pub(crate) fn split(&mut self, (low, high): (i64, i64)) -> bool {
+        if high <= low {
+            return false;
+        }
+        let mid = low + (high - low) / 2;
+        let (earlier, later) = ((low, mid), (mid + 1, high));
+        // Push the half to walk first last so it is popped next
+        if self.descending {
+            self.pending.push(earlier);
+            self.pending.push(later);
+        } else {
+            self.pending.push(later);
+            self.pending.push(earlier);
+        }
+        true
+    }
+}
+
+impl fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts: Vec<String> = Vec::new();
+
+        parts.extend(self.repos.iter().map(|repo| format!("repo:{}", repo)));
+        parts.extend(self.orgs.iter().map(|org| format!("org:{}", org)));
+        match self.kind {
+            Some(SearchKind::Issue) => parts.push("is:issue".to_string()),
+            Some(SearchKind::PullRequest) => parts.push("is:pr".to_string()),
+            None => {}
+        }
+        parts.extend(
+            self.states
+                .iter()
+                .map(|state| state.qualifier().to_string()),
+        );
+        parts.extend(
+            self.labels
+                .iter()
+                .map(|label| format!("label:{}", quote(label))),
+        );
+        if !self.any_labels.is_empty() {
+            let labels: Vec<String> = self.any_labels.iter().map(|label| quote(label)).collect();
+            parts.push(format!("label:{}", labels.join(",")));
+        }
+        parts.extend(
+            self.exclude_labels
+                .iter()
+                .map(|label| format!("-label:{}", quote(label))),
+        );
+        if let Some(author) = &self.author {
+            parts.push(format!("author:{}", author));
+        }
+        if let Some(login) = &self.involves {
+            parts.push(format!("involves:{}", login));
+        }
+        if let Some(login) = &self.assignee {
+            parts.push(format!("assignee:{}", login));
+        }
+        if let Some(title) = &self.milestone {
+            parts.push(format!("milestone:{}", quote(title)));
+        }
+        if let Some(branch) = &self.base {
+            parts.push(format!("base:{}", branch));
+        }
+        let ranges = [
+            ("created", &self.created),
+            ("updated", &self.updated),
+            ("closed", &self.closed),
+            ("merged", &self.merged),
+        ];
+        for (qualifier, range) in ranges {
+            if let Some(range) = range.as_ref().and_then(date_range) {
+                parts.push(format!("{}:{}", qualifier, range));
+            }
+        }
+        match (self.min_comments, self.max_comments) {
+            (Some(min), Some(max)) => parts.push(format!("comments:{}..{}", min, max)),
+            (Some(min), None) => parts.push(format!("comments:>={}", min)),
+            (None, Some(max)) => parts.push(format!("comments:<={}", max)),
+            (None, None) => {}
+        }
+        if let Some(count) = self.min_reactions {
+            parts.push(format!("reactions:>={}", count));
+        }
+        if self.linked_pr {
+            parts.push("linked:pr".to_string());
+        }
+        parts.extend(self.keywords.iter().map(|keyword| quote(keyword)));
+        parts.extend(
+            self.excluded_keywords
+                .iter()
+                .map(|keyword| format!("-{}", quote(keyword))),
+        );
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Quote a value containing whitespace or quotes so it stays a single
+/// search term, escaping the quotes inside it
+fn quote(value: &str) -> String {
+    if value.chars().any(|c| c.is_whitespace() || c == '"') {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn date_range(range: &DateRange) -> Option<String> {
+    match (range.start, range.end) {
+        (Some(start), Some(end)) => Some(format!("{}..{}", format_date(start), format_date(end))),
+        (Some(start), None) => Some(format!(">={}", format_date(start))),
+        (None, Some(end)) => Some(format!("<={}", format_date(end))),
+        (None, None) => None,
+    }
+}
+
+/// Issues and pull requests returned by a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub query: String,
+    /// Total matches reported by GitHub, summed over all windows
+    pub total_count: u64,
+    pub issues: Vec<GitHubIssue>,
+    /// Number of windows of the sort key the query was split into to stay under the result cap
+    pub windows: usize,
+    /// GitHub timed out on part of the search, or a single window still exceeded the cap
+    pub incomplete: bool,
+    pub stats: CollectionStats,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_query_string() {
+        let query = SearchQuery::new()
+            .org("tokio-rs")
+            .pull_requests()
+            .state(SearchState::Merged)
+            .author("alice")
+            .involves("bob")
+            .assignee("carol")
+            .milestone("Tokio 2.0")
+            .base("master")
+            .label("A-io driver")
+            .created(DateRange {
+                start: Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+                end: Some(Utc.with_ymd_and_hms(2024, 6, 30, 23, 59, 59).unwrap()),
+            })
+            .updated(DateRange {
+                start: None,
+                end: Some(Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap()),
+            })
+            .date(
+                DateField::Merged,
+                DateRange {
+                    start: Some(Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap()),
+                    end: None,
+                },
+            )
+            .min_reactions(10)
+            .linked_pr()
+            .keyword("use after free")
+            .keyword("say \"hi\"")
+            .exclude_keyword("wip");
+
+        assert_eq!(
+            query.to_string(),
+            "org:tokio-rs is:pr is:merged label:\"A-io driver\" author:alice involves:bob \
+             assignee:carol milestone:\"Tokio 2.0\" base:master \
+             created:2024-01-01T00:00:00Z..2024-06-30T23:59:59Z updated:<=2024-07-01T00:00:00Z \
+             merged:>=2024-03-01T00:00:00Z reactions:>=10 linked:pr \"use after free\" \
+             \"say \\\"hi\\\"\" -wip"
+        );
+    }
+
+    #[test]
+    fn test_search_windows() {
+        // Keys of 3000 results, most of them in a burst near the end
+        let keys: Vec<i64> = (0..3000)
+            .map(|i| if i < 500 { i * 10 } else { 5000 + i % 800 })
+            .collect();
+        let count = |(low, high): (i64, i64)| {
+            keys.iter()
+                .filter(|key| (low..=high).contains(*key))
+                .count() as u64
+        };
+
+        for descending in [false, true] {
+            let mut windows = SearchWindows::new(0, 6000, descending);
+            let mut walked = Vec::new();
+            while let Some(window) = windows.next_window() {
+                if count(window) > SEARCH_RESULT_CAP && windows.split(window) {
+                    continue;
+                }
+                walked.push(window);
+            }
+
+            assert!(walked
+                .iter()
+                .all(|window| count(*window) <= SEARCH_RESULT_CAP));
+            assert_eq!(walked.iter().map(|w| count(*w)).sum::<u64>(), 3000);
+            // Windows are disjoint, adjacent and walked in sort order
+            if descending {
+                walked.reverse();
+            }
+            assert_eq!(walked.first().map(|w| w.0), Some(0));
+            assert_eq!(walked.last().map(|w| w.1), Some(6000));
+            assert!(walked.windows(2).all(|pair| pair[0].1 + 1 == pair[1].0));
+        }
+
+        // A single key value cannot be split further
+        let mut windows = SearchWindows::new(7, 7, false);
+        assert!(!windows.split((7, 7)));
+
+        let query = SearchQuery::new().min_comments(2);
+        assert_eq!(query.window_bounds(SortField::Comments), (Some(2), None));
+        assert_eq!(
+            query.with_window(SortField::Comments, (2, 9)).to_string(),
+            "comments:2..9"
+        );
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            query
+                .with_window(
+                    SortField::Updated,
+                    (start.timestamp(), start.timestamp() + 59)
+                )
+                .to_string(),
+            "updated:2024-01-01T00:00:00Z..2024-01-01T00:00:59Z comments:>=2"
+        );
+    }
+}
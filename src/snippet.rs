@@ -0,0 +1,311 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+use syn::visit::{self, Visit};
+
+use crate::codeblock::{extract_code_blocks, CodeBlock, CodeLanguage};
+use crate::environment::{extract_rustc, Channel};
+
+/// How a snippet parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnippetForm {
+    /// A whole source file of items
+    File,
+    /// Statements that only parse inside a function body
+    Statements,
+}
+
+/// What `syn` could tell about one Rust snippet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnippetAnalysis {
+    /// None when the snippet parses neither as a file nor as statements
+    pub form: Option<SnippetForm>,
+    /// Parse error for the snippet as a file
+    pub error: Option<String>,
+    /// Defines a top-level `fn main`, including `async fn main` under a runtime attribute
+    pub has_main: bool,
+    /// Crate roots named by `use`, `extern crate`, and paths in attributes and
+    /// macros, leaving out `std`, `core`, `alloc` and the snippet's own modules
+    /// and enums
+    pub external_crates: Vec<String>,
+    /// `#![feature(...)]` gates the snippet enables
+    pub features: Vec<String>,
+    /// Toolchain mentioned in the snippet, such as `nightly-2024-05-01`,
+    /// `beta` from `cargo +beta` or `1.79.0` from a `rustc --version` line;
+    /// `nightly` when it only enables feature gates
+    pub toolchain: Option<String>,
+}
+
+impl SnippetAnalysis {
+    pub fn parses(&self) -> bool {
+        self.form.is_some()
+    }
+
+    /// A complete program: it parses as a file and defines `fn main`
+    ///
+    /// Crates in `external_crates` still have to be added to build it.
+    pub fn is_self_contained(&self) -> bool {
+        self.form == Some(SnippetForm::File) && self.has_main
+    }
+}
+
+/// Parse a Rust snippet and report what it needs
+///
+/// ```
+/// use github_fetch::snippet::analyze_snippet;
+///
+/// let analysis = analyze_snippet(
+///     "#![feature(let_chains)]\nuse tokio::sync::Mutex;\n\n#[tokio::main]\nasync fn main() {}",
+/// );
+/// assert!(analysis.is_self_contained());
+/// assert_eq!(analysis.external_crates, vec!["tokio"]);
+/// assert_eq!(analysis.toolchain.as_deref(), Some("nightly"));
+/// ```
+pub fn analyze_snippet(code: &str) -> SnippetAnalysis {
+    let mut analysis = SnippetAnalysis {
+        form: None,
+        error: None,
+        has_main: false,
+        external_crates: Vec::new(),
+        features: Vec::new(),
+        toolchain: mentioned_toolchain(code),
+    };
+    let mut visitor = SnippetVisitor::default();
+
+    match syn::parse_file(code) {
+        Ok(file) => {
+            analysis.form = Some(SnippetForm::File);
+            analysis.has_main = file.items.iter().any(is_main);
+            visitor.visit_file(&file);
+        }
+        Err(error) => {
+            analysis.error = Some(error.to_string());
+            if let Ok(block) = syn::parse_str::<syn::Block>(&format!("{{\n{}\n}}", code)) {
+                analysis.form = Some(SnippetForm::Statements);
+                visitor.visit_block(&block);
+            }
+        }
+    }
+
+    analysis.features = visitor.features;
+    analysis.external_crates = visitor
+        .roots
+        .into_iter()
+        .filter(|root| !visitor.local.contains(root) && !is_builtin_root(root))
+        .collect();
+    if analysis.toolchain.is_none() && !analysis.features.is_empty() {
+        analysis.toolchain = Some("nightly".to_string());
+    }
+    analysis
+}
+
+/// Every Rust code block in `markdown` with its analysis
+///
+/// Blocks count as Rust when they are tagged `rust` or, untagged, look like Rust.
+pub fn analyze_rust_blocks(markdown: &str) -> Vec<(CodeBlock, SnippetAnalysis)> {
+    extract_code_blocks(markdown)
+        .into_iter()
+        .filter(|block| block.language == CodeLanguage::Rust)
+        .map(|block| {
+            let analysis = analyze_snippet(&block.code);
+            (block, analysis)
+        })
+        .collect()
+}
+
+/// Whether `markdown` holds a Rust code block that is a complete program
+pub fn has_self_contained_repro(markdown: &str) -> bool {
+    analyze_rust_blocks(markdown)
+        .iter()
+        .any(|(_, analysis)| analysis.is_self_contained())
+}
+
+fn is_main(item: &syn::Item) -> bool {
+    matches!(item, syn::Item::Fn(function) if function.sig.ident == "main")
+}
+
+fn is_builtin_root(root: &str) -> bool {
+    matches!(
+        root,
+        "std" | "core" | "alloc" | "proc_macro" | "test" | "crate" | "self" | "super" | "Self"
+    )
+}
+
+/// Namespaces of tool attributes such as `#[rustfmt::skip]`, which are not crates
+fn is_tool_root(root: &str) -> bool {
+    matches!(root, "rustfmt" | "clippy" | "diagnostic")
+}
+
+#[derive(Default)]
+struct SnippetVisitor {
+    roots: BTreeSet<String>,
+    /// Modules and enums the snippet declares, whose paths are not crates
+    local: BTreeSet<String>,
+    features: Vec<String>,
+}
+
+impl SnippetVisitor {
+    fn add_path_root(&mut self, path: &syn::Path) {
+        if path.segments.len() > 1 {
+            self.roots.insert(path.segments[0].ident.to_string());
+        }
+    }
+
+    fn add_use_roots(&mut self, tree: &syn::UseTree) {
+        match tree {
+            syn::UseTree::Path(path) => {
+                self.roots.insert(path.ident.to_string());
+            }
+            syn::UseTree::Name(name) => {
+                self.roots.insert(name.ident.to_string());
+            }
+            syn::UseTree::Rename(rename) => {
+                self.roots.insert(rename.ident.to_string());
+            }
+            syn::UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.add_use_roots(tree);
+                }
+            }
+            syn::UseTree::Glob(_) => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for SnippetVisitor {
+    fn visit_item_use(&mut self, item: &'ast syn::ItemUse) {
+        self.add_use_roots(&item.tree);
+        visit::visit_item_use(self, item);
+    }
+
+    fn visit_item_extern_crate(&mut self, item: &'ast syn::ItemExternCrate) {
+        self.roots.insert(item.ident.to_string());
+        if let Some((_, rename)) = &item.rename {
+            self.local.insert(rename.to_string());
+        }
+        visit::visit_item_extern_crate(self, item);
+    }
+
+    fn visit_item_mod(&mut self, item: &'ast syn::ItemMod) {
+        self.local.insert(item.ident.to_string());
+        visit::visit_item_mod(self, item);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast syn::ItemEnum) {
+        self.local.insert(item.ident.to_string());
+        visit::visit_item_enum(self, item);
+    }
+
+    fn visit_attribute(&mut self, attribute: &'ast syn::Attribute) {
+        let path = attribute.path();
+        if !path
+            .segments
+            .first()
+            .is_some_and(|root| is_tool_root(&root.ident.to_string()))
+        {
+            self.add_path_root(path);
+        }
+        if attribute.path().is_ident("derive") {
+            let _ = attribute.parse_nested_meta(|meta| {
+                self.add_path_root(&meta.path);
+                Ok(())
+            });
+        } else if matches!(attribute.style, syn::AttrStyle::Inner(_))
+            && attribute.path().is_ident("feature")
+        {
+            let _ = attribute.parse_nested_meta(|meta| {
+                if let Some(ident) = meta.path.get_ident() {
+                    self.features.push(ident.to_string());
+                }
+                Ok(())
+            });
+        }
+        visit::visit_attribute(self, attribute);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.add_path_root(&mac.path);
+        visit::visit_macro(self, mac);
+    }
+}
+
+/// A `rustc --version` line, read the same way as issue bodies, or a rustup
+/// toolchain spelled out as `+nightly`, `nightly-2024-05-01` or
+/// `channel = "beta"`; a bare channel word is too common in prose to count
+fn mentioned_toolchain(code: &str) -> Option<String> {
+    if let Some(rustc) = extract_rustc(code) {
+        return Some(match rustc.channel {
+            Channel::Stable => rustc.version.to_string(),
+            channel => format!("{}-{}", rustc.version, channel.name()),
+        });
+    }
+
+    static TOOLCHAIN: OnceLock<Regex> = OnceLock::new();
+    let toolchain = TOOLCHAIN.get_or_init(|| {
+        Regex::new(
+            r#"(?:^|\s)\+([a-z]+(?:-\d{4}-\d{2}-\d{2})?)\b|\b([a-z]+-\d{4}-\d{2}-\d{2})\b|\bchannel\s*=\s*"([a-z]+(?:-\d{4}-\d{2}-\d{2})?)""#,
+        )
+        .unwrap()
+    });
+
+    toolchain.captures_iter(code).find_map(|captures| {
+        let name = captures.iter().skip(1).find_map(|group| group)?.as_str();
+        let channel = Channel::from_name(name.split('-').next()?)?;
+        (channel != Channel::Dev).then(|| name.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_snippet() {
+        let program = analyze_snippet(
+            "// rustc 1.79.0 (129f3b996 2024-06-10)\n\
+             extern crate serde_json as json;\n\
+             use std::collections::HashMap;\n\
+             use {rand::Rng, self::util::helper};\n\
+             mod util { pub fn helper() {} }\n\
+             #[derive(serde::Serialize)]\n\
+             #[rustfmt::skip]\n\
+             struct Point { x: i32 }\n\
+             fn main() {\n    util::helper();\n    let v = anyhow::anyhow!(\"boom\");\n}",
+        );
+        assert_eq!(program.form, Some(SnippetForm::File));
+        assert!(program.is_self_contained());
+        assert_eq!(
+            program.external_crates,
+            vec!["anyhow", "rand", "serde", "serde_json"]
+        );
+        assert_eq!(program.toolchain.as_deref(), Some("1.79.0"));
+        assert!(program.features.is_empty());
+
+        let statements = analyze_snippet("let x = vec![1, 2];\nlet y = x;\nprintln!(\"{:?}\", x);");
+        assert_eq!(statements.form, Some(SnippetForm::Statements));
+        assert!(statements.error.is_some());
+        assert!(!statements.is_self_contained());
+
+        let broken = analyze_snippet("fn main() {\n    let x = ;\n}\n// nightly-2024-05-01");
+        assert!(!broken.parses());
+        assert_eq!(broken.toolchain.as_deref(), Some("nightly-2024-05-01"));
+
+        let toolchain = |code: &str| analyze_snippet(code).toolchain;
+        assert_eq!(toolchain("// this is stable\nfn main() {}"), None);
+        assert_eq!(
+            toolchain("// cargo +beta run\nfn main() {}").as_deref(),
+            Some("beta")
+        );
+        assert_eq!(
+            toolchain("// rustc 1.80.0-nightly (ada5e2c7b 2024-05-31)\nfn main() {}").as_deref(),
+            Some("1.80.0-nightly")
+        );
+
+        let body =
+            "Repro:\n\n```rust\nfn main() {}\n```\n\nFails with:\n\n```\nerror[E0499]: oops\n```";
+        assert_eq!(analyze_rust_blocks(body).len(), 1);
+        assert!(has_self_contained_repro(body));
+        assert!(!has_self_contained_repro("```rust\nlet x = 1;\n```"));
+    }
+}
@@ -0,0 +1,639 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff::parse_patch;
+use crate::error::Result;
+use crate::types::PrReviewComment;
+
+const PATCH_CONTEXT_LINES: usize = 3;
+
+/// A ```suggestion block taken from a review comment, anchored to the lines it replaces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub comment_id: u64,
+    pub author: String,
+    pub path: String,
+    /// First replaced line (1-based, new side of the diff)
+    pub start_line: u32,
+    /// Last replaced line (1-based, inclusive)
+    pub end_line: u32,
+    /// Lines the reviewer saw when writing the suggestion, recovered from the diff hunk
+    pub original_lines: Option<Vec<String>>,
+    /// Replacement lines; empty when the suggestion deletes the range
+    pub replacement: Vec<String>,
+}
+
+impl Suggestion {
+    /// Extract all suggestions from one review comment.
+    ///
+    /// Comments without a line range on the new side of the diff (file-level,
+    /// outdated or LEFT-side comments) yield nothing.
+    pub fn from_comment(comment: &PrReviewComment) -> Vec<Suggestion> {
+        let range = match comment.new_side_range() {
+            Some(range) => range,
+            None => return Vec::new(),
+        };
+        let (start_line, end_line) = (*range.start(), *range.end());
+        let original_lines =
+            hunk_new_side_tail(&comment.diff_hunk, (end_line - start_line + 1) as usize);
+
+        parse_suggestion_blocks(&comment.body)
+            .into_iter()
+            .map(|replacement| Suggestion {
+                comment_id: comment.id,
+                author: comment.user.login.clone(),
+                path: comment.path.clone(),
+                start_line,
+                end_line,
+                original_lines: original_lines.clone(),
+                replacement,
+            })
+            .collect()
+    }
+}
+
+/// Extract suggestions from every review comment, in comment order
+pub fn extract_suggestions(comments: &[PrReviewComment]) -> Vec<Suggestion> {
+    comments.iter().flat_map(Suggestion::from_comment).collect()
+}
+
+/// Return the contents of each ```suggestion fenced block in a comment body
+pub fn parse_suggestion_blocks(body: &str) -> Vec<Vec<String>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(usize, Vec<String>)> = None;
+
+    for line in body.lines() {
+        let line = line.trim_end_matches('\r');
+        let trimmed = line.trim_start();
+        let fence_len = trimmed.chars().take_while(|&c| c == '`').count();
+
+        match current.take() {
+            Some((open_len, lines)) => {
+                if fence_len >= open_len && trimmed[fence_len..].trim().is_empty() {
+                    blocks.push(lines);
+                } else {
+                    let mut lines = lines;
+                    lines.push(line.to_string());
+                    current = Some((open_len, lines));
+                }
+            }
+            None => {
+                if fence_len >= 3 && trimmed[fence_len..].trim() == "suggestion" {
+                    current = Some((fence_len, Vec::new()));
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+/// Last `count` lines of the new side of a diff hunk, which end at the commented line
+fn hunk_new_side_tail(diff_hunk: &str, count: usize) -> Option<Vec<String>> {
+    let new_side: Vec<String> = parse_patch(diff_hunk)
+        .into_iter()
+        .flat_map(|hunk| hunk.lines)
+        .filter(|line| line.is_new_side())
+        .map(|line| line.content)
+        .collect();
+
+    if new_side.len() < count {
+        return None;
+    }
+
+    Some(new_side[new_side.len() - count..].to_vec())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictReason {
+    /// The target file does not exist in the working tree
+    MissingFile,
+    /// The path is absolute or leaves the checkout, directly or through a
+    /// symlink, so it is never read or written
+    UnsafePath,
+    /// The target file is not valid UTF-8
+    NotText,
+    /// The target lines are past the end of the file
+    OutOfRange { file_lines: usize },
+    /// The target lines no longer contain what the reviewer commented on
+    ContentMismatch { found: Vec<String> },
+    /// Another accepted suggestion already touches these lines
+    Overlapping { comment_id: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestionConflict {
+    pub suggestion: Suggestion,
+    pub reason: ConflictReason,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedSuggestion {
+    pub suggestion: Suggestion,
+    /// Distance in lines between the commented position and where the content was found
+    pub offset: i64,
+}
+
+/// Outcome of checking a set of suggestions against a working tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SuggestionReport {
+    pub applied: Vec<AppliedSuggestion>,
+    pub conflicts: Vec<SuggestionConflict>,
+    /// Unified diff of all applicable suggestions, relative to the checkout root
+    pub patch: String,
+}
+
+impl SuggestionReport {
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+}
+
+/// Applies review suggestions to a local checkout without touching the network
+pub struct SuggestionApplier {
+    root: PathBuf,
+}
+
+struct FilePlan {
+    /// Canonical path of the file inside the checkout
+    full_path: PathBuf,
+    lines: Vec<String>,
+    line_ending: &'static str,
+    trailing_newline: bool,
+    edits: Vec<Edit>,
+}
+
+/// A located suggestion, in 0-based original line coordinates with an exclusive end
+struct Edit {
+    start: usize,
+    end: usize,
+    replacement: Vec<String>,
+    comment_id: u64,
+}
+
+impl SuggestionApplier {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Check suggestions against the working tree and build a patch, without writing files
+    pub fn check(&self, suggestions: &[Suggestion]) -> Result<SuggestionReport> {
+        self.plan(suggestions).map(|(report, _)| report)
+    }
+
+    /// Write every non-conflicting suggestion to the working tree
+    pub fn apply(&self, suggestions: &[Suggestion]) -> Result<SuggestionReport> {
+        let (report, plans) = self.plan(suggestions)?;
+
+        for plan in plans.values() {
+            if plan.edits.is_empty() {
+                continue;
+            }
+            let new_lines = plan.apply_edits();
+            let mut content = new_lines.join(plan.line_ending);
+            if plan.trailing_newline && !new_lines.is_empty() {
+                content.push_str(plan.line_ending);
+            }
+            fs::write(&plan.full_path, content)?;
+        }
+
+        Ok(report)
+    }
+
+    fn plan(
+        &self,
+        suggestions: &[Suggestion],
+    ) -> Result<(SuggestionReport, BTreeMap<String, FilePlan>)> {
+        let mut report = SuggestionReport::default();
+        let mut plans: BTreeMap<String, FilePlan> = BTreeMap::new();
+
+        for suggestion in suggestions {
+            if !plans.contains_key(&suggestion.path) {
+                match self.load(&suggestion.path)? {
+                    Ok(plan) => {
+                        plans.insert(suggestion.path.clone(), plan);
+                    }
+                    Err(reason) => {
+                        report.conflicts.push(SuggestionConflict {
+                            suggestion: suggestion.clone(),
+                            reason,
+                        });
+                        continue;
+                    }
+                }
+            }
+            let plan = plans.get_mut(&suggestion.path).expect("plan loaded above");
+
+            match plan.locate(suggestion) {
+                Ok((start, offset)) => {
+                    let end = start + (suggestion.end_line - suggestion.start_line + 1) as usize;
+                    if let Some(other) = plan.edits.iter().find(|e| e.start < end && start < e.end)
+                    {
+                        report.conflicts.push(SuggestionConflict {
+                            suggestion: suggestion.clone(),
+                            reason: ConflictReason::Overlapping {
+                                comment_id: other.comment_id,
+                            },
+                        });
+                        continue;
+                    }
+
+                    plan.edits.push(Edit {
+                        start,
+                        end,
+                        replacement: suggestion.replacement.clone(),
+                        comment_id: suggestion.comment_id,
+                    });
+                    report.applied.push(AppliedSuggestion {
+                        suggestion: suggestion.clone(),
+                        offset,
+                    });
+                }
+                Err(reason) => report.conflicts.push(SuggestionConflict {
+                    suggestion: suggestion.clone(),
+                    reason,
+                }),
+            }
+        }
+
+        for (path, plan) in plans.iter_mut() {
+            plan.edits.sort_by_key(|edit| edit.start);
+            report.patch.push_str(&plan.unified_diff(path));
+        }
+
+        Ok((report, plans))
+    }
+
+    fn load(&self, path: &str) -> Result<std::result::Result<FilePlan, ConflictReason>> {
+        // Paths come from the PR, so only plain relative ones are joined to the root
+        let relative = Path::new(path);
+        let is_plain = relative.components().next().is_some()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !is_plain {
+            return Ok(Err(ConflictReason::UnsafePath));
+        }
+
+        // Symlinks are resolved so that none can lead out of the checkout
+        let full_path = match self.root.join(relative).canonicalize() {
+            Ok(full_path) if full_path.is_file() => full_path,
+            _ => return Ok(Err(ConflictReason::MissingFile)),
+        };
+        if !full_path.starts_with(self.root.canonicalize()?) {
+            return Ok(Err(ConflictReason::UnsafePath));
+        }
+
+        let content = match String::from_utf8(fs::read(&full_path)?) {
+            Ok(content) => content,
+            Err(_) => return Ok(Err(ConflictReason::NotText)),
+        };
+        let line_ending = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let trailing_newline = content.ends_with('\n');
+
+        Ok(Ok(FilePlan {
+            full_path,
+            lines: content
+                .lines()
+                .map(|line| line.trim_end_matches('\r').to_string())
+                .collect(),
+            line_ending,
+            trailing_newline,
+            edits: Vec::new(),
+        }))
+    }
+}
+
+impl FilePlan {
+    /// Find where the suggestion applies, returning the 0-based start index and drift offset
+    fn locate(&self, suggestion: &Suggestion) -> std::result::Result<(usize, i64), ConflictReason> {
+        let start = (suggestion.start_line as usize).saturating_sub(1);
+        let len = (suggestion.end_line - suggestion.start_line + 1) as usize;
+
+        let expected = match &suggestion.original_lines {
+            Some(expected) => expected,
+            None if start + len <= self.lines.len() => return Ok((start, 0)),
+            None => {
+                return Err(ConflictReason::OutOfRange {
+                    file_lines: self.lines.len(),
+                })
+            }
+        };
+
+        if self.lines.get(start..start + len) == Some(expected.as_slice()) {
+            return Ok((start, 0));
+        }
+
+        // The file drifted; accept the content only if it now lives at exactly one place
+        let candidates: Vec<usize> = (0..=self.lines.len().saturating_sub(len))
+            .filter(|&i| self.lines.get(i..i + len) == Some(expected.as_slice()))
+            .collect();
+
+        match candidates.as_slice() {
+            [found] => Ok((*found, *found as i64 - start as i64)),
+            _ if start + len > self.lines.len() => Err(ConflictReason::OutOfRange {
+                file_lines: self.lines.len(),
+            }),
+            _ => Err(ConflictReason::ContentMismatch {
+                found: self.lines[start..start + len].to_vec(),
+            }),
+        }
+    }
+
+    fn apply_edits(&self) -> Vec<String> {
+        let mut result = Vec::with_capacity(self.lines.len());
+        let mut cursor = 0;
+
+        for edit in &self.edits {
+            result.extend_from_slice(&self.lines[cursor..edit.start]);
+            result.extend(edit.replacement.iter().cloned());
+            cursor = edit.end;
+        }
+        result.extend_from_slice(&self.lines[cursor..]);
+
+        result
+    }
+
+    fn unified_diff(&self, path: &str) -> String {
+        if self.edits.is_empty() {
+            return String::new();
+        }
+
+        // Group edits whose context windows touch into a single hunk
+        let mut groups: Vec<Vec<&Edit>> = Vec::new();
+        for edit in &self.edits {
+            match groups.last_mut() {
+                Some(group)
+                    if edit.start <= group[group.len() - 1].end + 2 * PATCH_CONTEXT_LINES =>
+                {
+                    group.push(edit)
+                }
+                _ => groups.push(vec![edit]),
+            }
+        }
+
+        let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+        let mut line_delta: i64 = 0;
+
+        for group in groups {
+            let hunk_start = group[0].start.saturating_sub(PATCH_CONTEXT_LINES);
+            let hunk_end = (group[group.len() - 1].end + PATCH_CONTEXT_LINES).min(self.lines.len());
+
+            let mut body = String::new();
+            let mut old_count = 0;
+            let mut new_count = 0;
+            let mut cursor = hunk_start;
+
+            for edit in &group {
+                for line in &self.lines[cursor..edit.start] {
+                    body.push_str(&format!(" {}\n", line));
+                    old_count += 1;
+                    new_count += 1;
+                }
+                for line in &self.lines[edit.start..edit.end] {
+                    body.push_str(&format!("-{}\n", line));
+                    old_count += 1;
+                }
+                for line in &edit.replacement {
+                    body.push_str(&format!("+{}\n", line));
+                    new_count += 1;
+                }
+                cursor = edit.end;
+            }
+            for line in &self.lines[cursor..hunk_end] {
+                body.push_str(&format!(" {}\n", line));
+                old_count += 1;
+                new_count += 1;
+            }
+
+            let old_start = if old_count == 0 {
+                hunk_start
+            } else {
+                hunk_start + 1
+            };
+            let new_start = (hunk_start as i64 + line_delta + i64::from(new_count != 0)).max(0);
+            out.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                old_start, old_count, new_start, new_count
+            ));
+            out.push_str(&body);
+            line_delta += new_count as i64 - old_count as i64;
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::review_comment;
+
+    fn comment(
+        id: u64,
+        line: u32,
+        start_line: Option<u32>,
+        body: &str,
+        hunk: &str,
+    ) -> PrReviewComment {
+        PrReviewComment {
+            body: body.to_string(),
+            line: Some(line),
+            original_line: Some(line),
+            start_line,
+            original_start_line: start_line,
+            diff_hunk: hunk.to_string(),
+            start_side: start_line.map(|_| "RIGHT".to_string()),
+            ..review_comment(id)
+        }
+    }
+
+    fn checkout(name: &str, content: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "github-fetch-suggestion-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), content).unwrap();
+        root
+    }
+
+    const HUNK: &str = "@@ -1,3 +1,4 @@\n fn main() {\n-    let x = 1;\n+    let x = 2;\n+    println!(\"{}\", x);";
+
+    #[test]
+    fn test_parse_suggestion_blocks() {
+        let body = "Try this:\n```suggestion\n    let x = 3;\n```\nand\n````suggestion\n````";
+        let blocks = parse_suggestion_blocks(body);
+        assert_eq!(blocks, vec![vec!["    let x = 3;".to_string()], vec![]]);
+        assert!(parse_suggestion_blocks("```rust\nlet x = 1;\n```").is_empty());
+    }
+
+    #[test]
+    fn test_apply_and_patch() {
+        let root = checkout(
+            "apply",
+            "fn main() {\n    let x = 2;\n    println!(\"{}\", x);\n}\n",
+        );
+        let comments = vec![comment(
+            1,
+            3,
+            Some(2),
+            "```suggestion\n    let x = 3;\n    dbg!(x);\n```",
+            HUNK,
+        )];
+
+        let suggestions = extract_suggestions(&comments);
+        assert_eq!(
+            suggestions[0].original_lines.as_deref(),
+            Some(
+                &[
+                    "    let x = 2;".to_string(),
+                    "    println!(\"{}\", x);".to_string()
+                ][..]
+            )
+        );
+
+        let applier = SuggestionApplier::new(&root);
+        let report = applier.check(&suggestions).unwrap();
+        assert!(!report.has_conflicts());
+        assert!(report.patch.contains("@@ -1,4 +1,4 @@"));
+        assert!(report.patch.contains("-    let x = 2;\n"));
+        assert!(report.patch.contains("+    dbg!(x);\n"));
+
+        applier.apply(&suggestions).unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "fn main() {\n    let x = 3;\n    dbg!(x);\n}\n"
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_conflicts() {
+        // A line was added at the top since the review
+        let root = checkout(
+            "drift",
+            "// header\nfn main() {\n    let x = 2;\n    let y = 5;\n}\n",
+        );
+        let hunk = "@@ -1,2 +1,2 @@\n fn main() {\n+    let x = 2;";
+        let comments = vec![
+            comment(1, 2, None, "```suggestion\n    let x = 9;\n```", hunk),
+            comment(2, 2, None, "```suggestion\n    let x = 8;\n```", hunk),
+            comment(
+                3,
+                4,
+                None,
+                "```suggestion\n    let y = 6;\n```",
+                "@@ -1 +1 @@\n+    let y = 4;",
+            ),
+        ];
+
+        let report = SuggestionApplier::new(&root)
+            .check(&extract_suggestions(&comments))
+            .unwrap();
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.applied[0].offset, 1);
+        assert!(report.patch.contains("@@ -1,5 +1,5 @@"));
+        assert!(matches!(
+            report.conflicts[0].reason,
+            ConflictReason::Overlapping { comment_id: 1 }
+        ));
+        assert!(matches!(
+            report.conflicts[1].reason,
+            ConflictReason::ContentMismatch { .. }
+        ));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_unsafe_and_binary_files() {
+        let root = checkout("unsafe", "fn main() {}\n");
+        fs::write(root.join("src/data.bin"), [0xff, 0xfe, b'\n']).unwrap();
+        let suggestion = |path: &str| Suggestion {
+            comment_id: 1,
+            author: "reviewer".to_string(),
+            path: path.to_string(),
+            start_line: 1,
+            end_line: 1,
+            original_lines: None,
+            replacement: vec!["pwned".to_string()],
+        };
+        let outside = root.join("../outside.rs").display().to_string();
+        let suggestions = vec![
+            suggestion("../outside.rs"),
+            suggestion("src/../../outside.rs"),
+            suggestion(&outside),
+            suggestion("src/data.bin"),
+            suggestion("src/lib.rs"),
+        ];
+
+        let report = SuggestionApplier::new(&root).apply(&suggestions).unwrap();
+        let reasons: Vec<&ConflictReason> = report.conflicts.iter().map(|c| &c.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                &ConflictReason::UnsafePath,
+                &ConflictReason::UnsafePath,
+                &ConflictReason::UnsafePath,
+                &ConflictReason::NotText,
+            ]
+        );
+        assert_eq!(report.applied.len(), 1);
+        assert!(!root.join("../outside.rs").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_out_of_checkout() {
+        let root = checkout("symlink", "fn main() {}\n");
+        let outside = root.with_extension("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("target.rs"), "fn main() {}\n").unwrap();
+        std::os::unix::fs::symlink(outside.join("target.rs"), root.join("src/link.rs")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("linked")).unwrap();
+        std::os::unix::fs::symlink("lib.rs", root.join("src/inside.rs")).unwrap();
+        let suggestion = |path: &str| Suggestion {
+            comment_id: 1,
+            author: "reviewer".to_string(),
+            path: path.to_string(),
+            start_line: 1,
+            end_line: 1,
+            original_lines: None,
+            replacement: vec!["pwned".to_string()],
+        };
+
+        let report = SuggestionApplier::new(&root)
+            .apply(&[
+                suggestion("src/link.rs"),
+                suggestion("linked/target.rs"),
+                suggestion("src/inside.rs"),
+            ])
+            .unwrap();
+        let reasons: Vec<&ConflictReason> = report.conflicts.iter().map(|c| &c.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![&ConflictReason::UnsafePath, &ConflictReason::UnsafePath]
+        );
+        // A link that stays inside the checkout is followed
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(
+            fs::read_to_string(outside.join("target.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "pwned\n"
+        );
+        fs::remove_dir_all(root).unwrap();
+        fs::remove_dir_all(outside).unwrap();
+    }
+}
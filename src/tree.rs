@@ -0,0 +1,439 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::types::Repository;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeEntryType {
+    Blob,
+    Tree,
+    /// Submodule
+    Commit,
+}
+
+impl TreeEntryType {
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "blob" => Some(Self::Blob),
+            "tree" => Some(Self::Tree),
+            "commit" => Some(Self::Commit),
+            _ => None,
+        }
+    }
+}
+
+/// A single entry of a git tree, with its path relative to the repository root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeEntry {
+    pub path: String,
+    /// File mode, e.g. `100644`, `100755`, `040000`, `120000` (symlink), `160000` (submodule)
+    pub mode: String,
+    pub entry_type: TreeEntryType,
+    /// Size in bytes; only set for blobs
+    pub size: Option<u64>,
+    pub sha: String,
+}
+
+impl TreeEntry {
+    pub fn is_file(&self) -> bool {
+        self.entry_type == TreeEntryType::Blob
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.entry_type == TreeEntryType::Tree
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode == "120000"
+    }
+
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// A repository's full tree at a ref
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoTree {
+    pub repository: Repository,
+    pub git_ref: String,
+    /// SHA of the root tree
+    pub sha: String,
+    /// All entries, sorted by path
+    pub entries: Vec<TreeEntry>,
+    /// GitHub truncated the listing and some subtrees could not be walked either
+    pub truncated: bool,
+}
+
+impl RepoTree {
+    pub fn files(&self) -> impl Iterator<Item = &TreeEntry> {
+        self.entries.iter().filter(|entry| entry.is_file())
+    }
+
+    pub fn get(&self, path: &str) -> Option<&TreeEntry> {
+        let path = path.trim_matches('/');
+        self.entries
+            .binary_search_by(|entry| entry.path.as_str().cmp(path))
+            .ok()
+            .map(|index| &self.entries[index])
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.get(path).is_some()
+    }
+
+    /// Files matching a glob pattern
+    pub fn glob<'a>(&'a self, glob: &'a Glob) -> impl Iterator<Item = &'a TreeEntry> {
+        self.files().filter(move |entry| glob.is_match(&entry.path))
+    }
+
+    /// Test files that likely cover `path`
+    ///
+    /// A file counts when it lives under a `tests`/`test` directory or is named
+    /// like a test (`*_test.*`, `test_*`, `*.test.*`, `*_spec.*`), and its name
+    /// contains the source file's stem. A Rust `foo/tests.rs` module next to
+    /// `foo.rs` is included as well.
+    pub fn test_files_for(&self, path: &str) -> Vec<&TreeEntry> {
+        let source = Path::new(path);
+        let stem = match source.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) if stem != "mod" && stem != "lib" => stem.to_lowercase(),
+            _ => source
+                .parent()
+                .and_then(|p| p.file_name())
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_lowercase(),
+        };
+        let sibling_module = match path.strip_suffix(".rs") {
+            Some(module) => format!("{}/tests.rs", module),
+            None => String::new(),
+        };
+
+        self.files()
+            .filter(|entry| entry.path != path)
+            .filter(|entry| {
+                if entry.path == sibling_module {
+                    return true;
+                }
+
+                let name = entry.file_name().to_lowercase();
+                let in_test_dir = entry
+                    .path
+                    .split('/')
+                    .rev()
+                    .skip(1)
+                    .any(|segment| segment == "tests" || segment == "test");
+                let test_named = name.starts_with("test_")
+                    || name.contains("_test.")
+                    || name.contains(".test.")
+                    || name.contains("_spec.");
+
+                !stem.is_empty() && (in_test_dir || test_named) && name.contains(&stem)
+            })
+            .collect()
+    }
+}
+
+/// Listing of a tree GitHub truncated, one git trees API call at a time
+///
+/// Each subtree is first listed recursively; one that is truncated again is
+/// listed a level at a time instead, down to the levels that are truncated
+/// themselves.
+#[derive(Debug)]
+pub(crate) struct TreeWalk {
+    /// (path prefix, tree sha, whether to try a recursive listing first)
+    pending: Vec<(String, String, bool)>,
+    current: Option<(String, String, bool)>,
+    entries: Vec<TreeEntry>,
+    truncated: bool,
+}
+
+impl TreeWalk {
+    pub(crate) fn new(root_sha: &str) -> Self {
+        Self {
+            pending: vec![(String::new(), root_sha.to_string(), false)],
+            current: None,
+            entries: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    /// The tree to list next and whether to list it recursively
+    pub(crate) fn next_listing(&mut self) -> Option<(String, bool)> {
+        let next = self.pending.pop()?;
+        let listing = (next.1.clone(), next.2);
+        self.current = Some(next);
+        Some(listing)
+    }
+
+    /// Take the listing of the tree `next_listing` returned last
+    pub(crate) fn add_listing(&mut self, listing: Vec<TreeEntry>, truncated: bool) {
+        let Some((prefix, sha, recursive)) = self.current.take() else {
+            return;
+        };
+
+        if recursive {
+            if truncated {
+                self.pending.push((prefix, sha, false));
+            } else {
+                self.entries
+                    .extend(listing.into_iter().map(|entry| prefixed(&prefix, entry)));
+            }
+            return;
+        }
+
+        self.truncated |= truncated;
+        for entry in listing {
+            let entry = prefixed(&prefix, entry);
+            if entry.is_dir() {
+                self.pending
+                    .push((entry.path.clone(), entry.sha.clone(), true));
+            }
+            self.entries.push(entry);
+        }
+    }
+
+    /// The entries walked, unsorted, and whether some level was still truncated
+    pub(crate) fn finish(self) -> (Vec<TreeEntry>, bool) {
+        (self.entries, self.truncated)
+    }
+}
+
+/// Make a tree entry's path relative to the repository root
+fn prefixed(prefix: &str, mut entry: TreeEntry) -> TreeEntry {
+    if !prefix.is_empty() {
+        entry.path = format!("{}/{}", prefix, entry.path);
+    }
+    entry
+}
+
+/// Shell-style glob for repository paths
+///
+/// Supports `*` and `?` within a path segment, `**` across segments, `[abc]` /
+/// `[!abc]` character classes and `{a,b}` alternatives. A pattern without a `/`
+/// matches the file name in any directory, so `Cargo.toml` finds every manifest.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pattern: String,
+    regex: Regex,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let trimmed = pattern.trim_start_matches('/');
+        let anchored = if trimmed.contains('/') {
+            trimmed.to_string()
+        } else {
+            format!("**/{}", trimmed)
+        };
+
+        let regex = Regex::new(&format!("^{}$", glob_to_regex(&anchored)?))
+            .map_err(|e| anyhow::anyhow!("Invalid glob pattern {}: {}", pattern, e))?;
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.regex.is_match(path.trim_start_matches('/'))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+}
+
+fn glob_to_regex(pattern: &str) -> anyhow::Result<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut regex = String::new();
+    let mut braces = 0usize;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let at_segment_start = i == 0 || chars[i - 1] == '/';
+                if at_segment_start && chars.get(i + 2) == Some(&'/') {
+                    regex.push_str("(?:[^/]*/)*");
+                    i += 3;
+                } else {
+                    regex.push_str(".*");
+                    i += 2;
+                }
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .skip(1)
+                    .position(|&c| c == ']')
+                    .map(|offset| i + 2 + offset)
+                    .ok_or_else(|| anyhow::anyhow!("Unclosed [ in glob pattern {}", pattern))?;
+                let mut class: String = chars[i + 1..end].iter().collect();
+                if let Some(rest) = class.strip_prefix('!') {
+                    class = format!("^{}", rest);
+                }
+                regex.push('[');
+                regex.push_str(&class.replace('\\', "\\\\").replace('[', "\\["));
+                regex.push(']');
+                i = end;
+            }
+            '{' => {
+                braces += 1;
+                regex.push_str("(?:");
+            }
+            ',' if braces > 0 => regex.push('|'),
+            '}' if braces > 0 => {
+                braces -= 1;
+                regex.push(')');
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    if braces > 0 {
+        return Err(anyhow::anyhow!("Unclosed {{ in glob pattern {}", pattern));
+    }
+
+    Ok(regex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            mode: "100644".to_string(),
+            entry_type: TreeEntryType::Blob,
+            size: Some(0),
+            sha: String::new(),
+        }
+    }
+
+    fn dir(path: &str, sha: &str) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            mode: "040000".to_string(),
+            entry_type: TreeEntryType::Tree,
+            size: None,
+            sha: sha.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_tree_walk() {
+        // Listings by (tree sha, recursive): the entries and the truncation flag
+        let listing = |sha: &str, recursive: bool| -> (Vec<TreeEntry>, bool) {
+            match (sha, recursive) {
+                ("root", false) => (
+                    vec![dir("a", "a"), dir("b", "b"), entry("README.md")],
+                    false,
+                ),
+                ("a", true) => (vec![dir("x", "x"), entry("x/lib.rs")], false),
+                ("b", true) => (vec![], true),
+                ("b", false) => (vec![dir("c", "c"), entry("main.rs")], false),
+                ("c", true) => (vec![], true),
+                ("c", false) => (vec![entry("huge.rs")], true),
+                _ => panic!("unexpected listing of {} (recursive: {})", sha, recursive),
+            }
+        };
+
+        let mut walk = TreeWalk::new("root");
+        let mut requests = Vec::new();
+        while let Some((sha, recursive)) = walk.next_listing() {
+            requests.push((sha.clone(), recursive));
+            let (entries, truncated) = listing(&sha, recursive);
+            walk.add_listing(entries, truncated);
+        }
+        let (mut entries, truncated) = walk.finish();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "README.md",
+                "a",
+                "a/x",
+                "a/x/lib.rs",
+                "b",
+                "b/c",
+                "b/c/huge.rs",
+                "b/main.rs"
+            ]
+        );
+        assert!(truncated);
+        assert_eq!(requests.len(), 6);
+        assert_eq!(requests[0], ("root".to_string(), false));
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        let manifests = Glob::new("Cargo.toml").unwrap();
+        assert!(manifests.is_match("Cargo.toml"));
+        assert!(manifests.is_match("crates/core/Cargo.toml"));
+        assert!(!manifests.is_match("crates/core/Cargo.toml.orig"));
+
+        let sources = Glob::new("src/**/*.rs").unwrap();
+        assert!(sources.is_match("src/lib.rs"));
+        assert!(sources.is_match("src/a/b/c.rs"));
+        assert!(!sources.is_match("tests/lib.rs"));
+
+        let alternatives = Glob::new("{src,tests}/[!_]?*.{rs,toml}").unwrap();
+        assert!(alternatives.is_match("tests/api.rs"));
+        assert!(!alternatives.is_match("tests/_private.rs"));
+        assert!(!alternatives.is_match("benches/api.rs"));
+
+        assert!(Glob::new("src/[abc").is_err());
+        assert!(Glob::new("src/{a,b").is_err());
+    }
+
+    #[test]
+    fn test_test_files_for() {
+        let mut entries: Vec<TreeEntry> = [
+            "src/parser.rs",
+            "src/parser/tests.rs",
+            "src/lexer.rs",
+            "tests/parser_roundtrip.rs",
+            "tests/lexer.rs",
+            "web/parser.test.ts",
+        ]
+        .iter()
+        .map(|path| entry(path))
+        .collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let tree = RepoTree {
+            repository: Repository::new("owner", "repo"),
+            git_ref: "main".to_string(),
+            sha: String::new(),
+            entries,
+            truncated: false,
+        };
+
+        let tests: Vec<&str> = tree
+            .test_files_for("src/parser.rs")
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect();
+        assert_eq!(
+            tests,
+            vec![
+                "src/parser/tests.rs",
+                "tests/parser_roundtrip.rs",
+                "web/parser.test.ts"
+            ]
+        );
+        assert!(tree.contains("tests/lexer.rs"));
+        assert!(!tree.contains("tests"));
+    }
+}
@@ -1,5 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubIssue {
@@ -18,6 +20,49 @@ pub struct GitHubIssue {
     pub html_url: String,
     pub is_pull_request: bool,
     pub comments: u32,
+    /// Title of the milestone the issue belongs to
+    #[serde(default)]
+    pub milestone: Option<String>,
+    /// The author's relationship to the repository, e.g. `MEMBER` or `FIRST_TIME_CONTRIBUTOR`
+    #[serde(default)]
+    pub author_association: String,
+    /// Total reactions, or `None` when the endpoint does not report them
+    #[serde(default)]
+    pub reactions: Option<u32>,
+}
+
+impl GitHubIssue {
+    /// The timestamp `field` refers to; `None` when the issue was never closed or merged
+    pub fn date(&self, field: DateField) -> Option<DateTime<Utc>> {
+        match field {
+            DateField::Created => Some(self.created_at),
+            DateField::Updated => Some(self.updated_at),
+            DateField::Closed => self.closed_at,
+            DateField::Merged => self.merged_at,
+        }
+    }
+}
+
+/// Which timestamp of an issue a date range or sort applies to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum DateField {
+    #[default]
+    Created,
+    Updated,
+    Closed,
+    Merged,
+}
+
+impl DateField {
+    /// Name used in search qualifiers and filter queries
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Updated => "updated",
+            Self::Closed => "closed",
+            Self::Merged => "merged",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +78,9 @@ pub struct GitHubUser {
     pub id: u64,
     pub login: String,
     pub avatar_url: String,
+    /// Account type as GitHub reports it: `User`, `Bot` or `Organization`; empty when unknown
+    #[serde(default)]
+    pub user_type: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,13 +168,242 @@ pub struct PrFile {
     pub patch: Option<String>,
 }
 
+/// Base and head branches of a PR and the commits they point at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrRefs {
+    pub base_ref: String,
+    pub base_sha: String,
+    pub head_ref: String,
+    pub head_sha: String,
+}
+
+/// Mergeability information for a PR
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrMergeStatus {
+    pub state: String,
+    pub draft: bool,
+    pub merged: bool,
+    /// None while GitHub is still computing mergeability
+    pub mergeable: Option<bool>,
+    /// clean, dirty, blocked, behind, unstable, has_hooks, draft or unknown
+    pub mergeable_state: String,
+    pub head_sha: String,
+    pub requested_reviewers: Vec<String>,
+    pub requested_teams: Vec<String>,
+}
+
+/// One CI signal for a commit: a commit status context or a check run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CiCheck {
+    pub name: String,
+    /// success, pending or failure
+    pub state: String,
+    pub url: Option<String>,
+}
+
+/// Combined commit statuses and check runs for a commit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CiStatus {
+    pub sha: String,
+    /// success, pending, failure, or none when no CI reported
+    pub state: String,
+    pub checks: Vec<CiCheck>,
+}
+
+impl CiStatus {
+    pub fn failing(&self) -> impl Iterator<Item = &CiCheck> {
+        self.checks.iter().filter(|check| check.state == "failure")
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &CiCheck> {
+        self.checks.iter().filter(|check| check.state == "pending")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitSummary {
+    pub sha: String,
+    pub message: String,
+    pub author: Option<String>,
+    /// Author date
+    pub date: Option<DateTime<Utc>>,
+    pub committed_at: Option<DateTime<Utc>>,
+    pub html_url: String,
+    /// Parent commit SHAs, first parent first
+    #[serde(default)]
+    pub parents: Vec<String>,
+}
+
+/// A published or draft release
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubRelease {
+    pub id: u64,
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+    /// Branch or commit the tag is created from, if it does not exist yet
+    pub target_commitish: String,
+    pub author: Option<GitHubUser>,
+    pub created_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub html_url: String,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseAsset {
+    pub id: u64,
+    pub name: String,
+    pub content_type: String,
+    pub size: u64,
+    pub download_count: u64,
+    pub browser_download_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubTag {
+    pub name: String,
+    pub commit_sha: String,
+}
+
+/// Result of comparing two commits with the compare API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comparison {
+    pub base: String,
+    pub head: String,
+    /// ahead, behind, diverged or identical
+    pub status: String,
+    pub ahead_by: u32,
+    pub behind_by: u32,
+    pub total_commits: u32,
+    pub merge_base_sha: Option<String>,
+    pub commits: Vec<CommitSummary>,
+    pub files: Vec<PrFile>,
+}
+
+impl Comparison {
+    pub fn total_additions(&self) -> u32 {
+        self.files.iter().map(|f| f.additions).sum()
+    }
+
+    pub fn total_deletions(&self) -> u32 {
+        self.files.iter().map(|f| f.deletions).sum()
+    }
+
+    /// GitHub lists at most 300 files per comparison
+    pub fn files_truncated(&self) -> bool {
+        self.files.len() >= 300
+    }
+}
+
+/// A file's contents at a given ref
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileContent {
+    pub path: String,
+    pub git_ref: String,
+    /// Blob SHA
+    pub sha: String,
+    pub size: u64,
+    pub bytes: Vec<u8>,
+}
+
+impl FileContent {
+    /// Whether the file looks binary: a NUL byte near the start or invalid UTF-8
+    pub fn is_binary(&self) -> bool {
+        self.bytes.iter().take(8000).any(|&b| b == 0) || std::str::from_utf8(&self.bytes).is_err()
+    }
+
+    /// File contents as text, or None for binary files
+    pub fn text(&self) -> Option<&str> {
+        if self.is_binary() {
+            return None;
+        }
+        std::str::from_utf8(&self.bytes).ok()
+    }
+}
+
+/// The file version and lines a review comment's line numbers point at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentAnchor {
+    /// Commit the line numbers were recorded against
+    pub commit_id: Option<String>,
+    /// The lines are on the LEFT side, numbered in the PR's base: the merge
+    /// base of the base branch and `commit_id`
+    pub on_base: bool,
+    pub lines: RangeInclusive<u32>,
+}
+
+impl CommentAnchor {
+    /// Commit to read the lines at, given the merge base of the PR's base
+    /// branch and `commit_id`
+    pub fn git_ref(&self, merge_base: Option<&str>) -> Option<String> {
+        if self.on_base {
+            merge_base.map(str::to_string)
+        } else {
+            self.commit_id.clone()
+        }
+    }
+}
+
+/// Lines of code around a review comment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeContext {
+    pub path: String,
+    pub commit_id: Option<String>,
+    /// Line number of the first entry in `lines`
+    pub first_line: u32,
+    pub lines: Vec<String>,
+    /// Commented line range within the file
+    pub comment_start: u32,
+    pub comment_end: u32,
+}
+
+impl CodeContext {
+    /// Lines with their 1-based numbers, marking the commented ones
+    pub fn numbered_lines(&self) -> impl Iterator<Item = (u32, bool, &str)> {
+        self.lines.iter().enumerate().map(move |(i, line)| {
+            let number = self.first_line + i as u32;
+            let commented = number >= self.comment_start && number <= self.comment_end;
+            (number, commented, line.as_str())
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectionResult {
     pub repository: Repository,
     pub issues: Vec<GitHubIssue>,
     pub total_collected: usize,
     pub collection_time: DateTime<Utc>,
+    /// Query terms of the filters; `IssueFilters::parse` on the joined terms replays the collection
     pub filters_applied: Vec<String>,
+    /// Filters GitHub applied before download; every filter is also re-checked locally
+    pub server_side_filters: Vec<String>,
+    pub stats: CollectionStats,
+}
+
+/// Where the items of a collection went
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollectionStats {
+    pub pages_scanned: usize,
+    /// Issues returned by GitHub before local filtering
+    pub items_seen: usize,
+    /// Requests made by the client while collecting, including per-PR lookups
+    pub api_calls: u64,
+    /// Rejected issues per rule, keyed by the rule's description
+    pub rejections: BTreeMap<String, usize>,
+}
+
+impl CollectionStats {
+    pub fn record_rejection(&mut self, rule: String) {
+        *self.rejections.entry(rule).or_insert(0) += 1;
+    }
+
+    pub fn rejected(&self) -> usize {
+        self.rejections.values().sum()
+    }
 }
 
 /// PR Review information
@@ -155,11 +432,25 @@ pub struct PrReviewComment {
     pub line: Option<u32>,
     /// Original line number (for multi-line comments)
     pub original_line: Option<u32>,
+    /// First line of a multi-line comment
+    pub start_line: Option<u32>,
+    /// First line of a multi-line comment on the original commit
+    pub original_start_line: Option<u32>,
     /// Diff hunk context
     pub diff_hunk: String,
     /// Side of the diff: LEFT or RIGHT
     pub side: Option<String>,
+    /// Side of the diff the first line of a multi-line comment is on
+    pub start_side: Option<String>,
+    /// What the comment is attached to: line or file
+    pub subject_type: Option<String>,
+    /// Author's relationship to the repository: OWNER, MEMBER, CONTRIBUTOR, NONE, ...
+    #[serde(default)]
+    pub author_association: String,
     pub commit_id: Option<String>,
+    /// Commit `original_line` and `original_start_line` refer to
+    #[serde(default)]
+    pub original_commit_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub html_url: String,
@@ -168,3 +459,258 @@ pub struct PrReviewComment {
     /// In reply to another comment
     pub in_reply_to_id: Option<u64>,
 }
+
+impl PrReviewComment {
+    /// Whether the comment is attached to the whole file rather than to lines
+    pub fn is_file_level(&self) -> bool {
+        self.subject_type
+            .as_deref()
+            .is_some_and(|t| t.eq_ignore_ascii_case("file"))
+    }
+
+    /// Whether GitHub no longer maps the comment onto the current diff
+    pub fn is_outdated(&self) -> bool {
+        !self.is_file_level() && self.line.is_none()
+    }
+
+    /// Whether the comment is on the LEFT side, numbered in the old file
+    pub fn is_left_side(&self) -> bool {
+        !is_right_side(self.side.as_deref())
+    }
+
+    /// Where the commented lines are: the current range at `commit_id`, or
+    /// for outdated comments the original range at `original_commit_id`
+    ///
+    /// Returns None for file-level comments and comments without a line.
+    pub fn anchor(&self) -> Option<CommentAnchor> {
+        if self.is_file_level() {
+            return None;
+        }
+
+        let on_base = self.is_left_side();
+        let same_side = |start_side: Option<&str>| {
+            start_side.map_or(true, |start_side| {
+                is_right_side(Some(start_side)) != on_base
+            })
+        };
+        let (commit_id, start, end) = match self.line {
+            Some(end) => (
+                self.commit_id.clone(),
+                self.start_line
+                    .filter(|start| *start <= end && same_side(self.start_side.as_deref())),
+                end,
+            ),
+            None => {
+                let end = self.original_line?;
+                (
+                    self.original_commit_id.clone(),
+                    self.original_start_line
+                        .filter(|start| *start <= end && same_side(self.start_side.as_deref())),
+                    end,
+                )
+            }
+        };
+
+        Some(CommentAnchor {
+            commit_id,
+            on_base,
+            lines: start.unwrap_or(end)..=end,
+        })
+    }
+
+    /// Take `context_lines` lines on each side of the commented range from the file
+    ///
+    /// `file` must be the version `anchor` points at. Returns None for
+    /// file-level comments, binary files, or when the range lies outside the
+    /// file.
+    pub fn surrounding_lines(&self, file: &FileContent, context_lines: u32) -> Option<CodeContext> {
+        let anchor = self.anchor()?;
+        let (start, end) = (*anchor.lines.start(), *anchor.lines.end());
+
+        let lines: Vec<&str> = file.text()?.lines().collect();
+        if start == 0 || end as usize > lines.len() {
+            return None;
+        }
+
+        let first_line = start.saturating_sub(context_lines).max(1);
+        let last_line = (end + context_lines).min(lines.len() as u32);
+
+        Some(CodeContext {
+            path: self.path.clone(),
+            commit_id: Some(file.git_ref.clone()),
+            first_line,
+            lines: lines[first_line as usize - 1..last_line as usize]
+                .iter()
+                .map(|line| line.to_string())
+                .collect(),
+            comment_start: start,
+            comment_end: end,
+        })
+    }
+
+    /// Whether the comment spans more than one line
+    pub fn is_multi_line(&self) -> bool {
+        matches!((self.start_line, self.line), (Some(start), Some(end)) if start != end)
+    }
+
+    /// Commented line range on the new (RIGHT) side of the diff.
+    ///
+    /// Returns `None` for file-level comments, outdated comments without a
+    /// current `line`, and comments anchored on the LEFT side. When a
+    /// multi-line comment starts on the LEFT side, only the RIGHT-side end
+    /// line is returned.
+    pub fn new_side_range(&self) -> Option<RangeInclusive<u32>> {
+        if self.is_file_level() || !is_right_side(self.side.as_deref()) {
+            return None;
+        }
+
+        let end = self.line?;
+        let start = match self.start_line {
+            Some(start) if is_right_side(self.start_side.as_deref()) && start <= end => start,
+            _ => end,
+        };
+
+        Some(start..=end)
+    }
+}
+
+fn is_right_side(side: Option<&str>) -> bool {
+    side.map_or(true, |s| s.eq_ignore_ascii_case("RIGHT"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::review_comment;
+
+    #[test]
+    fn test_new_side_range() {
+        let single = review_comment(1);
+        assert_eq!(single.new_side_range(), Some(12..=12));
+        assert!(!single.is_multi_line());
+
+        let multi = PrReviewComment {
+            start_line: Some(8),
+            start_side: Some("RIGHT".to_string()),
+            ..review_comment(1)
+        };
+        assert_eq!(multi.new_side_range(), Some(8..=12));
+        assert!(multi.is_multi_line());
+
+        let left = PrReviewComment {
+            side: Some("LEFT".to_string()),
+            ..review_comment(1)
+        };
+        assert_eq!(left.new_side_range(), None);
+
+        let file_level = PrReviewComment {
+            line: None,
+            subject_type: Some("file".to_string()),
+            ..review_comment(1)
+        };
+        assert!(file_level.is_file_level());
+        assert_eq!(file_level.new_side_range(), None);
+    }
+
+    #[test]
+    fn test_surrounding_lines() {
+        let file = FileContent {
+            path: "src/lib.rs".to_string(),
+            git_ref: "abc".to_string(),
+            sha: String::new(),
+            size: 0,
+            bytes: (1..=20)
+                .map(|i| format!("line {}", i))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .into_bytes(),
+        };
+        let comment = PrReviewComment {
+            start_line: Some(10),
+            start_side: Some("RIGHT".to_string()),
+            ..review_comment(1)
+        };
+
+        let context = comment.surrounding_lines(&file, 3).unwrap();
+        assert_eq!(context.first_line, 7);
+        assert_eq!(context.lines.first().unwrap(), "line 7");
+        assert_eq!(context.lines.last().unwrap(), "line 15");
+        let commented: Vec<u32> = context
+            .numbered_lines()
+            .filter(|(_, commented, _)| *commented)
+            .map(|(number, _, _)| number)
+            .collect();
+        assert_eq!(commented, vec![10, 11, 12]);
+
+        let binary = FileContent {
+            bytes: vec![0, 159, 146, 150],
+            ..file
+        };
+        assert!(binary.is_binary());
+        assert!(comment.surrounding_lines(&binary, 3).is_none());
+    }
+
+    #[test]
+    fn test_outdated_comment_anchor() {
+        let outdated = PrReviewComment {
+            line: None,
+            original_line: Some(30),
+            start_line: None,
+            original_start_line: Some(28),
+            start_side: Some("RIGHT".to_string()),
+            commit_id: Some("head".to_string()),
+            original_commit_id: Some("original".to_string()),
+            ..review_comment(1)
+        };
+        assert_eq!(
+            outdated.anchor(),
+            Some(CommentAnchor {
+                commit_id: Some("original".to_string()),
+                on_base: false,
+                lines: 28..=30,
+            })
+        );
+        assert_eq!(
+            outdated.anchor().unwrap().git_ref(Some("base")).as_deref(),
+            Some("original")
+        );
+    }
+
+    #[test]
+    fn test_left_side_comment_anchor() {
+        let left = PrReviewComment {
+            side: Some("LEFT".to_string()),
+            line: Some(5),
+            start_line: Some(4),
+            start_side: Some("LEFT".to_string()),
+            commit_id: Some("head".to_string()),
+            ..review_comment(1)
+        };
+        assert_eq!(
+            left.anchor(),
+            Some(CommentAnchor {
+                commit_id: Some("head".to_string()),
+                on_base: true,
+                lines: 4..=5,
+            })
+        );
+
+        // In a PR of two commits, base -> first -> head, LEFT lines are read
+        // at the merge base rather than at the head's parent
+        let anchor = left.anchor().unwrap();
+        assert_eq!(anchor.git_ref(Some("base")).as_deref(), Some("base"));
+        assert_eq!(anchor.git_ref(None), None);
+
+        // The old file's lines 4 and 5, not the head file's
+        let base_file = FileContent {
+            path: "src/lib.rs".to_string(),
+            git_ref: "base".to_string(),
+            sha: String::new(),
+            size: 0,
+            bytes: b"a\nb\nc\nold 4\nold 5\nf".to_vec(),
+        };
+        let context = left.surrounding_lines(&base_file, 0).unwrap();
+        assert_eq!(context.lines, vec!["old 4", "old 5"]);
+        assert_eq!(context.commit_id.as_deref(), Some("base"));
+    }
+}
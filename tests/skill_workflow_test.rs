@@ -72,6 +72,29 @@ async fn test_pr_review_workflow() {
     println!("\nPR review workflow completed successfully!");
 }
 
+/// Test fetching the whole PR review workflow in one bundle
+#[tokio::test]
+#[ignore] // Run with: cargo test --ignored -- --nocapture
+async fn test_pr_bundle_workflow() {
+    if std::env::var("GITHUB_TOKEN").is_err() {
+        eprintln!("Skipping: GITHUB_TOKEN not set");
+        return;
+    }
+
+    let fetcher = GitHubFetcher::new(None).expect("Failed to create fetcher");
+    let repo = Repository::new("tokio-rs", "axum");
+
+    let bundle = fetcher.fetch_pr_bundle(&repo, 2865).await;
+    assert!(bundle.is_ok(), "Should fetch PR bundle");
+    let bundle = bundle.unwrap();
+
+    assert!(bundle.is_complete(), "Bundle errors: {:?}", bundle.errors);
+    assert!(bundle.head_sha.is_some());
+    assert!(bundle.pr.is_some());
+    assert!(bundle.files.is_some());
+    println!("  Head: {:?} (consistent: {})", bundle.head_sha, bundle.consistent);
+}
+
 /// Test error handling for non-existent PR
 #[tokio::test]
 #[ignore]